@@ -0,0 +1,39 @@
+-- Revoked access tokens, keyed by JWT ID (jti)
+-- Rows can be purged once the token itself has expired.
+CREATE TABLE IF NOT EXISTS revoked_tokens (
+    jti UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    expires_at TIMESTAMPTZ NOT NULL,
+    revoked_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_revoked_tokens_expires_at ON revoked_tokens(expires_at);
+
+-- Per-user cutoff: access tokens issued before revoked_before are rejected
+CREATE TABLE IF NOT EXISTS user_token_revocations (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    revoked_before TIMESTAMPTZ NOT NULL
+);
+
+-- Revoke all sessions when a user's password changes or the account is deactivated
+CREATE OR REPLACE FUNCTION revoke_user_sessions() RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO user_token_revocations (user_id, revoked_before)
+    VALUES (NEW.id, NOW())
+    ON CONFLICT (user_id) DO UPDATE SET revoked_before = EXCLUDED.revoked_before;
+
+    UPDATE refresh_tokens
+    SET revoked_at = NOW()
+    WHERE user_id = NEW.id AND revoked_at IS NULL;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS users_revoke_sessions ON users;
+CREATE TRIGGER users_revoke_sessions
+    AFTER UPDATE OF password_hash, is_active ON users
+    FOR EACH ROW
+    WHEN (NEW.password_hash IS DISTINCT FROM OLD.password_hash
+          OR (OLD.is_active AND NOT NEW.is_active))
+    EXECUTE FUNCTION revoke_user_sessions();
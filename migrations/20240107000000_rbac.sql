@@ -0,0 +1,49 @@
+-- Roles and permissions
+CREATE TABLE IF NOT EXISTS roles (
+    name VARCHAR(50) PRIMARY KEY,
+    description VARCHAR(255) NOT NULL DEFAULT '',
+    -- Permissions from this role are only granted to users with two-factor authentication enabled
+    requires_mfa BOOLEAN NOT NULL DEFAULT false,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS permissions (
+    name VARCHAR(100) PRIMARY KEY,
+    description VARCHAR(255) NOT NULL DEFAULT ''
+);
+
+CREATE TABLE IF NOT EXISTS role_permissions (
+    role_name VARCHAR(50) NOT NULL REFERENCES roles(name) ON DELETE CASCADE,
+    permission_name VARCHAR(100) NOT NULL REFERENCES permissions(name) ON DELETE CASCADE,
+    PRIMARY KEY (role_name, permission_name)
+);
+
+CREATE TABLE IF NOT EXISTS user_roles (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    role_name VARCHAR(50) NOT NULL REFERENCES roles(name) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, role_name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_roles_role_name ON user_roles(role_name);
+
+-- Seed default roles and permissions
+INSERT INTO permissions (name, description) VALUES
+    ('users:read', 'View any user profile'),
+    ('users:write', 'Update any user'),
+    ('users:delete', 'Delete any user')
+ON CONFLICT (name) DO NOTHING;
+
+INSERT INTO roles (name, description, requires_mfa) VALUES
+    ('user', 'Default role for registered users', false),
+    ('admin', 'Full administrative access', true)
+ON CONFLICT (name) DO NOTHING;
+
+INSERT INTO role_permissions (role_name, permission_name)
+SELECT 'admin', name FROM permissions
+ON CONFLICT DO NOTHING;
+
+-- Existing users get the default role
+INSERT INTO user_roles (user_id, role_name)
+SELECT id, 'user' FROM users
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,9 @@
+-- Create account lockouts table
+-- Tracks consecutive failed logins per account. Rows are deleted on successful
+-- login, password reset or admin unlock.
+CREATE TABLE IF NOT EXISTS account_lockouts (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    failed_attempts INTEGER NOT NULL DEFAULT 0,
+    last_failed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    locked_until TIMESTAMPTZ
+);
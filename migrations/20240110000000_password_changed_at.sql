@@ -0,0 +1,3 @@
+-- Track when each user's password last changed
+-- Access tokens issued before this moment are rejected.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS password_changed_at TIMESTAMPTZ;
@@ -0,0 +1,6 @@
+-- Indexes for paginated user listing
+-- Cursor pagination seeks on (sort column, id); the email column is already
+-- covered by its unique index.
+CREATE INDEX IF NOT EXISTS idx_users_created_at_id ON users(created_at, id);
+CREATE INDEX IF NOT EXISTS idx_users_name_id ON users(name, id);
+CREATE INDEX IF NOT EXISTS idx_users_email_domain ON users(lower(split_part(email, '@', 2)));
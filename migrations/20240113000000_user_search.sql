@@ -0,0 +1,24 @@
+-- User search
+-- Full-text search finds whole words and word prefixes; trigrams rank fuzzy
+-- matches and speed up substring matches. Scripts without spaces between
+-- words, such as Thai, rely on the substring match: full-text search sees a
+-- Thai name as a single word, and whether trigrams cover Thai letters depends
+-- on the database locale.
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+-- Name and email normalized like the search query: lowercase, single spaces
+-- and Thai SARA AM in its composed form (see common::search::normalize)
+ALTER TABLE users ADD COLUMN IF NOT EXISTS search_text TEXT GENERATED ALWAYS AS (
+    regexp_replace(
+        regexp_replace(lower(name || ' ' || email), '\s+', ' ', 'g'),
+        'ํ(่|้|๊|๋)?า', '\1ำ', 'g'
+    )
+) STORED;
+
+-- Email split into words so `john` and `example` both match john@example.com
+ALTER TABLE users ADD COLUMN IF NOT EXISTS search_vector TSVECTOR GENERATED ALWAYS AS (
+    to_tsvector('simple', name || ' ' || translate(email, '@.', '  '))
+) STORED;
+
+CREATE INDEX IF NOT EXISTS idx_users_search_vector ON users USING GIN (search_vector);
+CREATE INDEX IF NOT EXISTS idx_users_search_text_trgm ON users USING GIN (search_text gin_trgm_ops);
@@ -0,0 +1,39 @@
+-- Emails identify accounts ignoring case. New addresses are normalized by the
+-- application (trimmed, Unicode NFC, lowercase domain); bring existing rows in
+-- line where that doesn't make two accounts share an address. Unicode
+-- normalization is left to the application, as it needs a UTF-8 database.
+UPDATE users AS u
+SET email = n.email, updated_at = NOW()
+FROM (
+    SELECT id,
+           regexp_replace(btrim(email), '@[^@]*$', '')
+               || lower(substring(btrim(email) FROM '@[^@]*$')) AS email
+    FROM users
+    WHERE email LIKE '%@%'
+) AS n
+WHERE u.id = n.id
+  AND u.email <> n.email
+  AND NOT EXISTS (
+      SELECT 1 FROM users AS other
+      WHERE other.id <> u.id AND lower(btrim(other.email)) = lower(n.email)
+  );
+
+-- Accounts whose emails differ only in case must be merged or renamed by hand
+-- before uniqueness can be enforced
+DO $$
+DECLARE
+    duplicates BIGINT;
+BEGIN
+    SELECT COUNT(*) INTO duplicates
+    FROM (SELECT 1 FROM users GROUP BY lower(email) HAVING COUNT(*) > 1) AS shared;
+
+    IF duplicates > 0 THEN
+        RAISE EXCEPTION '% email addresses belong to more than one account when compared ignoring case', duplicates
+            USING HINT = 'Run `axum-api-template report-duplicate-emails` to list them, merge or rename the accounts, then restart.';
+    END IF;
+END $$;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_users_email_lower ON users(lower(email));
+
+-- Case-insensitive uniqueness covers the exact-match constraint
+ALTER TABLE users DROP CONSTRAINT IF EXISTS users_email_key;
@@ -0,0 +1,9 @@
+-- Accounts deleted by their owner are kept for a grace period, during which
+-- an administrator can restore them, then purged. Accounts deactivated before
+-- this migration have no deletion time and are left alone.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+-- Set when a deleted account was anonymized rather than removed
+ALTER TABLE users ADD COLUMN IF NOT EXISTS purged_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_users_pending_purge ON users(deleted_at)
+    WHERE deleted_at IS NOT NULL AND purged_at IS NULL;
@@ -0,0 +1,110 @@
+-- Row-level security for organization data
+-- With TENANT_ISOLATION=row_level_security, tenant-scoped repositories run
+-- their queries in a transaction that switches to the app_tenant role and sets
+-- app.current_org_id and app.current_user_id. The policies below then hide
+-- rows of other organizations even from a query that forgets its org_id
+-- filter. Every other role, whether or not it owns the tables, keeps
+-- unrestricted access, so sign-up, login and other queries outside an
+-- organization are unaffected whatever TENANT_ISOLATION is set to.
+--
+-- Policies for a role also apply to its members, and the application role is
+-- a member of app_tenant, so the restrictive policies only take effect once
+-- the current role actually is app_tenant.
+
+-- Roles are shared by every database of the server; creating one needs the
+-- CREATEROLE privilege, so without it the role must be created by hand
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_roles WHERE rolname = 'app_tenant') THEN
+        CREATE ROLE app_tenant NOLOGIN;
+    END IF;
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+    WHEN insufficient_privilege THEN
+        RAISE WARNING 'Cannot create role app_tenant; row-level security is unavailable'
+            USING HINT = 'Run `CREATE ROLE app_tenant NOLOGIN; GRANT app_tenant TO <application role>;` as a superuser, then rerun the migrations.';
+END $$;
+
+-- The organization and user of the current tenant transaction, or NULL
+-- outside of one
+CREATE OR REPLACE FUNCTION app_current_org_id() RETURNS UUID
+LANGUAGE sql STABLE AS $$
+    SELECT NULLIF(current_setting('app.current_org_id', true), '')::uuid
+$$;
+
+CREATE OR REPLACE FUNCTION app_current_user_id() RETURNS UUID
+LANGUAGE sql STABLE AS $$
+    SELECT NULLIF(current_setting('app.current_user_id', true), '')::uuid
+$$;
+
+ALTER TABLE organizations ENABLE ROW LEVEL SECURITY;
+ALTER TABLE organization_memberships ENABLE ROW LEVEL SECURITY;
+ALTER TABLE organization_invitations ENABLE ROW LEVEL SECURITY;
+ALTER TABLE users ENABLE ROW LEVEL SECURITY;
+
+DROP POLICY IF EXISTS application_access ON organizations;
+CREATE POLICY application_access ON organizations USING (true) WITH CHECK (true);
+DROP POLICY IF EXISTS application_access ON organization_memberships;
+CREATE POLICY application_access ON organization_memberships USING (true) WITH CHECK (true);
+DROP POLICY IF EXISTS application_access ON organization_invitations;
+CREATE POLICY application_access ON organization_invitations USING (true) WITH CHECK (true);
+DROP POLICY IF EXISTS application_access ON users;
+CREATE POLICY application_access ON users USING (true) WITH CHECK (true);
+
+DROP POLICY IF EXISTS tenant_isolation ON organizations;
+DROP POLICY IF EXISTS tenant_isolation ON organization_memberships;
+DROP POLICY IF EXISTS tenant_isolation ON organization_invitations;
+DROP POLICY IF EXISTS tenant_isolation ON users;
+
+-- Without the role there is nothing to restrict
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_roles WHERE rolname = 'app_tenant') THEN
+        RETURN;
+    END IF;
+
+    CREATE POLICY tenant_isolation ON organizations
+        AS RESTRICTIVE TO app_tenant
+        USING (current_user <> 'app_tenant' OR id = app_current_org_id());
+
+    CREATE POLICY tenant_isolation ON organization_memberships
+        AS RESTRICTIVE TO app_tenant
+        USING (current_user <> 'app_tenant' OR org_id = app_current_org_id())
+        WITH CHECK (current_user <> 'app_tenant' OR org_id = app_current_org_id());
+
+    CREATE POLICY tenant_isolation ON organization_invitations
+        AS RESTRICTIVE TO app_tenant
+        USING (current_user <> 'app_tenant' OR org_id = app_current_org_id())
+        WITH CHECK (current_user <> 'app_tenant' OR org_id = app_current_org_id());
+
+    -- Users see themselves and the members of their organization
+    CREATE POLICY tenant_isolation ON users
+        AS RESTRICTIVE FOR SELECT TO app_tenant
+        USING (
+            current_user <> 'app_tenant'
+            OR id = app_current_user_id()
+            OR id IN (
+                SELECT user_id FROM organization_memberships
+                WHERE org_id = app_current_org_id()
+            )
+        );
+END $$;
+
+-- Tables added later must be granted to app_tenant in their own migration
+-- to be reachable from tenant-scoped repositories
+DO $$
+BEGIN
+    IF EXISTS (SELECT 1 FROM pg_roles WHERE rolname = 'app_tenant') THEN
+        GRANT SELECT ON users TO app_tenant;
+        GRANT SELECT, UPDATE ON organizations TO app_tenant;
+        GRANT SELECT, INSERT, UPDATE, DELETE
+            ON organization_memberships, organization_invitations TO app_tenant;
+        IF NOT pg_has_role(current_user, 'app_tenant', 'MEMBER') THEN
+            EXECUTE format('GRANT app_tenant TO %I', current_user);
+        END IF;
+    END IF;
+EXCEPTION
+    WHEN insufficient_privilege THEN
+        RAISE WARNING 'Cannot grant app_tenant to %; row-level security is unavailable', current_user
+            USING HINT = 'Run `GRANT app_tenant TO <application role>;` as a superuser.';
+END $$;
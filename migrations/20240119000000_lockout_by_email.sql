@@ -0,0 +1,21 @@
+-- Track failed logins by email address instead of by account
+-- Addresses without an account are locked and throttled like existing ones,
+-- so login responses do not reveal which addresses are registered. Rows are
+-- keyed by the lowercased address, like email lookups.
+ALTER TABLE account_lockouts ADD COLUMN IF NOT EXISTS email TEXT;
+
+UPDATE account_lockouts l
+SET email = lower(u.email)
+FROM users u
+WHERE u.id = l.user_id;
+
+DELETE FROM account_lockouts WHERE email IS NULL;
+
+ALTER TABLE account_lockouts DROP CONSTRAINT IF EXISTS account_lockouts_pkey;
+ALTER TABLE account_lockouts DROP COLUMN IF EXISTS user_id;
+ALTER TABLE account_lockouts ALTER COLUMN email SET NOT NULL;
+ALTER TABLE account_lockouts ADD PRIMARY KEY (email);
+
+-- Stale rows are purged periodically
+CREATE INDEX IF NOT EXISTS idx_account_lockouts_last_failed_at
+    ON account_lockouts(last_failed_at);
@@ -0,0 +1,32 @@
+-- Revoke sessions when the password is changed rather than whenever its hash
+-- is rewritten: rehashing the same password on login must not log users out.
+-- Every password change sets password_changed_at.
+--
+-- Access tokens issued before password_changed_at are already rejected, and
+-- the application stamps it with the clock it issues tokens with. Stamping a
+-- cutoff with the database clock as well would reject tokens issued right
+-- after the change whenever that clock runs ahead, so only deactivation, which
+-- rejects every token anyway, still records one here.
+CREATE OR REPLACE FUNCTION revoke_user_sessions() RETURNS TRIGGER AS $$
+BEGIN
+    IF OLD.is_active AND NOT NEW.is_active THEN
+        INSERT INTO user_token_revocations (user_id, revoked_before)
+        VALUES (NEW.id, NOW())
+        ON CONFLICT (user_id) DO UPDATE SET revoked_before = EXCLUDED.revoked_before;
+    END IF;
+
+    UPDATE refresh_tokens
+    SET revoked_at = NOW()
+    WHERE user_id = NEW.id AND revoked_at IS NULL;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS users_revoke_sessions ON users;
+CREATE TRIGGER users_revoke_sessions
+    AFTER UPDATE OF password_changed_at, is_active ON users
+    FOR EACH ROW
+    WHEN (NEW.password_changed_at IS DISTINCT FROM OLD.password_changed_at
+          OR (OLD.is_active AND NOT NEW.is_active))
+    EXECUTE FUNCTION revoke_user_sessions();
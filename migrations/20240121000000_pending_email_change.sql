@@ -0,0 +1,3 @@
+-- New email address awaiting verification. The account keeps its current
+-- address until the link sent to the new one is followed.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS pending_email VARCHAR(255);
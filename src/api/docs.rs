@@ -2,45 +2,213 @@
 
 use utoipa::{
     OpenApi,
-    openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+    openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme},
 };
 
 use crate::{
     api::{
-        error::{ApiError, ErrorBody, ErrorResponse},
-        handlers::{auth, health, users},
+        error::{ApiError, ErrorBody, ErrorDetail, ErrorResponse},
+        handlers::{admin, api_keys, auth, exports, health, jwks, mfa, organizations, users},
+        pagination::{PaginatedResponse, PaginationMeta},
     },
-    domain::models::User,
+    common::pagination::SortDirection,
+    domain::models::{ExportFormat, ExportStatus, OrgRole, User, UserSortField},
 };
 
 #[derive(OpenApi)]
 #[openapi(
+    info(
+        description = "Authentication uses a short-lived JWT access token sent as \
+            `Authorization: Bearer <token>` and an opaque refresh token.\n\n\
+            `POST /auth/register` and `POST /auth/login` return both tokens. When the access \
+            token expires, call `POST /auth/refresh` with the refresh token to obtain a new \
+            pair. Refresh tokens are single-use: each refresh returns a new refresh token and \
+            invalidates the old one. Reusing an old refresh token revokes all tokens issued \
+            from the same login, forcing a new login.\n\n\
+            `POST /auth/logout` revokes the current session and `POST /auth/logout-all` \
+            revokes every session of the user. All sessions are also revoked when the \
+            password changes (`PUT /users/me/password` or a reset), when the account is \
+            deleted with `DELETE /users/me`, and when an administrator deactivates it.\n\n\
+            Accounts with two-factor authentication get `mfa_required: true` and an \
+            `mfa_token` from `POST /auth/login` instead of tokens; exchange it together \
+            with a TOTP or recovery code at `POST /auth/mfa/verify`.\n\n\
+            Repeated failed logins first slow the account down with 429 responses and then \
+            lock it with 423 `ACCOUNT_LOCKED`; both carry a `Retry-After` header. A password \
+            reset or `POST /admin/users/{id}/unlock` lifts the lock early.\n\n\
+            Access tokens carry the user's roles and permissions. Endpoints that need a \
+            permission list it as the scope of the `jwt` security requirement and respond \
+            with 403 `INSUFFICIENT_PERMISSIONS` when it is missing. Permissions of the \
+            `admin` role are only granted once two-factor authentication is enabled. \
+            Administrators manage accounts under `/admin/users`; every action there is \
+            recorded in an audit log. Accounts deleted by their owner can be restored at \
+            `POST /admin/users/{id}/restore` until they are purged after a grace period; \
+            their email address can then be registered again.\n\n\
+            Users get a copy of their personal data with `POST /users/me/export`. The \
+            export is assembled in the background; poll `GET /users/me/export/{id}` until \
+            it is `ready` and follow its signed `download_url`, which works without a \
+            session until the export expires.\n\n\
+            Users belong to organizations with a per-organization role (`owner`, `admin` \
+            or `member`) and join them through email invitations. \
+            `POST /orgs/switch` returns tokens whose `org_id` claim selects an \
+            organization; `/orgs/current` endpoints act on it and respond with 403 \
+            `ORGANIZATION_REQUIRED` without one. Members and invitations of other \
+            organizations are always reported as not found (404).\n\n\
+            Access tokens carry a `kid` header. When they are signed with an asymmetric key \
+            (RS256, ES256 or EdDSA), other services can verify them with the public keys \
+            published at `/.well-known/jwks.json`.\n\n\
+            List endpoints return a `pagination` object next to `data`. Pass its \
+            `next_cursor` as `cursor` to get the next page until `has_more` is false; \
+            `offset` paging and a `total` count (`include_total=true`) are also available.\n\n\
+            Validation errors return 400 `VALIDATION_ERROR` with a `details` list naming \
+            each failed field and rule, e.g. `PASSWORD_TOO_SHORT` or `PASSWORD_BREACHED` \
+            when a new password breaks the password policy.\n\n\
+            Rejected access tokens return 401 with a `code` telling why: `INVALID_TOKEN`, \
+            `TOKEN_EXPIRED`, `TOKEN_NOT_YET_VALID`, `INVALID_TOKEN_ISSUER`, \
+            `INVALID_TOKEN_AUDIENCE` or `TOKEN_REVOKED`.\n\n\
+            Machine clients can authenticate with a personal API key in the `X-API-Key` \
+            header instead. Keys are created at `POST /users/me/api-keys`, are limited to the \
+            scopes chosen at creation, and cannot log out or manage two-factor settings or \
+            other API keys."
+    ),
     paths(
         health::health_check,
+        jwks::jwks,
         auth::register,
         auth::login,
+        auth::refresh,
+        auth::logout,
+        auth::logout_all,
+        auth::forgot_password,
+        auth::reset_password,
+        auth::verify_email,
+        auth::resend_verification,
+        mfa::enroll,
+        mfa::confirm,
+        mfa::verify,
+        mfa::disable,
+        users::list_users,
+        users::search_users,
         users::get_current_user,
+        users::update_current_user,
+        users::delete_current_user,
+        users::change_password,
         users::get_user_by_id,
+        admin::list_users,
+        admin::get_user,
+        admin::update_user,
+        admin::deactivate_user,
+        admin::reactivate_user,
+        admin::restore_user,
+        admin::unlock_user,
+        admin::import_users,
+        admin::delete_user,
+        api_keys::create_api_key,
+        api_keys::list_api_keys,
+        api_keys::revoke_api_key,
+        exports::request_export,
+        exports::get_export,
+        exports::download_export,
+        organizations::create_organization,
+        organizations::list_organizations,
+        organizations::switch_organization,
+        organizations::accept_invitation,
+        organizations::get_current_organization,
+        organizations::list_members,
+        organizations::get_member,
+        organizations::update_member,
+        organizations::remove_member,
+        organizations::create_invitation,
+        organizations::list_invitations,
+        organizations::revoke_invitation,
     ),
     components(
         schemas(
             User,
             auth::RegisterRequest,
             auth::LoginRequest,
+            auth::RefreshRequest,
+            auth::ForgotPasswordRequest,
+            auth::ResetPasswordRequest,
+            auth::VerifyEmailRequest,
+            auth::ResendVerificationRequest,
+            auth::MessageResponse,
+            auth::MessageData,
             auth::AuthResponse,
             auth::AuthData,
+            auth::LoginResponse,
+            auth::LoginData,
+            auth::MfaChallengeData,
+            mfa::MfaCodeRequest,
+            mfa::MfaVerifyRequest,
+            mfa::MfaEnrollResponse,
+            mfa::MfaEnrollData,
+            mfa::MfaRecoveryCodesResponse,
+            mfa::MfaRecoveryCodesData,
             users::UserResponse,
+            users::UserSearchResponse,
+            users::UserSearchHitData,
+            users::FieldHighlightData,
+            users::HighlightFragmentData,
+            PaginatedResponse<users::UserData>,
+            PaginationMeta,
+            SortDirection,
+            UserSortField,
             users::UserData,
+            users::UpdateProfileRequest,
+            users::DeleteAccountRequest,
+            users::ChangePasswordRequest,
+            admin::ImportUsersRequest,
+            admin::ImportUserRequest,
+            admin::ImportUsersResponse,
+            admin::ImportUsersData,
+            admin::SkippedUserData,
+            admin::AdminUpdateUserRequest,
+            admin::AdminUserData,
+            admin::AdminUserResponse,
+            admin::AdminUserDetailResponse,
+            admin::AdminUserDetailData,
+            admin::AdminUserListResponse,
+            api_keys::CreateApiKeyRequest,
+            api_keys::ApiKeyData,
+            api_keys::CreatedApiKeyResponse,
+            api_keys::CreatedApiKeyData,
+            api_keys::ApiKeyListResponse,
+            exports::RequestExportRequest,
+            exports::DataExportResponse,
+            exports::DataExportData,
+            ExportFormat,
+            ExportStatus,
+            organizations::CreateOrganizationRequest,
+            organizations::SwitchOrganizationRequest,
+            organizations::UpdateMemberRequest,
+            organizations::InviteMemberRequest,
+            organizations::AcceptInvitationRequest,
+            organizations::OrganizationData,
+            organizations::OrganizationResponse,
+            organizations::OrganizationListResponse,
+            organizations::MemberData,
+            organizations::MemberResponse,
+            organizations::MemberListResponse,
+            organizations::InvitationData,
+            organizations::InvitationResponse,
+            organizations::InvitationListResponse,
+            OrgRole,
             health::HealthResponse,
+            jwks::JwksResponse,
             ApiError,
             ErrorResponse,
             ErrorBody,
+            ErrorDetail,
         )
     ),
     tags(
         (name = "system", description = "System endpoints"),
         (name = "auth", description = "Authentication endpoints"),
         (name = "users", description = "User management endpoints"),
+        (name = "api-keys", description = "Personal API keys for machine clients"),
+        (name = "exports", description = "Copies of everything stored about a user"),
+        (name = "organizations", description = "Organizations, their members and invitations"),
+        (name = "admin", description = "User administration; every action is audit logged"),
     ),
     modifiers(&SecurityAddon),
     security(
@@ -63,6 +231,10 @@ impl utoipa::Modify for SecurityAddon {
                         .build(),
                 ),
             );
+            components.add_security_scheme(
+                "api_key",
+                SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("X-API-Key"))),
+            );
         }
     }
 }
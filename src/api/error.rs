@@ -4,11 +4,14 @@
 
 use axum::{
     Json,
-    http::StatusCode,
+    http::{StatusCode, header::RETRY_AFTER},
     response::{IntoResponse, Response},
 };
 use serde::Serialize;
 use utoipa::ToSchema;
+use validator::ValidationErrorsKind;
+
+use crate::common::jwt::JwtError;
 
 /// Unified API error type
 #[derive(Debug, ToSchema)]
@@ -19,6 +22,11 @@ pub struct ApiError {
     pub message: String,
     #[schema(example = "INTERNAL_ERROR")]
     pub error_code: Option<String>,
+    /// Seconds the client should wait before retrying (sent as `Retry-After`)
+    #[schema(ignore)]
+    pub retry_after: Option<u64>,
+    /// Individual problems, e.g. one per failed validation rule
+    pub details: Vec<ErrorDetail>,
 }
 
 /// JSON response body for errors
@@ -33,6 +41,20 @@ pub struct ErrorBody {
     pub message: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub code: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub details: Vec<ErrorDetail>,
+}
+
+/// One problem with a request, such as a field failing a validation rule
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ErrorDetail {
+    /// Path of the offending field
+    #[schema(example = "password")]
+    pub field: String,
+    #[schema(example = "PASSWORD_TOO_SHORT")]
+    pub code: String,
+    #[schema(example = "Password must be at least 8 characters")]
+    pub message: String,
 }
 
 #[allow(dead_code)]
@@ -42,6 +64,8 @@ impl ApiError {
             status,
             message: message.into(),
             error_code: None,
+            retry_after: None,
+            details: Vec::new(),
         }
     }
 
@@ -50,6 +74,16 @@ impl ApiError {
         self
     }
 
+    pub fn with_retry_after(mut self, seconds: u64) -> Self {
+        self.retry_after = Some(seconds);
+        self
+    }
+
+    pub fn with_details(mut self, details: Vec<ErrorDetail>) -> Self {
+        self.details = details;
+        self
+    }
+
     // Common error constructors
     pub fn bad_request(message: impl Into<String>) -> Self {
         Self::new(StatusCode::BAD_REQUEST, message)
@@ -78,6 +112,14 @@ impl ApiError {
     pub fn unprocessable(message: impl Into<String>) -> Self {
         Self::new(StatusCode::UNPROCESSABLE_ENTITY, message)
     }
+
+    pub fn locked(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::LOCKED, message)
+    }
+
+    pub fn too_many_requests(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::TOO_MANY_REQUESTS, message)
+    }
 }
 
 impl IntoResponse for ApiError {
@@ -87,10 +129,16 @@ impl IntoResponse for ApiError {
             error: ErrorBody {
                 message: self.message,
                 code: self.error_code,
+                details: self.details,
             },
         };
 
-        (self.status, Json(body)).into_response()
+        let mut response = (self.status, Json(body)).into_response();
+        if let Some(seconds) = self.retry_after {
+            response.headers_mut().insert(RETRY_AFTER, seconds.into());
+        }
+
+        response
     }
 }
 
@@ -102,8 +150,69 @@ impl From<sqlx::Error> for ApiError {
     }
 }
 
+impl From<JwtError> for ApiError {
+    fn from(err: JwtError) -> Self {
+        let code = match err {
+            JwtError::TokenCreationFailed => {
+                return ApiError::internal("Failed to create token");
+            }
+            JwtError::InvalidToken => "INVALID_TOKEN",
+            JwtError::TokenExpired => "TOKEN_EXPIRED",
+            JwtError::TokenNotYetValid => "TOKEN_NOT_YET_VALID",
+            JwtError::InvalidIssuer => "INVALID_TOKEN_ISSUER",
+            JwtError::InvalidAudience => "INVALID_TOKEN_AUDIENCE",
+        };
+
+        ApiError::unauthorized(err.to_string()).with_code(code)
+    }
+}
+
 impl From<validator::ValidationErrors> for ApiError {
     fn from(err: validator::ValidationErrors) -> Self {
-        ApiError::bad_request(format!("Validation failed: {}", err)).with_code("VALIDATION_ERROR")
+        let mut details = Vec::new();
+        collect_validation_details(&err, "", &mut details);
+        details.sort_by(|a, b| a.field.cmp(&b.field));
+
+        ApiError::bad_request(format!("Validation failed: {}", err))
+            .with_code("VALIDATION_ERROR")
+            .with_details(details)
+    }
+}
+
+/// Flatten validation errors into details, with paths like `users[0].email`
+fn collect_validation_details(
+    errors: &validator::ValidationErrors,
+    prefix: &str,
+    details: &mut Vec<ErrorDetail>,
+) {
+    for (field, kind) in errors.errors() {
+        let path = if prefix.is_empty() {
+            field.to_string()
+        } else {
+            format!("{prefix}.{field}")
+        };
+
+        match kind {
+            ValidationErrorsKind::Field(errors) => {
+                details.extend(errors.iter().map(|error| {
+                    ErrorDetail {
+                        field: path.clone(),
+                        code: error.code.to_uppercase(),
+                        message: error
+                            .message
+                            .as_ref()
+                            .map_or_else(|| error.to_string(), ToString::to_string),
+                    }
+                }));
+            }
+            ValidationErrorsKind::Struct(nested) => {
+                collect_validation_details(nested, &path, details);
+            }
+            ValidationErrorsKind::List(items) => {
+                for (index, nested) in items {
+                    collect_validation_details(nested, &format!("{path}[{index}]"), details);
+                }
+            }
+        }
     }
 }
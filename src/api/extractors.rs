@@ -0,0 +1,560 @@
+//! Custom extractors
+
+use std::{
+    convert::Infallible,
+    marker::PhantomData,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+};
+
+use axum::{
+    extract::{ConnectInfo, FromRequestParts},
+    http::{HeaderName, header::AUTHORIZATION, request::Parts},
+};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    api::error::ApiError,
+    common::jwt::{Claims, verify_token},
+    config::AppState,
+    domain::{
+        models::Membership,
+        services::{ApiKeyService, OrganizationService, SessionService},
+    },
+};
+
+/// Header carrying a personal API key
+pub const API_KEY_HEADER: HeaderName = HeaderName::from_static("x-api-key");
+
+/// Header listing the client and proxy addresses a request passed through
+const X_FORWARDED_FOR: HeaderName = HeaderName::from_static("x-forwarded-for");
+
+/// How the caller authenticated
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub enum Credential {
+    /// A JWT access token from an interactive login
+    AccessToken(Claims),
+    /// A personal API key
+    ApiKey { key_id: Uuid },
+}
+
+/// The authenticated caller, taken from a valid `Authorization: Bearer` access
+/// token or an `X-API-Key` header.
+///
+/// Rejects the request with 401 when the credential is missing, invalid,
+/// expired or revoked. The result is cached in the request extensions, so
+/// several extractors in one handler only verify the credential once.
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub struct AuthUser {
+    pub user_id: Uuid,
+    pub roles: Vec<String>,
+    /// Permissions granted through the roles, limited to the key's scopes for API keys
+    pub scopes: Vec<String>,
+    /// The login session (refresh token family) the token was issued from.
+    /// `None` for API keys.
+    pub session_id: Option<Uuid>,
+    /// The organization the session is switched to. `None` for API keys.
+    pub org_id: Option<Uuid>,
+    /// When the access token or API key was issued
+    pub issued_at: DateTime<Utc>,
+    pub credential: Credential,
+}
+
+impl AuthUser {
+    /// The access token of an interactive session.
+    ///
+    /// Actions that manage the account's credentials (logging out, two-factor
+    /// settings, API keys) call this to refuse callers using an API key.
+    pub fn session(&self) -> Result<&Claims, ApiError> {
+        match &self.credential {
+            Credential::AccessToken(claims) => Ok(claims),
+            Credential::ApiKey { .. } => Err(ApiError::forbidden(
+                "This action requires a logged-in session, not an API key",
+            )
+            .with_code("SESSION_REQUIRED")),
+        }
+    }
+
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope)
+    }
+
+    async fn from_access_token(token: &str, state: &AppState) -> Result<Self, ApiError> {
+        // Verify token and extract claims
+        let claims = verify_token(token, &state.jwt_keys)?;
+
+        // Reject tokens revoked by logout, password change or deactivation
+        if SessionService::new(state).is_revoked(&claims).await? {
+            return Err(ApiError::unauthorized("Token has been revoked").with_code("TOKEN_REVOKED"));
+        }
+
+        Ok(Self {
+            user_id: claims.sub,
+            roles: claims.roles.clone(),
+            scopes: claims.perms.clone(),
+            session_id: Some(claims.sid),
+            org_id: claims.org_id,
+            issued_at: DateTime::from_timestamp_millis(claims.issued_at_millis())
+                .unwrap_or_default(),
+            credential: Credential::AccessToken(claims),
+        })
+    }
+
+    async fn from_api_key(key: &str, state: &AppState) -> Result<Self, ApiError> {
+        let principal = ApiKeyService::new(state).authenticate(key).await?;
+
+        Ok(Self {
+            user_id: principal.key.user_id,
+            roles: principal.roles,
+            scopes: principal.permissions,
+            session_id: None,
+            org_id: None,
+            issued_at: principal.key.created_at,
+            credential: Credential::ApiKey {
+                key_id: principal.key.id,
+            },
+        })
+    }
+}
+
+impl FromRequestParts<AppState> for AuthUser {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        if let Some(user) = parts.extensions.get::<AuthUser>() {
+            return Ok(user.clone());
+        }
+
+        let user = if let Some(token) = bearer_token(parts)? {
+            Self::from_access_token(token, state).await?
+        } else if let Some(key) = api_key(parts)? {
+            Self::from_api_key(key, state).await?
+        } else {
+            return Err(ApiError::unauthorized("Missing authorization header"));
+        };
+
+        parts.extensions.insert(user.clone());
+        Ok(user)
+    }
+}
+
+/// Like [`AuthUser`], but yields `None` for anonymous requests, for public
+/// endpoints that respond differently to signed-in callers.
+///
+/// A request that does send credentials is still rejected when they are
+/// invalid, so a bad token never silently downgrades to anonymous access.
+#[derive(Debug, Clone)]
+#[cfg_attr(not(test), allow(dead_code))]
+pub struct OptionalAuthUser(pub Option<AuthUser>);
+
+impl FromRequestParts<AppState> for OptionalAuthUser {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        if !parts.headers.contains_key(AUTHORIZATION) && !parts.headers.contains_key(API_KEY_HEADER)
+        {
+            return Ok(Self(None));
+        }
+
+        AuthUser::from_request_parts(parts, state)
+            .await
+            .map(|user| Self(Some(user)))
+    }
+}
+
+/// The organization the caller's session is switched to with
+/// `POST /orgs/switch`, and the caller's membership in it.
+///
+/// Rejects the request with 403 when no organization is selected, which is
+/// always the case for API keys, and with 404 when the caller no longer
+/// belongs to it. Authenticates the request like [`AuthUser`] and derefs to it.
+pub struct CurrentOrg {
+    user: AuthUser,
+    pub membership: Membership,
+}
+
+impl std::ops::Deref for CurrentOrg {
+    type Target = AuthUser;
+
+    fn deref(&self) -> &AuthUser {
+        &self.user
+    }
+}
+
+impl FromRequestParts<AppState> for CurrentOrg {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let user = AuthUser::from_request_parts(parts, state).await?;
+
+        let Some(org_id) = user.org_id else {
+            return Err(ApiError::forbidden(
+                "This action requires a session switched to an organization",
+            )
+            .with_code("ORGANIZATION_REQUIRED"));
+        };
+
+        // Membership is checked on every request, so removed members lose
+        // access right away rather than when their access token expires
+        let membership = OrganizationService::new(state)
+            .membership(org_id, user.user_id)
+            .await?;
+
+        Ok(Self { user, membership })
+    }
+}
+
+/// Address of the client making the request.
+///
+/// Taken from the last `X-Forwarded-For` entry (the one added by our proxy)
+/// when `TRUST_PROXY_HEADERS` is set, otherwise from the TCP connection.
+#[derive(Debug, Clone, Copy)]
+pub struct ClientIp(pub IpAddr);
+
+impl FromRequestParts<AppState> for ClientIp {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        if state.config.trust_proxy_headers
+            && let Some(ip) = forwarded_for(parts)
+        {
+            return Ok(Self(ip));
+        }
+
+        let ip = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip())
+            .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+
+        Ok(Self(ip))
+    }
+}
+
+/// Extract the address appended last to the X-Forwarded-For header
+fn forwarded_for(parts: &Parts) -> Option<IpAddr> {
+    parts
+        .headers
+        .get_all(X_FORWARDED_FOR)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .next_back()
+        .and_then(|entry| entry.trim().parse().ok())
+}
+
+/// Extract the bearer token from the Authorization header
+fn bearer_token(parts: &Parts) -> Result<Option<&str>, ApiError> {
+    let Some(header) = parts.headers.get(AUTHORIZATION) else {
+        return Ok(None);
+    };
+
+    header
+        .to_str()
+        .ok()
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(Some)
+        .ok_or_else(|| ApiError::unauthorized("Invalid authorization format"))
+}
+
+/// Extract the API key from the X-API-Key header
+fn api_key(parts: &Parts) -> Result<Option<&str>, ApiError> {
+    let Some(header) = parts.headers.get(API_KEY_HEADER) else {
+        return Ok(None);
+    };
+
+    header
+        .to_str()
+        .map(|value| Some(value.trim()))
+        .map_err(|_| ApiError::unauthorized("Invalid API key format"))
+}
+
+/// A permission that can be required by a handler
+pub trait Permission {
+    const NAME: &'static str;
+}
+
+macro_rules! permissions {
+    ($($(#[$meta:meta])* $ty:ident => $name:literal),* $(,)?) => {
+        $(
+            $(#[$meta])*
+            pub struct $ty;
+
+            impl Permission for $ty {
+                const NAME: &'static str = $name;
+            }
+        )*
+    };
+}
+
+permissions! {
+    /// View any user profile
+    UsersRead => "users:read",
+    /// Modify any user account
+    UsersWrite => "users:write",
+    /// Permanently delete any user account
+    UsersDelete => "users:delete",
+}
+
+/// Rejects the request with 403 unless the authenticated user holds permission `P`.
+///
+/// Authenticates the request like [`AuthUser`] and derefs to it.
+pub struct RequirePermission<P: Permission> {
+    user: AuthUser,
+    _permission: PhantomData<P>,
+}
+
+impl<P: Permission> std::ops::Deref for RequirePermission<P> {
+    type Target = AuthUser;
+
+    fn deref(&self) -> &AuthUser {
+        &self.user
+    }
+}
+
+impl<P: Permission> FromRequestParts<AppState> for RequirePermission<P> {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let user = AuthUser::from_request_parts(parts, state).await?;
+
+        if !user.has_scope(P::NAME) {
+            return Err(
+                ApiError::forbidden(format!("Missing required permission: {}", P::NAME))
+                    .with_code("INSUFFICIENT_PERMISSIONS"),
+            );
+        }
+
+        Ok(Self {
+            user,
+            _permission: PhantomData,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{Request, StatusCode};
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::{
+        domain::{
+            models::User,
+            services::{AuthService, UserService},
+        },
+        infrastructure::repositories::RoleRepository,
+        test_utils::test_state,
+    };
+
+    /// Request parts carrying the given header
+    fn parts_with(name: HeaderName, value: &str) -> Parts {
+        Request::builder()
+            .header(name, value)
+            .body(())
+            .unwrap()
+            .into_parts()
+            .0
+    }
+
+    async fn register(state: &AppState, email: &str) -> (User, String) {
+        let tokens = AuthService::new(state)
+            .register(email, "password123", "Extractor User")
+            .await
+            .unwrap()
+            .unwrap();
+        let user = UserService::new(state).get_by_email(email).await.unwrap();
+        (user, tokens.refresh_token)
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_auth_user_from_credentials(pool: PgPool) {
+        let (state, _mailer) = test_state(pool);
+        let (user, refresh_token) = register(&state, "extract@example.com").await;
+        let tokens = AuthService::new(&state)
+            .refresh(&refresh_token)
+            .await
+            .unwrap();
+
+        let mut parts = Request::new(()).into_parts().0;
+        let err = AuthUser::from_request_parts(&mut parts, &state)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status, StatusCode::UNAUTHORIZED);
+
+        for header in ["Basic abc", "Bearer not-a-jwt"] {
+            let mut parts = parts_with(AUTHORIZATION, header);
+            let err = AuthUser::from_request_parts(&mut parts, &state)
+                .await
+                .unwrap_err();
+            assert_eq!(err.status, StatusCode::UNAUTHORIZED);
+        }
+
+        let mut parts = parts_with(AUTHORIZATION, &format!("Bearer {}", tokens.access_token));
+        let auth_user = AuthUser::from_request_parts(&mut parts, &state)
+            .await
+            .unwrap();
+        let claims = auth_user.session().unwrap().clone();
+        assert_eq!(auth_user.user_id, user.id);
+        assert_eq!(auth_user.roles, vec!["user".to_string()]);
+        assert_eq!(auth_user.session_id, Some(claims.sid));
+        assert_eq!(
+            auth_user.issued_at.timestamp_millis(),
+            claims.issued_at_millis()
+        );
+        // Cached for further extractors of the same request
+        assert!(parts.extensions.get::<AuthUser>().is_some());
+
+        SessionService::new(&state).logout(&claims).await.unwrap();
+        let mut parts = parts_with(AUTHORIZATION, &format!("Bearer {}", tokens.access_token));
+        let err = AuthUser::from_request_parts(&mut parts, &state)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status, StatusCode::UNAUTHORIZED);
+        assert_eq!(err.error_code.as_deref(), Some("TOKEN_REVOKED"));
+
+        // API keys authenticate without a session
+        let key = ApiKeyService::new(&state)
+            .create(user.id, "ci".into(), vec![], None)
+            .await
+            .unwrap();
+        let mut parts = parts_with(API_KEY_HEADER, &key.secret);
+        let auth_user = AuthUser::from_request_parts(&mut parts, &state)
+            .await
+            .unwrap();
+        assert_eq!(auth_user.user_id, user.id);
+        assert_eq!(auth_user.session_id, None);
+        assert_eq!(
+            auth_user.session().unwrap_err().status,
+            StatusCode::FORBIDDEN
+        );
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_optional_auth_user(pool: PgPool) {
+        let (state, _mailer) = test_state(pool);
+        let (user, refresh_token) = register(&state, "optional@example.com").await;
+        let tokens = AuthService::new(&state)
+            .refresh(&refresh_token)
+            .await
+            .unwrap();
+
+        // Anonymous requests are let through
+        let mut parts = Request::new(()).into_parts().0;
+        let OptionalAuthUser(anonymous) = OptionalAuthUser::from_request_parts(&mut parts, &state)
+            .await
+            .unwrap();
+        assert!(anonymous.is_none());
+
+        let mut parts = parts_with(AUTHORIZATION, &format!("Bearer {}", tokens.access_token));
+        let OptionalAuthUser(caller) = OptionalAuthUser::from_request_parts(&mut parts, &state)
+            .await
+            .unwrap();
+        assert_eq!(caller.unwrap().user_id, user.id);
+
+        // Credentials that are sent must be valid
+        for (name, value) in [
+            (AUTHORIZATION, "Bearer not-a-jwt"),
+            (AUTHORIZATION, "Basic abc"),
+            (API_KEY_HEADER, "not-a-key"),
+        ] {
+            let mut parts = parts_with(name, value);
+            let err = OptionalAuthUser::from_request_parts(&mut parts, &state)
+                .await
+                .unwrap_err();
+            assert_eq!(err.status, StatusCode::UNAUTHORIZED);
+        }
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_require_permission(pool: PgPool) {
+        let (state, _mailer) = test_state(pool);
+        let (user, refresh_token) = register(&state, "rbac@example.com").await;
+        let auth_service = AuthService::new(&state);
+
+        let tokens = auth_service.refresh(&refresh_token).await.unwrap();
+        let mut parts = parts_with(AUTHORIZATION, &format!("Bearer {}", tokens.access_token));
+        let err = RequirePermission::<UsersRead>::from_request_parts(&mut parts, &state)
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.status, StatusCode::FORBIDDEN);
+        assert_eq!(err.error_code.as_deref(), Some("INSUFFICIENT_PERMISSIONS"));
+
+        // Roles requiring two-factor authentication grant nothing without it
+        let role_repo = RoleRepository::new(&state.db_pool);
+        role_repo.assign(user.id, "admin").await.unwrap();
+        let tokens = auth_service.refresh(&tokens.refresh_token).await.unwrap();
+        let mut parts = parts_with(AUTHORIZATION, &format!("Bearer {}", tokens.access_token));
+        assert!(
+            RequirePermission::<UsersRead>::from_request_parts(&mut parts, &state)
+                .await
+                .is_err()
+        );
+
+        // Permissions are carried in the token, so a new role applies from
+        // the next refresh
+        sqlx::query("INSERT INTO roles (name, description) VALUES ('auditor', 'Reads users')")
+            .execute(&state.db_pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO role_permissions (role_name, permission_name) VALUES ('auditor', 'users:read')",
+        )
+        .execute(&state.db_pool)
+        .await
+        .unwrap();
+        role_repo.assign(user.id, "auditor").await.unwrap();
+        let tokens = auth_service.refresh(&tokens.refresh_token).await.unwrap();
+        let mut parts = parts_with(AUTHORIZATION, &format!("Bearer {}", tokens.access_token));
+        let auditor = RequirePermission::<UsersRead>::from_request_parts(&mut parts, &state)
+            .await
+            .unwrap();
+        assert_eq!(auditor.user_id, user.id);
+        assert!(auditor.roles.contains(&"auditor".to_string()));
+        let mut parts = parts_with(AUTHORIZATION, &format!("Bearer {}", tokens.access_token));
+        assert!(
+            RequirePermission::<UsersDelete>::from_request_parts(&mut parts, &state)
+                .await
+                .is_err()
+        );
+
+        // API keys only carry the scopes they were created with
+        let key = ApiKeyService::new(&state)
+            .create(user.id, "ci".into(), vec!["users:read".into()], None)
+            .await
+            .unwrap();
+        let mut parts = parts_with(API_KEY_HEADER, &key.secret);
+        assert!(
+            RequirePermission::<UsersRead>::from_request_parts(&mut parts, &state)
+                .await
+                .is_ok()
+        );
+        let mut parts = parts_with(API_KEY_HEADER, &key.secret);
+        let err = RequirePermission::<UsersWrite>::from_request_parts(&mut parts, &state)
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.status, StatusCode::FORBIDDEN);
+    }
+}
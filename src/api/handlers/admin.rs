@@ -0,0 +1,639 @@
+//! Admin handlers - user management for operations staff
+
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+use validator::{Validate, ValidationError, ValidationErrors};
+
+use crate::{
+    api::{
+        error::ApiError,
+        extractors::{ClientIp, RequirePermission, UsersDelete, UsersRead, UsersWrite},
+    },
+    config::AppState,
+    domain::{
+        models::User,
+        services::{Actor, AdminService, AdminUserUpdate, ImportSkipReason, ImportedUser},
+    },
+};
+
+// ============================================================================
+// Request/Response DTOs
+// ============================================================================
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListUsersQuery {
+    /// Part of the email or name to match, ignoring case
+    #[validate(length(min = 1, max = 255, message = "Search must be 1-255 characters"))]
+    pub q: Option<String>,
+    /// Only active (`true`) or deactivated (`false`) users; omit for both
+    pub active: Option<bool>,
+    /// Page size, 1-100 (default 20)
+    #[validate(range(min = 1, max = 100, message = "Limit must be 1-100"))]
+    pub limit: Option<i64>,
+    /// Number of users to skip (default 0)
+    #[validate(range(min = 0, message = "Offset must not be negative"))]
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct AdminUpdateUserRequest {
+    #[validate(length(min = 1, message = "Name is required"))]
+    #[schema(example = "John Doe")]
+    pub name: Option<String>,
+    /// A new address is unverified, and gets a verification email, unless
+    /// `email_verified` is also true
+    #[validate(email(message = "Invalid email format"))]
+    #[schema(example = "user@example.com")]
+    pub email: Option<String>,
+    /// Mark the email address verified or unverified
+    pub email_verified: Option<bool>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AdminUserData {
+    pub id: Uuid,
+    #[schema(example = "user@example.com")]
+    pub email: String,
+    #[schema(example = "John Doe")]
+    pub name: String,
+    pub is_active: bool,
+    pub email_verified_at: Option<DateTime<Utc>>,
+    /// When the owner deleted the account; it can be restored until purged
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<User> for AdminUserData {
+    fn from(user: User) -> Self {
+        Self {
+            id: user.id,
+            email: user.email,
+            name: user.name,
+            is_active: user.is_active,
+            email_verified_at: user.email_verified_at,
+            deleted_at: user.deleted_at,
+            created_at: user.created_at,
+            updated_at: user.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AdminUserResponse {
+    pub success: bool,
+    pub data: AdminUserData,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AdminUserDetailResponse {
+    pub success: bool,
+    pub data: AdminUserDetailData,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AdminUserDetailData {
+    #[serde(flatten)]
+    pub user: AdminUserData,
+    #[schema(example = json!(["user"]))]
+    pub roles: Vec<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AdminUserListResponse {
+    pub success: bool,
+    pub data: Vec<AdminUserData>,
+    /// Number of users matching the filters
+    #[schema(example = 42)]
+    pub total: i64,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ImportUsersRequest {
+    /// 1-1000 users
+    pub users: Vec<ImportUserRequest>,
+}
+
+/// Written out because `#[validate(length)]` copies the value into the error,
+/// which would put every password hash of the batch there.
+impl Validate for ImportUsersRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        if !(1..=1000).contains(&self.users.len()) {
+            errors.add(
+                "users",
+                ValidationError::new("length").with_message("Import 1-1000 users at a time".into()),
+            );
+            return Err(errors);
+        }
+
+        errors.merge_self("users", self.users.validate());
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct ImportUserRequest {
+    #[validate(email(message = "Invalid email format"))]
+    #[schema(example = "user@example.com")]
+    pub email: String,
+    #[validate(length(min = 1, message = "Name is required"))]
+    #[schema(example = "John Doe")]
+    pub name: String,
+    /// Existing hash: Argon2 or PBKDF2 PHC string, bcrypt, or Django `pbkdf2_sha256`.
+    /// PBKDF2 hashes may use at most 1,000,000 iterations, bcrypt a cost of at
+    /// most 14, and Argon2 at most 256 MiB, 10 passes and 16 lanes.
+    #[validate(length(min = 1, max = 255, message = "Password hash must be 1-255 characters"))]
+    #[schema(example = "$2b$12$R9h/cIPz0gi.URNNX3kh2OPST9/PgBkqquzi.Ss7KIUgO2t0jWMUW")]
+    pub password_hash: String,
+    /// Mark the email address as already verified
+    #[serde(default)]
+    pub email_verified: bool,
+}
+
+impl From<ImportUserRequest> for ImportedUser {
+    fn from(user: ImportUserRequest) -> Self {
+        Self {
+            email: user.email,
+            name: user.name,
+            password_hash: user.password_hash,
+            email_verified: user.email_verified,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportUsersResponse {
+    pub success: bool,
+    pub data: ImportUsersData,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportUsersData {
+    /// Number of users created
+    #[schema(example = 998)]
+    pub imported: usize,
+    pub skipped: Vec<SkippedUserData>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SkippedUserData {
+    #[schema(example = "user@example.com")]
+    pub email: String,
+    /// `USER_ALREADY_EXISTS` or `UNSUPPORTED_HASH`
+    #[schema(example = "USER_ALREADY_EXISTS")]
+    pub reason: String,
+}
+
+/// Default page size of the user listing
+const DEFAULT_LIMIT: i64 = 20;
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+/// List users
+///
+/// Includes deactivated users. Requires the `users:read` permission.
+#[utoipa::path(
+    get,
+    path = "/admin/users",
+    tag = "admin",
+    params(ListUsersQuery),
+    responses(
+        (status = 200, description = "Users, newest first", body = AdminUserListResponse),
+        (status = 400, description = "Validation error", body = ApiError),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Email address not verified or missing permission")
+    ),
+    security(
+        ("jwt" = ["users:read"]),
+        ("api_key" = ["users:read"])
+    )
+)]
+pub async fn list_users(
+    State(state): State<AppState>,
+    user: RequirePermission<UsersRead>,
+    ClientIp(ip): ClientIp,
+    Query(query): Query<ListUsersQuery>,
+) -> Result<Json<AdminUserListResponse>, ApiError> {
+    // Validate input
+    query.validate()?;
+
+    let admin_service = AdminService::new(&state);
+    let page = admin_service
+        .list_users(
+            &Actor {
+                user_id: user.user_id,
+                ip,
+            },
+            query.q.as_deref(),
+            query.active,
+            query.limit.unwrap_or(DEFAULT_LIMIT),
+            query.offset.unwrap_or(0),
+        )
+        .await?;
+
+    Ok(Json(AdminUserListResponse {
+        success: true,
+        data: page.users.into_iter().map(Into::into).collect(),
+        total: page.total,
+    }))
+}
+
+/// Get a user
+///
+/// Includes deactivated users and lists the user's roles. Requires the
+/// `users:read` permission.
+#[utoipa::path(
+    get,
+    path = "/admin/users/{id}",
+    tag = "admin",
+    params(
+        ("id" = Uuid, Path, description = "User ID")
+    ),
+    responses(
+        (status = 200, description = "User details", body = AdminUserDetailResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Email address not verified or missing permission"),
+        (status = 404, description = "User not found")
+    ),
+    security(
+        ("jwt" = ["users:read"]),
+        ("api_key" = ["users:read"])
+    )
+)]
+pub async fn get_user(
+    State(state): State<AppState>,
+    user: RequirePermission<UsersRead>,
+    ClientIp(ip): ClientIp,
+    Path(id): Path<Uuid>,
+) -> Result<Json<AdminUserDetailResponse>, ApiError> {
+    let admin_service = AdminService::new(&state);
+    let (found, roles) = admin_service
+        .get_user(
+            &Actor {
+                user_id: user.user_id,
+                ip,
+            },
+            id,
+        )
+        .await?;
+
+    Ok(Json(AdminUserDetailResponse {
+        success: true,
+        data: AdminUserDetailData {
+            user: found.into(),
+            roles,
+        },
+    }))
+}
+
+/// Update a user
+///
+/// Requires the `users:write` permission.
+#[utoipa::path(
+    patch,
+    path = "/admin/users/{id}",
+    tag = "admin",
+    params(
+        ("id" = Uuid, Path, description = "User ID")
+    ),
+    request_body = AdminUpdateUserRequest,
+    responses(
+        (status = 200, description = "Updated user", body = AdminUserResponse),
+        (status = 400, description = "Validation error", body = ApiError),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Email address not verified or missing permission"),
+        (status = 404, description = "User not found"),
+        (status = 409, description = "Email already in use", body = ApiError)
+    ),
+    security(
+        ("jwt" = ["users:write"]),
+        ("api_key" = ["users:write"])
+    )
+)]
+pub async fn update_user(
+    State(state): State<AppState>,
+    user: RequirePermission<UsersWrite>,
+    ClientIp(ip): ClientIp,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<AdminUpdateUserRequest>,
+) -> Result<Json<AdminUserResponse>, ApiError> {
+    // Validate input
+    payload.validate()?;
+
+    let admin_service = AdminService::new(&state);
+    let updated = admin_service
+        .update_user(
+            &Actor {
+                user_id: user.user_id,
+                ip,
+            },
+            id,
+            AdminUserUpdate {
+                name: payload.name,
+                email: payload.email,
+                email_verified: payload.email_verified,
+            },
+        )
+        .await?;
+
+    Ok(Json(AdminUserResponse {
+        success: true,
+        data: updated.into(),
+    }))
+}
+
+/// Deactivate a user
+///
+/// The user can no longer log in, and their sessions and API keys stop
+/// working. Requires the `users:write` permission.
+#[utoipa::path(
+    post,
+    path = "/admin/users/{id}/deactivate",
+    tag = "admin",
+    params(
+        ("id" = Uuid, Path, description = "User ID")
+    ),
+    responses(
+        (status = 204, description = "User deactivated"),
+        (status = 400, description = "Cannot deactivate yourself", body = ApiError),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Email address not verified or missing permission"),
+        (status = 404, description = "User not found")
+    ),
+    security(
+        ("jwt" = ["users:write"]),
+        ("api_key" = ["users:write"])
+    )
+)]
+pub async fn deactivate_user(
+    State(state): State<AppState>,
+    user: RequirePermission<UsersWrite>,
+    ClientIp(ip): ClientIp,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    let admin_service = AdminService::new(&state);
+    admin_service
+        .deactivate_user(
+            &Actor {
+                user_id: user.user_id,
+                ip,
+            },
+            id,
+        )
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Reactivate a user
+///
+/// The user can log in again and their API keys work again; sessions ended
+/// by the deactivation stay ended. Accounts deleted by their owner are
+/// restored instead. Requires the `users:write` permission.
+#[utoipa::path(
+    post,
+    path = "/admin/users/{id}/reactivate",
+    tag = "admin",
+    params(
+        ("id" = Uuid, Path, description = "User ID")
+    ),
+    responses(
+        (status = 204, description = "User reactivated"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Email address not verified or missing permission"),
+        (status = 404, description = "User not found or deleted")
+    ),
+    security(
+        ("jwt" = ["users:write"]),
+        ("api_key" = ["users:write"])
+    )
+)]
+pub async fn reactivate_user(
+    State(state): State<AppState>,
+    user: RequirePermission<UsersWrite>,
+    ClientIp(ip): ClientIp,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    let admin_service = AdminService::new(&state);
+    admin_service
+        .reactivate_user(
+            &Actor {
+                user_id: user.user_id,
+                ip,
+            },
+            id,
+        )
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Restore a deleted user
+///
+/// Undoes an account deletion during the grace period before the account is
+/// purged. The user can log in again; their sessions and API keys stay
+/// revoked. Requires the `users:write` permission.
+#[utoipa::path(
+    post,
+    path = "/admin/users/{id}/restore",
+    tag = "admin",
+    params(
+        ("id" = Uuid, Path, description = "User ID")
+    ),
+    responses(
+        (status = 200, description = "User restored", body = AdminUserResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Email address not verified or missing permission"),
+        (status = 404, description = "User not found, not deleted or already purged")
+    ),
+    security(
+        ("jwt" = ["users:write"]),
+        ("api_key" = ["users:write"])
+    )
+)]
+pub async fn restore_user(
+    State(state): State<AppState>,
+    user: RequirePermission<UsersWrite>,
+    ClientIp(ip): ClientIp,
+    Path(id): Path<Uuid>,
+) -> Result<Json<AdminUserResponse>, ApiError> {
+    let admin_service = AdminService::new(&state);
+    let restored = admin_service
+        .restore_user(
+            &Actor {
+                user_id: user.user_id,
+                ip,
+            },
+            id,
+        )
+        .await?;
+
+    Ok(Json(AdminUserResponse {
+        success: true,
+        data: restored.into(),
+    }))
+}
+
+/// Unlock a user account
+///
+/// Lifts a lock placed after repeated failed logins and clears the failure
+/// count. Requires the `users:write` permission.
+#[utoipa::path(
+    post,
+    path = "/admin/users/{id}/unlock",
+    tag = "admin",
+    params(
+        ("id" = Uuid, Path, description = "User ID")
+    ),
+    responses(
+        (status = 204, description = "Account unlocked"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Email address not verified or missing permission"),
+        (status = 404, description = "User not found")
+    ),
+    security(
+        ("jwt" = ["users:write"]),
+        ("api_key" = ["users:write"])
+    )
+)]
+pub async fn unlock_user(
+    State(state): State<AppState>,
+    user: RequirePermission<UsersWrite>,
+    ClientIp(ip): ClientIp,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    let admin_service = AdminService::new(&state);
+    admin_service
+        .unlock_user(
+            &Actor {
+                user_id: user.user_id,
+                ip,
+            },
+            id,
+        )
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Permanently delete a user
+///
+/// Removes the account and all of its data; this cannot be undone. Requires
+/// the `users:delete` permission.
+#[utoipa::path(
+    delete,
+    path = "/admin/users/{id}",
+    tag = "admin",
+    params(
+        ("id" = Uuid, Path, description = "User ID")
+    ),
+    responses(
+        (status = 204, description = "User deleted"),
+        (status = 400, description = "Cannot delete yourself", body = ApiError),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Email address not verified or missing permission"),
+        (status = 404, description = "User not found"),
+        (status = 409, description = "Only owner of an organization", body = ApiError)
+    ),
+    security(
+        ("jwt" = ["users:delete"]),
+        ("api_key" = ["users:delete"])
+    )
+)]
+pub async fn delete_user(
+    State(state): State<AppState>,
+    user: RequirePermission<UsersDelete>,
+    ClientIp(ip): ClientIp,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    let admin_service = AdminService::new(&state);
+    admin_service
+        .delete_user(
+            &Actor {
+                user_id: user.user_id,
+                ip,
+            },
+            id,
+        )
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Import users with existing password hashes
+///
+/// Creates accounts migrated from another system without knowing their
+/// passwords. Besides Argon2, bcrypt and PBKDF2-SHA256 hashes are accepted;
+/// they are upgraded to Argon2 on each user's first login. Users whose email
+/// is taken or whose hash is not recognized are skipped and listed in the
+/// response. Requires the `users:write` permission.
+#[utoipa::path(
+    post,
+    path = "/admin/users/import",
+    tag = "admin",
+    request_body = ImportUsersRequest,
+    responses(
+        (status = 200, description = "Import report", body = ImportUsersResponse),
+        (status = 400, description = "Validation error", body = ApiError),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Email address not verified or missing permission")
+    ),
+    security(
+        ("jwt" = ["users:write"]),
+        ("api_key" = ["users:write"])
+    )
+)]
+pub async fn import_users(
+    State(state): State<AppState>,
+    user: RequirePermission<UsersWrite>,
+    ClientIp(ip): ClientIp,
+    Json(payload): Json<ImportUsersRequest>,
+) -> Result<Json<ImportUsersResponse>, ApiError> {
+    // Validate input
+    payload.validate()?;
+
+    let admin_service = AdminService::new(&state);
+    let report = admin_service
+        .import_users(
+            &Actor {
+                user_id: user.user_id,
+                ip,
+            },
+            payload.users.into_iter().map(Into::into).collect(),
+        )
+        .await?;
+
+    Ok(Json(ImportUsersResponse {
+        success: true,
+        data: ImportUsersData {
+            imported: report.imported,
+            skipped: report
+                .skipped
+                .into_iter()
+                .map(|(email, reason)| SkippedUserData {
+                    email,
+                    reason: match reason {
+                        ImportSkipReason::AlreadyExists => "USER_ALREADY_EXISTS",
+                        ImportSkipReason::UnsupportedHash => "UNSUPPORTED_HASH",
+                    }
+                    .to_string(),
+                })
+                .collect(),
+        },
+    }))
+}
@@ -0,0 +1,201 @@
+//! API key handlers
+
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    api::{error::ApiError, extractors::AuthUser},
+    config::AppState,
+    domain::{models::ApiKey, services::ApiKeyService},
+};
+
+// ============================================================================
+// Request/Response DTOs
+// ============================================================================
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CreateApiKeyRequest {
+    /// Label to tell keys apart
+    #[validate(length(min = 1, max = 100, message = "Name must be 1-100 characters"))]
+    #[schema(example = "CI deploys")]
+    pub name: String,
+    /// Permissions the key may use; each must be held by the user
+    #[serde(default)]
+    #[schema(example = json!(["users:read"]))]
+    pub scopes: Vec<String>,
+    /// Days until the key expires; omit for a key that does not expire
+    #[validate(range(min = 1, max = 365, message = "Expiry must be 1-365 days"))]
+    #[schema(example = 90)]
+    pub expires_in_days: Option<i64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ApiKeyData {
+    pub id: Uuid,
+    #[schema(example = "CI deploys")]
+    pub name: String,
+    /// Public part of the key, matching the start of the secret
+    #[schema(example = "ak_x7kq2mfa")]
+    pub prefix: String,
+    pub scopes: Vec<String>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<ApiKey> for ApiKeyData {
+    fn from(key: ApiKey) -> Self {
+        Self {
+            id: key.id,
+            name: key.name,
+            prefix: key.prefix,
+            scopes: key.scopes,
+            last_used_at: key.last_used_at,
+            expires_at: key.expires_at,
+            created_at: key.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreatedApiKeyResponse {
+    pub success: bool,
+    pub data: CreatedApiKeyData,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreatedApiKeyData {
+    /// The API key, sent as the `X-API-Key` header. Shown only once.
+    #[schema(example = "ak_x7kq2mfa_Jq3v...")]
+    pub key: String,
+    #[serde(flatten)]
+    pub api_key: ApiKeyData,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ApiKeyListResponse {
+    pub success: bool,
+    pub data: Vec<ApiKeyData>,
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+/// Create an API key
+///
+/// The key is returned only in this response; store it securely.
+#[utoipa::path(
+    post,
+    path = "/users/me/api-keys",
+    tag = "api-keys",
+    request_body = CreateApiKeyRequest,
+    responses(
+        (status = 201, description = "API key created", body = CreatedApiKeyResponse),
+        (status = 400, description = "Validation error or scope not held", body = ApiError),
+        (status = 401, description = "Unauthorized", body = ApiError),
+        (status = 403, description = "Called with an API key", body = ApiError)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn create_api_key(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(payload): Json<CreateApiKeyRequest>,
+) -> Result<(StatusCode, Json<CreatedApiKeyResponse>), ApiError> {
+    user.session()?;
+
+    // Validate input
+    payload.validate()?;
+
+    let api_key_service = ApiKeyService::new(&state);
+    let created = api_key_service
+        .create(
+            user.user_id,
+            payload.name,
+            payload.scopes,
+            payload.expires_in_days,
+        )
+        .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreatedApiKeyResponse {
+            success: true,
+            data: CreatedApiKeyData {
+                key: created.secret,
+                api_key: created.key.into(),
+            },
+        }),
+    ))
+}
+
+/// List API keys
+///
+/// Returns the keys that have not been revoked, without their secrets.
+#[utoipa::path(
+    get,
+    path = "/users/me/api-keys",
+    tag = "api-keys",
+    responses(
+        (status = 200, description = "API keys", body = ApiKeyListResponse),
+        (status = 401, description = "Unauthorized", body = ApiError)
+    ),
+    security(
+        ("jwt" = []),
+        ("api_key" = [])
+    )
+)]
+pub async fn list_api_keys(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<ApiKeyListResponse>, ApiError> {
+    let api_key_service = ApiKeyService::new(&state);
+    let keys = api_key_service.list(user.user_id).await?;
+
+    Ok(Json(ApiKeyListResponse {
+        success: true,
+        data: keys.into_iter().map(Into::into).collect(),
+    }))
+}
+
+/// Revoke an API key
+#[utoipa::path(
+    delete,
+    path = "/users/me/api-keys/{id}",
+    tag = "api-keys",
+    params(
+        ("id" = Uuid, Path, description = "API key ID")
+    ),
+    responses(
+        (status = 204, description = "API key revoked"),
+        (status = 401, description = "Unauthorized", body = ApiError),
+        (status = 403, description = "Called with an API key", body = ApiError),
+        (status = 404, description = "API key not found", body = ApiError)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn revoke_api_key(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    user.session()?;
+
+    let api_key_service = ApiKeyService::new(&state);
+    api_key_service.revoke(user.user_id, id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
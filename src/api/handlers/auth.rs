@@ -1,11 +1,26 @@
 //! Authentication handlers
 
-use axum::{Json, extract::State};
+use axum::{
+    Json,
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use validator::Validate;
 
-use crate::{api::error::ApiError, config::AppState, domain::services::AuthService};
+use crate::{
+    api::{
+        error::ApiError,
+        extractors::{AuthUser, ClientIp},
+    },
+    config::AppState,
+    domain::services::{
+        AuthService, AuthTokens, EmailVerificationService, LoginOutcome, PasswordResetService,
+        SessionService,
+    },
+};
 
 // ============================================================================
 // Request/Response DTOs
@@ -16,8 +31,8 @@ pub struct RegisterRequest {
     #[validate(email(message = "Invalid email format"))]
     #[schema(example = "user@example.com")]
     pub email: String,
-    #[validate(length(min = 8, message = "Password must be at least 8 characters"))]
-    #[schema(example = "password123")]
+    /// Checked against the password policy
+    #[schema(example = "correct-horse-battery")]
     pub password: String,
     #[validate(length(min = 1, message = "Name is required"))]
     #[schema(example = "John Doe")]
@@ -33,6 +48,42 @@ pub struct LoginRequest {
     pub password: String,
 }
 
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct RefreshRequest {
+    #[validate(length(min = 1, message = "Refresh token is required"))]
+    #[schema(example = "q3Jb0Yc9uKp2gX7m1o5vZ8wE4rT6yU0iA2sD3fG5hJk")]
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct ForgotPasswordRequest {
+    #[validate(email(message = "Invalid email format"))]
+    #[schema(example = "user@example.com")]
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct ResetPasswordRequest {
+    #[validate(length(min = 1, message = "Reset token is required"))]
+    pub token: String,
+    /// Checked against the password policy
+    #[schema(example = "correct-horse-staple")]
+    pub password: String,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct VerifyEmailRequest {
+    #[validate(length(min = 1, message = "Verification token is required"))]
+    pub token: String,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct ResendVerificationRequest {
+    #[validate(email(message = "Invalid email format"))]
+    #[schema(example = "user@example.com")]
+    pub email: String,
+}
+
 #[derive(Debug, Serialize, ToSchema)]
 pub struct AuthResponse {
     pub success: bool,
@@ -41,16 +92,108 @@ pub struct AuthResponse {
 
 #[derive(Debug, Serialize, ToSchema)]
 pub struct AuthData {
+    /// Short-lived access token (JWT)
     pub token: String,
+    #[schema(example = "Bearer")]
     pub token_type: String,
+    /// Access token lifetime in seconds
+    #[schema(example = 900)]
+    pub expires_in: i64,
+    /// Opaque refresh token; rotated on every use
+    pub refresh_token: String,
+    /// Refresh token lifetime in seconds
+    #[schema(example = 2592000)]
+    pub refresh_expires_in: i64,
+}
+
+impl From<AuthTokens> for AuthData {
+    fn from(tokens: AuthTokens) -> Self {
+        Self {
+            token: tokens.access_token,
+            token_type: "Bearer".to_string(),
+            expires_in: tokens.expires_in,
+            refresh_token: tokens.refresh_token,
+            refresh_expires_in: tokens.refresh_expires_in,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LoginResponse {
+    pub success: bool,
+    pub data: LoginData,
+}
+
+/// Either a token pair or, for accounts with two-factor authentication, a
+/// challenge to complete at `/auth/mfa/verify`
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum LoginData {
+    Authenticated(AuthData),
+    MfaRequired(MfaChallengeData),
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MfaChallengeData {
+    #[schema(example = true)]
+    pub mfa_required: bool,
+    /// Short-lived token to exchange, with a code, at `/auth/mfa/verify`
+    pub mfa_token: String,
+    /// MFA token lifetime in seconds
+    #[schema(example = 300)]
     pub expires_in: i64,
 }
 
+impl From<LoginOutcome> for LoginData {
+    fn from(outcome: LoginOutcome) -> Self {
+        match outcome {
+            LoginOutcome::Authenticated(tokens) => Self::Authenticated(tokens.into()),
+            LoginOutcome::MfaRequired {
+                mfa_token,
+                expires_in,
+            } => Self::MfaRequired(MfaChallengeData {
+                mfa_required: true,
+                mfa_token,
+                expires_in,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MessageResponse {
+    pub success: bool,
+    pub data: MessageData,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MessageData {
+    pub message: String,
+}
+
+impl MessageResponse {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            success: true,
+            data: MessageData {
+                message: message.into(),
+            },
+        }
+    }
+}
+
 // ============================================================================
 // Handlers
 // ============================================================================
 
 /// Register a new user
+///
+/// A verification link is emailed to the new address. When unverified accounts
+/// may not log in (`EMAIL_VERIFICATION_MODE=block_login`) no tokens are issued
+/// and the response is `202 Accepted`.
+///
+/// With `REGISTRATION_MODE=enumeration_resistant` the response is always
+/// `202 Accepted`; if the email is taken, its owner is emailed instead.
 #[utoipa::path(
     post,
     path = "/auth/register",
@@ -58,64 +201,278 @@ pub struct AuthData {
     request_body = RegisterRequest,
     responses(
         (status = 200, description = "Registration successful", body = AuthResponse),
-        (status = 400, description = "Validation error", body = ApiError),
-        (status = 409, description = "Email already exists", body = ApiError)
+        (status = 202, description = "Registration accepted, email verification required before login", body = MessageResponse),
+        (status = 400, description = "Validation error or password policy violation", body = ApiError),
+        (status = 409, description = "Email already exists (standard registration mode only)", body = ApiError)
     )
 )]
 pub async fn register(
     State(state): State<AppState>,
     Json(payload): Json<RegisterRequest>,
-) -> Result<Json<AuthResponse>, ApiError> {
+) -> Result<Response, ApiError> {
     // Validate input
     payload.validate()?;
 
     // Call auth service
     let auth_service = AuthService::new(&state);
-    let token = auth_service
+    let tokens = auth_service
         .register(&payload.email, &payload.password, &payload.name)
         .await?;
 
-    Ok(Json(AuthResponse {
-        success: true,
-        data: AuthData {
-            token,
-            token_type: "Bearer".to_string(),
-            expires_in: state.config.jwt_expiration_hours * 3600,
-        },
-    }))
+    let response = match tokens {
+        Some(tokens) => Json(AuthResponse {
+            success: true,
+            data: tokens.into(),
+        })
+        .into_response(),
+        None => (
+            StatusCode::ACCEPTED,
+            Json(MessageResponse::new(
+                "Registration successful, please verify your email address",
+            )),
+        )
+            .into_response(),
+    };
+
+    Ok(response)
 }
 
 /// Login with email and password
+///
+/// For accounts with two-factor authentication enabled, the response contains
+/// `mfa_required: true` and an `mfa_token` instead of tokens.
+///
+/// Repeated failures slow down further attempts (429) and eventually lock the
+/// account (423, `ACCOUNT_LOCKED`) until it expires, the password is reset or
+/// an administrator unlocks it.
 #[utoipa::path(
     post,
     path = "/auth/login",
     tag = "auth",
     request_body = LoginRequest,
     responses(
-        (status = 200, description = "Login successful", body = AuthResponse),
+        (status = 200, description = "Login successful or second factor required", body = LoginResponse),
         (status = 400, description = "Validation error", body = ApiError),
-        (status = 401, description = "Invalid credentials", body = ApiError)
+        (status = 401, description = "Invalid credentials", body = ApiError),
+        (status = 403, description = "Email address not verified", body = ApiError),
+        (status = 423, description = "Account locked after too many failed logins", body = ApiError),
+        (status = 429, description = "Too many failed logins; retry after the delay", body = ApiError)
     )
 )]
 pub async fn login(
     State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
     Json(payload): Json<LoginRequest>,
-) -> Result<Json<AuthResponse>, ApiError> {
+) -> Result<Json<LoginResponse>, ApiError> {
     // Validate input
     payload.validate()?;
 
     // Call auth service
     let auth_service = AuthService::new(&state);
-    let token = auth_service
-        .login(&payload.email, &payload.password)
+    let outcome = auth_service
+        .login(&payload.email, &payload.password, ip)
         .await?;
 
+    Ok(Json(LoginResponse {
+        success: true,
+        data: outcome.into(),
+    }))
+}
+
+/// Exchange a refresh token for a new token pair
+///
+/// The refresh token is single-use: the response contains a new refresh token
+/// that replaces it. Presenting an already-used refresh token revokes every
+/// token issued from the same login.
+#[utoipa::path(
+    post,
+    path = "/auth/refresh",
+    tag = "auth",
+    request_body = RefreshRequest,
+    responses(
+        (status = 200, description = "Tokens refreshed", body = AuthResponse),
+        (status = 400, description = "Validation error", body = ApiError),
+        (status = 401, description = "Invalid, expired or reused refresh token", body = ApiError)
+    )
+)]
+pub async fn refresh(
+    State(state): State<AppState>,
+    Json(payload): Json<RefreshRequest>,
+) -> Result<Json<AuthResponse>, ApiError> {
+    // Validate input
+    payload.validate()?;
+
+    // Call auth service
+    let auth_service = AuthService::new(&state);
+    let tokens = auth_service.refresh(&payload.refresh_token).await?;
+
     Ok(Json(AuthResponse {
         success: true,
-        data: AuthData {
-            token,
-            token_type: "Bearer".to_string(),
-            expires_in: state.config.jwt_expiration_hours * 3600,
-        },
+        data: tokens.into(),
     }))
 }
+
+/// Log out of the current session
+///
+/// Revokes the presented access token and every refresh token issued from the
+/// same login.
+#[utoipa::path(
+    post,
+    path = "/auth/logout",
+    tag = "auth",
+    responses(
+        (status = 204, description = "Logged out"),
+        (status = 401, description = "Unauthorized", body = ApiError)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn logout(State(state): State<AppState>, user: AuthUser) -> Result<StatusCode, ApiError> {
+    let session_service = SessionService::new(&state);
+    session_service.logout(user.session()?).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Log out of all sessions
+///
+/// Revokes every access and refresh token issued to the current user.
+#[utoipa::path(
+    post,
+    path = "/auth/logout-all",
+    tag = "auth",
+    responses(
+        (status = 204, description = "Logged out of all sessions"),
+        (status = 401, description = "Unauthorized", body = ApiError)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn logout_all(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<StatusCode, ApiError> {
+    let session_service = SessionService::new(&state);
+    session_service.logout_all(user.session()?).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Request a password reset email
+///
+/// Always responds the same way, whether or not the email belongs to an account.
+#[utoipa::path(
+    post,
+    path = "/auth/forgot-password",
+    tag = "auth",
+    request_body = ForgotPasswordRequest,
+    responses(
+        (status = 202, description = "Reset email sent if the account exists", body = MessageResponse),
+        (status = 400, description = "Validation error", body = ApiError)
+    )
+)]
+pub async fn forgot_password(
+    State(state): State<AppState>,
+    Json(payload): Json<ForgotPasswordRequest>,
+) -> Result<(StatusCode, Json<MessageResponse>), ApiError> {
+    // Validate input
+    payload.validate()?;
+
+    let reset_service = PasswordResetService::new(&state);
+    reset_service.request_reset(&payload.email).await?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(MessageResponse::new(
+            "If an account exists for this email, a password reset link has been sent",
+        )),
+    ))
+}
+
+/// Reset password with a token from the reset email
+///
+/// Tokens are single-use and expire. A successful reset logs the user out of
+/// all sessions.
+#[utoipa::path(
+    post,
+    path = "/auth/reset-password",
+    tag = "auth",
+    request_body = ResetPasswordRequest,
+    responses(
+        (status = 200, description = "Password reset", body = MessageResponse),
+        (status = 400, description = "Validation error, password policy violation or invalid token", body = ApiError)
+    )
+)]
+pub async fn reset_password(
+    State(state): State<AppState>,
+    Json(payload): Json<ResetPasswordRequest>,
+) -> Result<Json<MessageResponse>, ApiError> {
+    // Validate input
+    payload.validate()?;
+
+    let reset_service = PasswordResetService::new(&state);
+    reset_service
+        .reset_password(&payload.token, &payload.password)
+        .await?;
+
+    Ok(Json(MessageResponse::new("Password has been reset")))
+}
+
+/// Verify an email address with a token from the verification email
+#[utoipa::path(
+    post,
+    path = "/auth/verify-email",
+    tag = "auth",
+    request_body = VerifyEmailRequest,
+    responses(
+        (status = 200, description = "Email verified", body = MessageResponse),
+        (status = 400, description = "Validation error or invalid token", body = ApiError)
+    )
+)]
+pub async fn verify_email(
+    State(state): State<AppState>,
+    Json(payload): Json<VerifyEmailRequest>,
+) -> Result<Json<MessageResponse>, ApiError> {
+    // Validate input
+    payload.validate()?;
+
+    let verification_service = EmailVerificationService::new(&state);
+    verification_service.verify(&payload.token).await?;
+
+    Ok(Json(MessageResponse::new("Email address verified")))
+}
+
+/// Resend the email verification link
+///
+/// Always responds the same way, whether or not the email belongs to an
+/// unverified account. Requests for the same address are throttled.
+#[utoipa::path(
+    post,
+    path = "/auth/resend-verification",
+    tag = "auth",
+    request_body = ResendVerificationRequest,
+    responses(
+        (status = 202, description = "Verification email sent if the account is unverified", body = MessageResponse),
+        (status = 400, description = "Validation error", body = ApiError),
+        (status = 429, description = "Requested too recently; see Retry-After", body = ApiError)
+    )
+)]
+pub async fn resend_verification(
+    State(state): State<AppState>,
+    Json(payload): Json<ResendVerificationRequest>,
+) -> Result<(StatusCode, Json<MessageResponse>), ApiError> {
+    // Validate input
+    payload.validate()?;
+
+    let verification_service = EmailVerificationService::new(&state);
+    verification_service.resend(&payload.email).await?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(MessageResponse::new(
+            "If this email belongs to an unverified account, a verification link has been sent",
+        )),
+    ))
+}
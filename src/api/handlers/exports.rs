@@ -0,0 +1,202 @@
+//! Personal data export handlers
+
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::{
+    api::{error::ApiError, extractors::AuthUser},
+    config::AppState,
+    domain::{
+        models::{DataExport, ExportFormat, ExportStatus},
+        services::ExportService,
+    },
+};
+
+// ============================================================================
+// Request/Response DTOs
+// ============================================================================
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RequestExportRequest {
+    /// Archive format (default `json`)
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DownloadQuery {
+    /// Expiry of the link, in Unix seconds
+    pub expires: i64,
+    pub signature: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DataExportResponse {
+    pub success: bool,
+    pub data: DataExportData,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DataExportData {
+    pub id: Uuid,
+    pub format: ExportFormat,
+    pub status: ExportStatus,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    /// When the archive is deleted
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Signed link to the archive, present once it is ready; works without
+    /// authentication until `expires_at`
+    #[schema(example = "/api/v1/exports/3f1c.../download?expires=1700000000&signature=...")]
+    pub download_url: Option<String>,
+}
+
+impl DataExportData {
+    fn new(export: DataExport, export_service: &ExportService<'_>) -> Self {
+        Self {
+            download_url: export_service.download_url(&export),
+            id: export.id,
+            format: export.format,
+            status: export.status,
+            created_at: export.created_at,
+            completed_at: export.completed_at,
+            expires_at: export.expires_at,
+        }
+    }
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+/// Request a personal data export
+///
+/// Starts assembling a copy of everything stored about the current user:
+/// profile, roles, sessions, API key metadata, two-factor status and audit log
+/// entries. Poll the export until its status is `ready`, then follow its
+/// `download_url`. While an export is being assembled, requesting another
+/// returns it.
+#[utoipa::path(
+    post,
+    path = "/users/me/export",
+    tag = "exports",
+    request_body = RequestExportRequest,
+    responses(
+        (status = 202, description = "Export started", body = DataExportResponse),
+        (status = 401, description = "Unauthorized", body = ApiError),
+        (status = 403, description = "Called with an API key", body = ApiError)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn request_export(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(payload): Json<RequestExportRequest>,
+) -> Result<(StatusCode, Json<DataExportResponse>), ApiError> {
+    user.session()?;
+
+    let export_service = ExportService::new(&state);
+    let export = export_service.request(user.user_id, payload.format).await?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(DataExportResponse {
+            success: true,
+            data: DataExportData::new(export, &export_service),
+        }),
+    ))
+}
+
+/// Get a personal data export
+///
+/// Reports the status of an export and, once it is ready, a signed download
+/// link.
+#[utoipa::path(
+    get,
+    path = "/users/me/export/{id}",
+    tag = "exports",
+    params(
+        ("id" = Uuid, Path, description = "Export ID")
+    ),
+    responses(
+        (status = 200, description = "Export status", body = DataExportResponse),
+        (status = 401, description = "Unauthorized", body = ApiError),
+        (status = 403, description = "Called with an API key", body = ApiError),
+        (status = 404, description = "Export not found or expired", body = ApiError)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn get_export(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<DataExportResponse>, ApiError> {
+    user.session()?;
+
+    let export_service = ExportService::new(&state);
+    let export = export_service.get(user.user_id, id).await?;
+
+    Ok(Json(DataExportResponse {
+        success: true,
+        data: DataExportData::new(export, &export_service),
+    }))
+}
+
+/// Download a personal data export
+///
+/// Authorized by the signature of the link returned as `download_url`, not by
+/// a session.
+#[utoipa::path(
+    get,
+    path = "/exports/{id}/download",
+    tag = "exports",
+    params(
+        ("id" = Uuid, Path, description = "Export ID"),
+        DownloadQuery
+    ),
+    responses(
+        (status = 200, description = "The archive, as JSON or ZIP", content(
+            (Vec<u8> = "application/json"),
+            (Vec<u8> = "application/zip")
+        )),
+        (status = 403, description = "Invalid or expired link", body = ApiError),
+        (status = 404, description = "Export not found or expired", body = ApiError)
+    )
+)]
+pub async fn download_export(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<DownloadQuery>,
+) -> Result<Response, ApiError> {
+    let export_service = ExportService::new(&state);
+    let download = export_service
+        .download(id, query.expires, &query.signature)
+        .await?;
+
+    let headers = [
+        (
+            header::CONTENT_TYPE,
+            download.export.format.content_type().to_string(),
+        ),
+        (
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", download.file_name()),
+        ),
+        (header::CACHE_CONTROL, "no-store".to_string()),
+    ];
+
+    Ok((headers, download.archive).into_response())
+}
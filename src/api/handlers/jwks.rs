@@ -0,0 +1,41 @@
+//! JSON Web Key Set handler
+
+use axum::{Json, extract::State, http::header::CACHE_CONTROL, response::IntoResponse};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::config::AppState;
+
+/// How long clients may cache the key set, in seconds
+const JWKS_MAX_AGE_SECS: u64 = 300;
+
+/// JWK Set (RFC 7517)
+#[derive(Serialize, ToSchema)]
+pub struct JwksResponse {
+    /// Public keys, each identified by its `kid`
+    #[schema(value_type = Vec<Object>)]
+    pub keys: Vec<serde_json::Value>,
+}
+
+/// Public token verification keys
+///
+/// Served at the root as `/.well-known/jwks.json`, outside the `/api/v1` prefix.
+/// Retired keys stay listed while tokens signed by them may still be valid.
+/// Empty when tokens are signed with a shared HS256 secret.
+#[utoipa::path(
+    get,
+    path = "/.well-known/jwks.json",
+    tag = "system",
+    responses(
+        (status = 200, description = "JSON Web Key Set", body = JwksResponse)
+    )
+)]
+pub async fn jwks(State(state): State<AppState>) -> impl IntoResponse {
+    (
+        [(
+            CACHE_CONTROL,
+            format!("public, max-age={JWKS_MAX_AGE_SECS}"),
+        )],
+        Json(state.jwt_keys.jwks().clone()),
+    )
+}
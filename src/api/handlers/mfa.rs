@@ -0,0 +1,215 @@
+//! Two-factor authentication handlers
+
+use axum::{Json, extract::State};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::{
+    api::{
+        error::ApiError,
+        extractors::AuthUser,
+        handlers::auth::{AuthResponse, MessageResponse},
+    },
+    config::AppState,
+    domain::services::MfaService,
+};
+
+// ============================================================================
+// Request/Response DTOs
+// ============================================================================
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct MfaCodeRequest {
+    /// 6-digit code from the authenticator app, or a recovery code where accepted
+    #[validate(length(min = 1, message = "Code is required"))]
+    #[schema(example = "123456")]
+    pub code: String,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct MfaVerifyRequest {
+    /// Token returned by `/auth/login`
+    #[validate(length(min = 1, message = "MFA token is required"))]
+    pub mfa_token: String,
+    /// 6-digit code from the authenticator app, or an unused recovery code
+    #[validate(length(min = 1, message = "Code is required"))]
+    #[schema(example = "123456")]
+    pub code: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MfaEnrollResponse {
+    pub success: bool,
+    pub data: MfaEnrollData,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MfaEnrollData {
+    /// Base32-encoded secret, for manual entry
+    #[schema(example = "JBSWY3DPEHPK3PXPJBSWY3DPEHPK3PXP")]
+    pub secret: String,
+    /// Provisioning URI, usually rendered as a QR code
+    #[schema(
+        example = "otpauth://totp/Axum%20API:user%40example%2Ecom?secret=JBSWY3DPEHPK3PXPJBSWY3DPEHPK3PXP&issuer=Axum%20API&algorithm=SHA1&digits=6&period=30"
+    )]
+    pub otpauth_uri: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MfaRecoveryCodesResponse {
+    pub success: bool,
+    pub data: MfaRecoveryCodesData,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MfaRecoveryCodesData {
+    /// Single-use codes for when the authenticator is unavailable; shown only once
+    #[schema(example = json!(["abcde-fghij", "kmnpq-rstuv"]))]
+    pub recovery_codes: Vec<String>,
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+/// Start two-factor authentication enrollment
+///
+/// Returns a new TOTP secret. Two-factor authentication is not enabled until
+/// the enrollment is confirmed with a valid code.
+#[utoipa::path(
+    post,
+    path = "/auth/mfa/enroll",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Enrollment started", body = MfaEnrollResponse),
+        (status = 401, description = "Unauthorized", body = ApiError),
+        (status = 409, description = "Two-factor authentication already enabled", body = ApiError)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn enroll(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<MfaEnrollResponse>, ApiError> {
+    user.session()?;
+
+    let mfa_service = MfaService::new(&state);
+    let enrollment = mfa_service.enroll(user.user_id).await?;
+
+    Ok(Json(MfaEnrollResponse {
+        success: true,
+        data: MfaEnrollData {
+            secret: enrollment.secret,
+            otpauth_uri: enrollment.otpauth_uri,
+        },
+    }))
+}
+
+/// Confirm two-factor authentication enrollment
+///
+/// Enables two-factor authentication and returns one-time recovery codes.
+#[utoipa::path(
+    post,
+    path = "/auth/mfa/confirm",
+    tag = "auth",
+    request_body = MfaCodeRequest,
+    responses(
+        (status = 200, description = "Two-factor authentication enabled", body = MfaRecoveryCodesResponse),
+        (status = 400, description = "Enrollment not started", body = ApiError),
+        (status = 401, description = "Unauthorized or invalid code", body = ApiError),
+        (status = 409, description = "Two-factor authentication already enabled", body = ApiError),
+        (status = 429, description = "Too many invalid codes", body = ApiError)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn confirm(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(payload): Json<MfaCodeRequest>,
+) -> Result<Json<MfaRecoveryCodesResponse>, ApiError> {
+    user.session()?;
+
+    // Validate input
+    payload.validate()?;
+
+    let mfa_service = MfaService::new(&state);
+    let recovery_codes = mfa_service.confirm(user.user_id, &payload.code).await?;
+
+    Ok(Json(MfaRecoveryCodesResponse {
+        success: true,
+        data: MfaRecoveryCodesData { recovery_codes },
+    }))
+}
+
+/// Complete a two-factor login
+///
+/// Exchanges the `mfa_token` from `/auth/login` and a TOTP or recovery code
+/// for a token pair.
+#[utoipa::path(
+    post,
+    path = "/auth/mfa/verify",
+    tag = "auth",
+    request_body = MfaVerifyRequest,
+    responses(
+        (status = 200, description = "Login successful", body = AuthResponse),
+        (status = 400, description = "Validation error", body = ApiError),
+        (status = 401, description = "Invalid MFA token or code", body = ApiError),
+        (status = 429, description = "Too many invalid codes", body = ApiError)
+    )
+)]
+pub async fn verify(
+    State(state): State<AppState>,
+    Json(payload): Json<MfaVerifyRequest>,
+) -> Result<Json<AuthResponse>, ApiError> {
+    // Validate input
+    payload.validate()?;
+
+    let mfa_service = MfaService::new(&state);
+    let tokens = mfa_service
+        .verify_login(&payload.mfa_token, &payload.code)
+        .await?;
+
+    Ok(Json(AuthResponse {
+        success: true,
+        data: tokens.into(),
+    }))
+}
+
+/// Disable two-factor authentication
+#[utoipa::path(
+    post,
+    path = "/auth/mfa/disable",
+    tag = "auth",
+    request_body = MfaCodeRequest,
+    responses(
+        (status = 200, description = "Two-factor authentication disabled", body = MessageResponse),
+        (status = 400, description = "Two-factor authentication not enabled", body = ApiError),
+        (status = 401, description = "Unauthorized or invalid code", body = ApiError),
+        (status = 429, description = "Too many invalid codes", body = ApiError)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn disable(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(payload): Json<MfaCodeRequest>,
+) -> Result<Json<MessageResponse>, ApiError> {
+    user.session()?;
+
+    // Validate input
+    payload.validate()?;
+
+    let mfa_service = MfaService::new(&state);
+    mfa_service.disable(user.user_id, &payload.code).await?;
+
+    Ok(Json(MessageResponse::new(
+        "Two-factor authentication disabled",
+    )))
+}
@@ -2,6 +2,12 @@
 //!
 //! Each handler module corresponds to a feature/resource.
 
+pub mod admin;
+pub mod api_keys;
 pub mod auth;
+pub mod exports;
 pub mod health;
+pub mod jwks;
+pub mod mfa;
+pub mod organizations;
 pub mod users;
@@ -0,0 +1,614 @@
+//! Organization handlers
+
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    api::{
+        error::ApiError,
+        extractors::{AuthUser, CurrentOrg},
+        handlers::auth::AuthResponse,
+    },
+    config::AppState,
+    domain::{
+        models::{Invitation, Member, OrgRole, OrganizationMembership},
+        services::{AuthService, OrganizationService},
+    },
+};
+
+// ============================================================================
+// Request/Response DTOs
+// ============================================================================
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CreateOrganizationRequest {
+    #[validate(length(min = 1, max = 100, message = "Name must be 1-100 characters"))]
+    #[schema(example = "Acme Inc.")]
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct SwitchOrganizationRequest {
+    /// Refresh token of the session to switch; it is rotated like on refresh
+    #[validate(length(min = 1, message = "Refresh token is required"))]
+    #[schema(example = "q3Jb0Yc9uKp2gX7m1o5vZ8wE4rT6yU0iA2sD3fG5hJk")]
+    pub refresh_token: String,
+    /// Organization to switch to; `null` leaves the current one
+    pub organization_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateMemberRequest {
+    pub role: OrgRole,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct InviteMemberRequest {
+    #[validate(email(message = "Invalid email format"))]
+    #[schema(example = "colleague@example.com")]
+    pub email: String,
+    pub role: OrgRole,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct AcceptInvitationRequest {
+    /// Token from the invitation email
+    #[validate(length(min = 1, message = "Token is required"))]
+    pub token: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OrganizationData {
+    pub id: Uuid,
+    #[schema(example = "Acme Inc.")]
+    pub name: String,
+    /// The caller's role in the organization
+    pub role: OrgRole,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<OrganizationMembership> for OrganizationData {
+    fn from(membership: OrganizationMembership) -> Self {
+        Self {
+            id: membership.organization.id,
+            name: membership.organization.name,
+            role: membership.role,
+            created_at: membership.organization.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OrganizationResponse {
+    pub success: bool,
+    pub data: OrganizationData,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OrganizationListResponse {
+    pub success: bool,
+    pub data: Vec<OrganizationData>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MemberData {
+    pub user_id: Uuid,
+    #[schema(example = "user@example.com")]
+    pub email: String,
+    #[schema(example = "John Doe")]
+    pub name: String,
+    pub role: OrgRole,
+    /// When the user joined the organization
+    pub joined_at: DateTime<Utc>,
+}
+
+impl From<Member> for MemberData {
+    fn from(member: Member) -> Self {
+        Self {
+            user_id: member.membership.user_id,
+            email: member.email,
+            name: member.name,
+            role: member.membership.role,
+            joined_at: member.membership.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MemberResponse {
+    pub success: bool,
+    pub data: MemberData,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MemberListResponse {
+    pub success: bool,
+    pub data: Vec<MemberData>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct InvitationData {
+    pub id: Uuid,
+    pub organization_id: Uuid,
+    #[schema(example = "colleague@example.com")]
+    pub email: String,
+    pub role: OrgRole,
+    pub invited_by: Option<Uuid>,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<Invitation> for InvitationData {
+    fn from(invitation: Invitation) -> Self {
+        Self {
+            id: invitation.id,
+            organization_id: invitation.org_id,
+            email: invitation.email,
+            role: invitation.role,
+            invited_by: invitation.invited_by,
+            expires_at: invitation.expires_at,
+            created_at: invitation.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct InvitationResponse {
+    pub success: bool,
+    pub data: InvitationData,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct InvitationListResponse {
+    pub success: bool,
+    pub data: Vec<InvitationData>,
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+/// Create an organization
+///
+/// The caller becomes its owner. Switch to it with `POST /orgs/switch`.
+#[utoipa::path(
+    post,
+    path = "/orgs",
+    tag = "organizations",
+    request_body = CreateOrganizationRequest,
+    responses(
+        (status = 201, description = "Organization created", body = OrganizationResponse),
+        (status = 400, description = "Validation error", body = ApiError),
+        (status = 401, description = "Unauthorized", body = ApiError),
+        (status = 403, description = "Called with an API key", body = ApiError)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn create_organization(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(payload): Json<CreateOrganizationRequest>,
+) -> Result<(StatusCode, Json<OrganizationResponse>), ApiError> {
+    user.session()?;
+
+    // Validate input
+    payload.validate()?;
+
+    let organization_service = OrganizationService::new(&state);
+    let organization = organization_service
+        .create(user.user_id, payload.name)
+        .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(OrganizationResponse {
+            success: true,
+            data: organization.into(),
+        }),
+    ))
+}
+
+/// List the caller's organizations
+#[utoipa::path(
+    get,
+    path = "/orgs",
+    tag = "organizations",
+    responses(
+        (status = 200, description = "Organizations the caller belongs to", body = OrganizationListResponse),
+        (status = 401, description = "Unauthorized", body = ApiError)
+    ),
+    security(
+        ("jwt" = []),
+        ("api_key" = [])
+    )
+)]
+pub async fn list_organizations(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<OrganizationListResponse>, ApiError> {
+    let organization_service = OrganizationService::new(&state);
+    let organizations = organization_service.list_for_user(user.user_id).await?;
+
+    Ok(Json(OrganizationListResponse {
+        success: true,
+        data: organizations.into_iter().map(Into::into).collect(),
+    }))
+}
+
+/// Switch the session to an organization
+///
+/// Rotates the session's refresh token and returns tokens whose `org_id`
+/// claim names the organization; `/orgs/current` endpoints act on it.
+/// Refreshing keeps the organization until the caller leaves it. Pass
+/// `organization_id: null` to leave the organization context.
+#[utoipa::path(
+    post,
+    path = "/orgs/switch",
+    tag = "organizations",
+    request_body = SwitchOrganizationRequest,
+    responses(
+        (status = 200, description = "Tokens for the organization", body = AuthResponse),
+        (status = 400, description = "Validation error", body = ApiError),
+        (status = 401, description = "Unauthorized, or invalid refresh token", body = ApiError),
+        (status = 403, description = "Called with an API key", body = ApiError),
+        (status = 404, description = "Not a member of the organization", body = ApiError)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn switch_organization(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(payload): Json<SwitchOrganizationRequest>,
+) -> Result<Json<AuthResponse>, ApiError> {
+    user.session()?;
+
+    // Validate input
+    payload.validate()?;
+
+    let auth_service = AuthService::new(&state);
+    let tokens = auth_service
+        .switch_organization(
+            user.user_id,
+            &payload.refresh_token,
+            payload.organization_id,
+        )
+        .await?;
+
+    Ok(Json(AuthResponse {
+        success: true,
+        data: tokens.into(),
+    }))
+}
+
+/// Get the current organization
+#[utoipa::path(
+    get,
+    path = "/orgs/current",
+    tag = "organizations",
+    responses(
+        (status = 200, description = "Current organization", body = OrganizationResponse),
+        (status = 401, description = "Unauthorized", body = ApiError),
+        (status = 403, description = "No organization selected", body = ApiError),
+        (status = 404, description = "No longer a member of the organization", body = ApiError)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn get_current_organization(
+    State(state): State<AppState>,
+    org: CurrentOrg,
+) -> Result<Json<OrganizationResponse>, ApiError> {
+    let organization_service = OrganizationService::new(&state);
+    let organization = organization_service.get(&org.membership).await?;
+
+    Ok(Json(OrganizationResponse {
+        success: true,
+        data: OrganizationMembership {
+            organization,
+            role: org.membership.role,
+        }
+        .into(),
+    }))
+}
+
+/// List members of the current organization
+#[utoipa::path(
+    get,
+    path = "/orgs/current/members",
+    tag = "organizations",
+    responses(
+        (status = 200, description = "Members", body = MemberListResponse),
+        (status = 401, description = "Unauthorized", body = ApiError),
+        (status = 403, description = "No organization selected", body = ApiError),
+        (status = 404, description = "No longer a member of the organization", body = ApiError)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn list_members(
+    State(state): State<AppState>,
+    org: CurrentOrg,
+) -> Result<Json<MemberListResponse>, ApiError> {
+    let organization_service = OrganizationService::new(&state);
+    let members = organization_service.list_members(&org.membership).await?;
+
+    Ok(Json(MemberListResponse {
+        success: true,
+        data: members.into_iter().map(Into::into).collect(),
+    }))
+}
+
+/// Get a member of the current organization
+///
+/// Users outside the organization are reported as not found.
+#[utoipa::path(
+    get,
+    path = "/orgs/current/members/{user_id}",
+    tag = "organizations",
+    params(
+        ("user_id" = Uuid, Path, description = "User ID")
+    ),
+    responses(
+        (status = 200, description = "Member", body = MemberResponse),
+        (status = 401, description = "Unauthorized", body = ApiError),
+        (status = 403, description = "No organization selected", body = ApiError),
+        (status = 404, description = "Member not found", body = ApiError)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn get_member(
+    State(state): State<AppState>,
+    org: CurrentOrg,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<MemberResponse>, ApiError> {
+    let organization_service = OrganizationService::new(&state);
+    let member = organization_service
+        .get_member(&org.membership, user_id)
+        .await?;
+
+    Ok(Json(MemberResponse {
+        success: true,
+        data: member.into(),
+    }))
+}
+
+/// Change a member's role
+///
+/// Owners and admins manage members; only owners can grant or take away
+/// ownership. The organization always keeps at least one owner.
+#[utoipa::path(
+    patch,
+    path = "/orgs/current/members/{user_id}",
+    tag = "organizations",
+    params(
+        ("user_id" = Uuid, Path, description = "User ID")
+    ),
+    request_body = UpdateMemberRequest,
+    responses(
+        (status = 200, description = "Member updated", body = MemberResponse),
+        (status = 401, description = "Unauthorized", body = ApiError),
+        (status = 403, description = "No organization selected or role too low", body = ApiError),
+        (status = 404, description = "Member not found", body = ApiError),
+        (status = 409, description = "Would leave the organization without an owner", body = ApiError)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn update_member(
+    State(state): State<AppState>,
+    org: CurrentOrg,
+    Path(user_id): Path<Uuid>,
+    Json(payload): Json<UpdateMemberRequest>,
+) -> Result<Json<MemberResponse>, ApiError> {
+    org.session()?;
+
+    let organization_service = OrganizationService::new(&state);
+    let member = organization_service
+        .update_member_role(&org.membership, user_id, payload.role)
+        .await?;
+
+    Ok(Json(MemberResponse {
+        success: true,
+        data: member.into(),
+    }))
+}
+
+/// Remove a member
+///
+/// Members can remove themselves to leave the organization.
+#[utoipa::path(
+    delete,
+    path = "/orgs/current/members/{user_id}",
+    tag = "organizations",
+    params(
+        ("user_id" = Uuid, Path, description = "User ID")
+    ),
+    responses(
+        (status = 204, description = "Member removed"),
+        (status = 401, description = "Unauthorized", body = ApiError),
+        (status = 403, description = "No organization selected or role too low", body = ApiError),
+        (status = 404, description = "Member not found", body = ApiError),
+        (status = 409, description = "Would leave the organization without an owner", body = ApiError)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn remove_member(
+    State(state): State<AppState>,
+    org: CurrentOrg,
+    Path(user_id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    org.session()?;
+
+    let organization_service = OrganizationService::new(&state);
+    organization_service
+        .remove_member(&org.membership, user_id)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Invite someone to the current organization
+///
+/// Emails a link to accept the invitation, replacing any pending invitation
+/// for the same address.
+#[utoipa::path(
+    post,
+    path = "/orgs/current/invitations",
+    tag = "organizations",
+    request_body = InviteMemberRequest,
+    responses(
+        (status = 201, description = "Invitation sent", body = InvitationResponse),
+        (status = 400, description = "Validation error", body = ApiError),
+        (status = 401, description = "Unauthorized", body = ApiError),
+        (status = 403, description = "No organization selected or role too low", body = ApiError),
+        (status = 404, description = "No longer a member of the organization", body = ApiError),
+        (status = 409, description = "Already a member", body = ApiError)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn create_invitation(
+    State(state): State<AppState>,
+    org: CurrentOrg,
+    Json(payload): Json<InviteMemberRequest>,
+) -> Result<(StatusCode, Json<InvitationResponse>), ApiError> {
+    org.session()?;
+
+    // Validate input
+    payload.validate()?;
+
+    let organization_service = OrganizationService::new(&state);
+    let invitation = organization_service
+        .invite(&org.membership, &payload.email, payload.role)
+        .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(InvitationResponse {
+            success: true,
+            data: invitation.into(),
+        }),
+    ))
+}
+
+/// List pending invitations of the current organization
+#[utoipa::path(
+    get,
+    path = "/orgs/current/invitations",
+    tag = "organizations",
+    responses(
+        (status = 200, description = "Pending invitations", body = InvitationListResponse),
+        (status = 401, description = "Unauthorized", body = ApiError),
+        (status = 403, description = "No organization selected or role too low", body = ApiError),
+        (status = 404, description = "No longer a member of the organization", body = ApiError)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn list_invitations(
+    State(state): State<AppState>,
+    org: CurrentOrg,
+) -> Result<Json<InvitationListResponse>, ApiError> {
+    let organization_service = OrganizationService::new(&state);
+    let invitations = organization_service
+        .list_invitations(&org.membership)
+        .await?;
+
+    Ok(Json(InvitationListResponse {
+        success: true,
+        data: invitations.into_iter().map(Into::into).collect(),
+    }))
+}
+
+/// Withdraw a pending invitation
+#[utoipa::path(
+    delete,
+    path = "/orgs/current/invitations/{id}",
+    tag = "organizations",
+    params(
+        ("id" = Uuid, Path, description = "Invitation ID")
+    ),
+    responses(
+        (status = 204, description = "Invitation withdrawn"),
+        (status = 401, description = "Unauthorized", body = ApiError),
+        (status = 403, description = "No organization selected or role too low", body = ApiError),
+        (status = 404, description = "Invitation not found", body = ApiError)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn revoke_invitation(
+    State(state): State<AppState>,
+    org: CurrentOrg,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    org.session()?;
+
+    let organization_service = OrganizationService::new(&state);
+    organization_service
+        .revoke_invitation(&org.membership, id)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Accept an invitation
+///
+/// The invitation must have been sent to the caller's email address.
+#[utoipa::path(
+    post,
+    path = "/orgs/invitations/accept",
+    tag = "organizations",
+    request_body = AcceptInvitationRequest,
+    responses(
+        (status = 200, description = "Joined the organization", body = OrganizationResponse),
+        (status = 400, description = "Invalid or expired invitation", body = ApiError),
+        (status = 401, description = "Unauthorized", body = ApiError),
+        (status = 403, description = "Called with an API key", body = ApiError)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn accept_invitation(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(payload): Json<AcceptInvitationRequest>,
+) -> Result<Json<OrganizationResponse>, ApiError> {
+    user.session()?;
+
+    // Validate input
+    payload.validate()?;
+
+    let organization_service = OrganizationService::new(&state);
+    let organization = organization_service
+        .accept_invitation(user.user_id, &payload.token)
+        .await?;
+
+    Ok(Json(OrganizationResponse {
+        success: true,
+        data: organization.into(),
+    }))
+}
@@ -1,23 +1,35 @@
 //! User handlers
 
 use axum::{
-    Extension, Json,
-    extract::{Path, State},
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
 };
-use serde::Serialize;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+use validator::Validate;
 
 use crate::{
-    api::error::ApiError,
+    api::{
+        error::ApiError,
+        extractors::{AuthUser, RequirePermission, UsersRead},
+        handlers::auth::AuthResponse,
+        pagination::{PaginatedResponse, PaginationQuery},
+    },
+    common::pagination::SortDirection,
     config::AppState,
-    domain::{models::User, services::UserService},
+    domain::{
+        models::{User, UserFilter, UserSortField},
+        services::{AuthService, UserSearchHit, UserService},
+    },
 };
 
 // ============================================================================
-// Response DTOs
+// Request/Response DTOs
 // ============================================================================
 
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 
 #[derive(Debug, Serialize, ToSchema)]
 pub struct UserResponse {
@@ -32,7 +44,9 @@ pub struct UserData {
     pub email: String,
     #[schema(example = "John Doe")]
     pub name: String,
-    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub is_active: bool,
+    pub email_verified_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
 }
 
 impl From<User> for UserData {
@@ -41,11 +55,140 @@ impl From<User> for UserData {
             id: user.id,
             email: user.email,
             name: user.name,
+            is_active: user.is_active,
+            email_verified_at: user.email_verified_at,
             created_at: user.created_at,
         }
     }
 }
 
+/// Filters and sort order of the user listing
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListUsersQuery {
+    /// Only active (`true`) or deactivated (`false`) users; omit for both
+    pub active: Option<bool>,
+    /// Only users created at or after this time
+    pub created_after: Option<DateTime<Utc>>,
+    /// Only users created before this time
+    pub created_before: Option<DateTime<Utc>>,
+    /// Only users whose email address is at this domain
+    #[validate(length(min = 1, max = 255, message = "Email domain must be 1-255 characters"))]
+    #[param(example = "example.com")]
+    pub email_domain: Option<String>,
+    /// Field to sort by (default `created_at`)
+    pub sort_by: Option<UserSortField>,
+    /// Sort order (default `desc`)
+    pub order: Option<SortDirection>,
+}
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SearchUsersQuery {
+    /// Part of the name or email address
+    #[validate(length(min = 1, max = 100, message = "Query must be 1-100 characters"))]
+    #[param(example = "somchai")]
+    pub q: String,
+    /// Only active (`true`) or deactivated (`false`) users; omit for both
+    pub active: Option<bool>,
+    /// Maximum number of results, 1-50 (default 10)
+    #[validate(range(min = 1, max = 50, message = "Limit must be 1-50"))]
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UserSearchResponse {
+    pub success: bool,
+    pub data: Vec<UserSearchHitData>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UserSearchHitData {
+    #[serde(flatten)]
+    pub user: UserData,
+    /// Relevance; higher is a better match
+    #[schema(example = 1.42)]
+    pub rank: f32,
+    /// Fields containing a search term
+    pub highlights: Vec<FieldHighlightData>,
+}
+
+/// A field split into parts that matched the query and parts that did not
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FieldHighlightData {
+    /// `name` or `email`
+    #[schema(example = "name")]
+    pub field: String,
+    /// The field's full text, in order
+    pub fragments: Vec<HighlightFragmentData>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HighlightFragmentData {
+    #[schema(example = "chai")]
+    pub text: String,
+    pub matched: bool,
+}
+
+impl From<UserSearchHit> for UserSearchHitData {
+    fn from(hit: UserSearchHit) -> Self {
+        Self {
+            user: hit.user.into(),
+            rank: hit.rank,
+            highlights: hit
+                .highlights
+                .into_iter()
+                .map(|(field, fragments)| FieldHighlightData {
+                    field: field.to_string(),
+                    fragments: fragments
+                        .into_iter()
+                        .map(|fragment| HighlightFragmentData {
+                            text: fragment.text,
+                            matched: fragment.matched,
+                        })
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Default number of search results
+const DEFAULT_SEARCH_LIMIT: i64 = 10;
+
+/// Fields left out are not changed
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct UpdateProfileRequest {
+    #[validate(length(min = 1, message = "Name is required"))]
+    #[schema(example = "John Doe")]
+    pub name: Option<String>,
+    /// A new address replaces the current one once verified
+    #[validate(email(message = "Invalid email format"))]
+    #[schema(example = "user@example.com")]
+    pub email: Option<String>,
+    /// Current password, required to change the email address
+    #[schema(example = "correct-horse-battery")]
+    pub current_password: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct DeleteAccountRequest {
+    /// Current password, to confirm the deletion
+    #[validate(length(min = 1, message = "Password is required"))]
+    #[schema(example = "correct-horse-battery")]
+    pub password: String,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct ChangePasswordRequest {
+    #[validate(length(min = 1, message = "Current password is required"))]
+    #[schema(example = "correct-horse-battery")]
+    pub current_password: String,
+    /// Checked against the password policy
+    #[schema(example = "correct-horse-staple")]
+    pub new_password: String,
+}
+
 // ============================================================================
 // Handlers
 // ============================================================================
@@ -60,15 +203,165 @@ impl From<User> for UserData {
         (status = 401, description = "Unauthorized")
     ),
     security(
-        ("jwt" = [])
+        ("jwt" = []),
+        ("api_key" = [])
     )
 )]
 pub async fn get_current_user(
     State(state): State<AppState>,
-    Extension(user_id): Extension<Uuid>,
+    user: AuthUser,
+) -> Result<Json<UserResponse>, ApiError> {
+    let user_service = UserService::new(&state);
+    let user = user_service.get_by_id(user.user_id).await?;
+
+    Ok(Json(UserResponse {
+        success: true,
+        data: user.into(),
+    }))
+}
+
+/// List users
+///
+/// Paged with `next_cursor` by default; pass `offset` instead of `cursor` to
+/// jump to a position. A cursor only continues a listing with the same sort.
+/// Requires the `users:read` permission.
+#[utoipa::path(
+    get,
+    path = "/users",
+    tag = "users",
+    params(ListUsersQuery, PaginationQuery),
+    responses(
+        (status = 200, description = "A page of users", body = PaginatedResponse<UserData>),
+        (status = 400, description = "Validation error or invalid cursor", body = ApiError),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Email address not verified or missing permission")
+    ),
+    security(
+        ("jwt" = ["users:read"]),
+        ("api_key" = ["users:read"])
+    )
+)]
+pub async fn list_users(
+    State(state): State<AppState>,
+    _: RequirePermission<UsersRead>,
+    Query(query): Query<ListUsersQuery>,
+    Query(pagination): Query<PaginationQuery>,
+) -> Result<Json<PaginatedResponse<UserData>>, ApiError> {
+    // Validate input
+    query.validate()?;
+    let include_total = pagination.include_total;
+    let page = pagination.page_request()?;
+
+    let filter = UserFilter {
+        active: query.active,
+        created_after: query.created_after,
+        created_before: query.created_before,
+        email_domain: query.email_domain,
+        ..UserFilter::default()
+    };
+    let user_service = UserService::new(&state);
+    let users = user_service
+        .list(
+            &filter,
+            query.sort_by.unwrap_or_default(),
+            query.order.unwrap_or_default(),
+            page,
+            include_total,
+        )
+        .await?;
+
+    Ok(Json(PaginatedResponse::from_page(users)))
+}
+
+/// Search users
+///
+/// Finds users by whole words, word prefixes, similar spellings or any part of
+/// their name or email, including inside Thai names written without spaces.
+/// Results are ranked best first and list the matched parts of each field.
+/// Requires the `users:read` permission.
+#[utoipa::path(
+    get,
+    path = "/users/search",
+    tag = "users",
+    params(SearchUsersQuery),
+    responses(
+        (status = 200, description = "Matching users, best first", body = UserSearchResponse),
+        (status = 400, description = "Validation error", body = ApiError),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Email address not verified or missing permission")
+    ),
+    security(
+        ("jwt" = ["users:read"]),
+        ("api_key" = ["users:read"])
+    )
+)]
+pub async fn search_users(
+    State(state): State<AppState>,
+    _: RequirePermission<UsersRead>,
+    Query(query): Query<SearchUsersQuery>,
+) -> Result<Json<UserSearchResponse>, ApiError> {
+    // Validate input
+    query.validate()?;
+
+    let user_service = UserService::new(&state);
+    let hits = user_service
+        .search(
+            &query.q,
+            &UserFilter {
+                active: query.active,
+                ..UserFilter::default()
+            },
+            query.limit.unwrap_or(DEFAULT_SEARCH_LIMIT),
+        )
+        .await?;
+
+    Ok(Json(UserSearchResponse {
+        success: true,
+        data: hits.into_iter().map(Into::into).collect(),
+    }))
+}
+
+/// Update current user's profile
+///
+/// Changing the email address requires the current password. A confirmation
+/// link is sent to the new address, which replaces the current one once the
+/// link is followed; the current address is notified of the request.
+#[utoipa::path(
+    patch,
+    path = "/users/me",
+    tag = "users",
+    request_body = UpdateProfileRequest,
+    responses(
+        (status = 200, description = "Updated profile, with the email unchanged until verified", body = UserResponse),
+        (status = 400, description = "Validation error or wrong password", body = ApiError),
+        (status = 401, description = "Unauthorized", body = ApiError),
+        (status = 403, description = "Called with an API key", body = ApiError),
+        (status = 409, description = "Email already in use", body = ApiError),
+        (status = 423, description = "Account locked after too many wrong passwords", body = ApiError)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn update_current_user(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(payload): Json<UpdateProfileRequest>,
 ) -> Result<Json<UserResponse>, ApiError> {
+    user.session()?;
+
+    // Validate input
+    payload.validate()?;
+
     let user_service = UserService::new(&state);
-    let user = user_service.get_by_id(user_id).await?;
+    let user = user_service
+        .update_profile(
+            user.user_id,
+            payload.name,
+            payload.email,
+            payload.current_password.as_deref(),
+        )
+        .await?;
 
     Ok(Json(UserResponse {
         success: true,
@@ -76,7 +369,95 @@ pub async fn get_current_user(
     }))
 }
 
+/// Delete current user's account
+///
+/// Requires the account password. The account is deactivated and all of its
+/// sessions and API keys are revoked. An administrator can restore it until it
+/// is purged after the grace period.
+#[utoipa::path(
+    delete,
+    path = "/users/me",
+    tag = "users",
+    request_body = DeleteAccountRequest,
+    responses(
+        (status = 204, description = "Account deleted"),
+        (status = 400, description = "Validation error or wrong password", body = ApiError),
+        (status = 401, description = "Unauthorized", body = ApiError),
+        (status = 403, description = "Called with an API key", body = ApiError),
+        (status = 409, description = "Only owner of an organization", body = ApiError),
+        (status = 423, description = "Account locked after too many wrong passwords", body = ApiError)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn delete_current_user(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(payload): Json<DeleteAccountRequest>,
+) -> Result<StatusCode, ApiError> {
+    let claims = user.session()?;
+
+    // Validate input
+    payload.validate()?;
+
+    let user_service = UserService::new(&state);
+    user_service
+        .delete_account(claims, &payload.password)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Change password
+///
+/// Requires the current password. All existing sessions, including this one,
+/// are ended and access tokens issued before the change are rejected; the
+/// response contains tokens for a new session.
+#[utoipa::path(
+    put,
+    path = "/users/me/password",
+    tag = "users",
+    request_body = ChangePasswordRequest,
+    responses(
+        (status = 200, description = "Password changed", body = AuthResponse),
+        (status = 400, description = "Validation error, password policy violation or wrong current password", body = ApiError),
+        (status = 401, description = "Unauthorized", body = ApiError),
+        (status = 403, description = "Called with an API key", body = ApiError),
+        (status = 423, description = "Account locked after too many wrong passwords", body = ApiError)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn change_password(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(payload): Json<ChangePasswordRequest>,
+) -> Result<Json<AuthResponse>, ApiError> {
+    user.session()?;
+
+    // Validate input
+    payload.validate()?;
+
+    let auth_service = AuthService::new(&state);
+    let tokens = auth_service
+        .change_password(
+            user.user_id,
+            &payload.current_password,
+            &payload.new_password,
+        )
+        .await?;
+
+    Ok(Json(AuthResponse {
+        success: true,
+        data: tokens.into(),
+    }))
+}
+
 /// Get user by ID
+///
+/// Requires the `users:read` permission.
 #[utoipa::path(
     get,
     path = "/users/{id}",
@@ -87,14 +468,17 @@ pub async fn get_current_user(
     responses(
         (status = 200, description = "User details", body = UserResponse),
         (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Email address not verified or missing permission"),
         (status = 404, description = "User not found")
     ),
     security(
-        ("jwt" = [])
+        ("jwt" = ["users:read"]),
+        ("api_key" = ["users:read"])
     )
 )]
 pub async fn get_user_by_id(
     State(state): State<AppState>,
+    _: RequirePermission<UsersRead>,
     Path(id): Path<Uuid>,
 ) -> Result<Json<UserResponse>, ApiError> {
     let user_service = UserService::new(&state);
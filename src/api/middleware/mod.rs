@@ -1,3 +1,3 @@
 //! Custom middleware
 
-pub mod auth;
+pub mod verified;
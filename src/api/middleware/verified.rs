@@ -0,0 +1,28 @@
+//! Email verification middleware
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::{
+    api::{error::ApiError, extractors::AuthUser},
+    config::AppState,
+    domain::services::EmailVerificationService,
+};
+
+/// Email verification middleware
+/// Authenticates the request and rejects users who have not verified their email
+pub async fn require_verified_email(
+    State(state): State<AppState>,
+    user: AuthUser,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    EmailVerificationService::new(&state)
+        .ensure_verified(user.user_id)
+        .await?;
+
+    Ok(next.run(request).await)
+}
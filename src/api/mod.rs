@@ -4,12 +4,15 @@
 //! - Route definitions
 //! - Request handlers
 //! - Middleware (authentication, logging, etc.)
+//! - Extractors (permission guards)
 //! - Error handling
 
 pub mod docs;
 pub mod error;
+pub mod extractors;
 pub mod handlers;
 pub mod middleware;
+pub mod pagination;
 pub mod routes;
 
 pub use routes::create_router;
@@ -0,0 +1,83 @@
+//! Pagination query parameters and response envelope for list endpoints
+
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
+
+use crate::{
+    api::error::ApiError,
+    common::pagination::{Page, PageRequest},
+};
+
+/// Page size when the client does not ask for one
+const DEFAULT_LIMIT: i64 = 20;
+
+/// Paging parameters accepted by every list endpoint
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PaginationQuery {
+    /// `next_cursor` of the previous page; omit for the first page
+    pub cursor: Option<String>,
+    /// Number of items to skip, for offset paging instead of a cursor
+    #[validate(range(min = 0, message = "Offset must not be negative"))]
+    pub offset: Option<i64>,
+    /// Page size, 1-100 (default 20)
+    #[validate(range(min = 1, max = 100, message = "Limit must be 1-100"))]
+    pub limit: Option<i64>,
+    /// Also count all matching items (slower)
+    #[serde(default)]
+    pub include_total: bool,
+}
+
+impl PaginationQuery {
+    /// Validate the parameters and pick cursor or offset paging
+    pub fn page_request(self) -> Result<PageRequest, ApiError> {
+        self.validate()?;
+
+        let limit = self.limit.unwrap_or(DEFAULT_LIMIT);
+        match (self.cursor, self.offset) {
+            (Some(_), Some(_)) => Err(ApiError::bad_request(
+                "Use either cursor or offset pagination, not both",
+            )
+            .with_code("VALIDATION_ERROR")),
+            (None, Some(offset)) => Ok(PageRequest::Offset { offset, limit }),
+            (after, None) => Ok(PageRequest::Cursor { after, limit }),
+        }
+    }
+}
+
+/// Where a page sits in the list
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PaginationMeta {
+    /// Pass as `cursor` to get the next page; absent on the last page
+    #[schema(example = "eyJzb3J0IjoiY3JlYXRlZF9hdDpkZXNjIn0")]
+    pub next_cursor: Option<String>,
+    pub has_more: bool,
+    /// Number of matching items, when `include_total` was requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = 42)]
+    pub total: Option<i64>,
+}
+
+/// Envelope of a list response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PaginatedResponse<T> {
+    pub success: bool,
+    pub data: Vec<T>,
+    pub pagination: PaginationMeta,
+}
+
+impl<T> PaginatedResponse<T> {
+    /// Respond with a page, converting each item to its response type
+    pub fn from_page<U: Into<T>>(page: Page<U>) -> Self {
+        Self {
+            success: true,
+            data: page.items.into_iter().map(Into::into).collect(),
+            pagination: PaginationMeta {
+                next_cursor: page.next_cursor,
+                has_more: page.has_more,
+                total: page.total,
+            },
+        }
+    }
+}
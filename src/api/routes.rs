@@ -4,7 +4,7 @@
 
 use axum::{
     Router, middleware,
-    routing::{get, post},
+    routing::{delete, get, post, put},
 };
 
 use utoipa::OpenApi;
@@ -13,32 +13,120 @@ use utoipa_swagger_ui::SwaggerUi;
 use crate::config::AppState;
 
 use super::docs::ApiDoc;
-use super::handlers::{auth, health, users};
-use super::middleware::auth::auth_middleware;
+use super::handlers::{admin, api_keys, auth, exports, health, jwks, mfa, organizations, users};
+use super::middleware::verified::require_verified_email;
 
 /// Create the main application router
 pub fn create_router(state: AppState) -> Router {
-    // Public routes (no authentication required)
-    let public_routes = Router::new()
+    // Handlers that need a caller take an `AuthUser` (or `RequirePermission`)
+    // extractor, so public and authenticated routes share one router
+    let api_routes = Router::new()
         .route("/health", get(health::health_check))
         .route("/auth/register", post(auth::register))
-        .route("/auth/login", post(auth::login));
-
-    // Protected routes (authentication required)
-    let protected_routes = Router::new()
-        .route("/users/me", get(users::get_current_user))
-        .route("/users/{id}", get(users::get_user_by_id))
-        .layer(middleware::from_fn_with_state(
-            state.clone(),
-            auth_middleware,
-        ));
+        .route("/auth/login", post(auth::login))
+        .route("/auth/refresh", post(auth::refresh))
+        .route("/auth/logout", post(auth::logout))
+        .route("/auth/logout-all", post(auth::logout_all))
+        .route("/auth/forgot-password", post(auth::forgot_password))
+        .route("/auth/reset-password", post(auth::reset_password))
+        .route("/auth/verify-email", post(auth::verify_email))
+        .route("/auth/resend-verification", post(auth::resend_verification))
+        .route("/auth/mfa/verify", post(mfa::verify))
+        .route("/auth/mfa/enroll", post(mfa::enroll))
+        .route("/auth/mfa/confirm", post(mfa::confirm))
+        .route("/auth/mfa/disable", post(mfa::disable))
+        .route(
+            "/users/me",
+            get(users::get_current_user)
+                .patch(users::update_current_user)
+                .delete(users::delete_current_user),
+        )
+        .route("/users/me/password", put(users::change_password))
+        .route(
+            "/users/me/api-keys",
+            get(api_keys::list_api_keys).post(api_keys::create_api_key),
+        )
+        .route("/users/me/api-keys/{id}", delete(api_keys::revoke_api_key))
+        .route("/users/me/export", post(exports::request_export))
+        .route("/users/me/export/{id}", get(exports::get_export))
+        .route("/exports/{id}/download", get(exports::download_export))
+        .route(
+            "/orgs",
+            get(organizations::list_organizations).post(organizations::create_organization),
+        )
+        .route("/orgs/switch", post(organizations::switch_organization))
+        .route(
+            "/orgs/invitations/accept",
+            post(organizations::accept_invitation),
+        )
+        .route(
+            "/orgs/current",
+            get(organizations::get_current_organization),
+        )
+        .route("/orgs/current/members", get(organizations::list_members))
+        .route(
+            "/orgs/current/members/{user_id}",
+            get(organizations::get_member)
+                .patch(organizations::update_member)
+                .delete(organizations::remove_member),
+        )
+        .route(
+            "/orgs/current/invitations",
+            get(organizations::list_invitations).post(organizations::create_invitation),
+        )
+        .route(
+            "/orgs/current/invitations/{id}",
+            delete(organizations::revoke_invitation),
+        )
+        // Routes restricted to users with a verified email
+        .route(
+            "/users",
+            get(users::list_users).route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                require_verified_email,
+            )),
+        )
+        .route(
+            "/users/search",
+            get(users::search_users).route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                require_verified_email,
+            )),
+        )
+        .route(
+            "/users/{id}",
+            get(users::get_user_by_id).route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                require_verified_email,
+            )),
+        )
+        .nest("/admin", admin_routes(state.clone()));
 
     // Combine all routes under /api/v1 prefix
     Router::new()
         .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi()))
-        .nest(
-            "/api/v1",
-            Router::new().merge(public_routes).merge(protected_routes),
-        )
+        .route("/.well-known/jwks.json", get(jwks::jwks))
+        .nest("/api/v1", api_routes)
         .with_state(state)
 }
+
+/// Administration routes, all restricted to users with a verified email
+fn admin_routes(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/users", get(admin::list_users))
+        .route("/users/import", post(admin::import_users))
+        .route(
+            "/users/{id}",
+            get(admin::get_user)
+                .patch(admin::update_user)
+                .delete(admin::delete_user),
+        )
+        .route("/users/{id}/deactivate", post(admin::deactivate_user))
+        .route("/users/{id}/reactivate", post(admin::reactivate_user))
+        .route("/users/{id}/restore", post(admin::restore_user))
+        .route("/users/{id}/unlock", post(admin::unlock_user))
+        .route_layer(middleware::from_fn_with_state(
+            state,
+            require_verified_email,
+        ))
+}
@@ -0,0 +1,67 @@
+//! Symmetric encryption for secrets stored at rest (AES-256-GCM)
+
+use aes_gcm::{
+    Aes256Gcm, KeyInit, Nonce,
+    aead::{Aead, AeadCore, OsRng},
+};
+use base64::{Engine, engine::general_purpose::STANDARD};
+
+/// Nonce length for AES-GCM in bytes
+const NONCE_BYTES: usize = 12;
+
+/// Encrypt plaintext, returning base64 of `nonce || ciphertext`
+pub fn encrypt(key: &[u8; 32], plaintext: &[u8]) -> Result<String, CryptoError> {
+    let cipher = Aes256Gcm::new(key.into());
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| CryptoError::EncryptionFailed)?;
+
+    let mut payload = nonce.to_vec();
+    payload.extend_from_slice(&ciphertext);
+    Ok(STANDARD.encode(payload))
+}
+
+/// Decrypt a value produced by [`encrypt`]
+pub fn decrypt(key: &[u8; 32], encoded: &str) -> Result<Vec<u8>, CryptoError> {
+    let payload = STANDARD
+        .decode(encoded)
+        .map_err(|_| CryptoError::DecryptionFailed)?;
+    if payload.len() <= NONCE_BYTES {
+        return Err(CryptoError::DecryptionFailed);
+    }
+
+    let (nonce, ciphertext) = payload.split_at(NONCE_BYTES);
+    let cipher = Aes256Gcm::new(key.into());
+
+    cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| CryptoError::DecryptionFailed)
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CryptoError {
+    #[error("Encryption failed")]
+    EncryptionFailed,
+    #[error("Decryption failed")]
+    DecryptionFailed,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_and_decrypt() {
+        let key = [7u8; 32];
+        let encrypted = encrypt(&key, b"secret").expect("Failed to encrypt");
+
+        assert_eq!(
+            decrypt(&key, &encrypted).expect("Failed to decrypt"),
+            b"secret"
+        );
+        assert!(decrypt(&[8u8; 32], &encrypted).is_err());
+        assert_ne!(encrypted, encrypt(&key, b"secret").unwrap());
+    }
+}
@@ -0,0 +1,132 @@
+//! Email address normalization
+//!
+//! Accounts are identified by email, so the same address must always map to
+//! the same account however it is typed. Addresses are normalized before they
+//! are stored or looked up, and the database compares them ignoring case.
+//!
+//! The local part is kept as typed apart from Unicode normalization: it is
+//! case-sensitive by the standard, and mail is delivered to it as stored.
+
+use unicode_normalization::UnicodeNormalization;
+
+/// Providers that ignore parts of the local part when delivering mail
+struct Provider {
+    domains: &'static [&'static str],
+    /// Domain all aliases are stored under, when the provider has several
+    canonical_domain: Option<&'static str>,
+    /// Dots in the local part are ignored
+    ignores_dots: bool,
+    /// Everything from this character on is a delivery tag
+    tag_separator: char,
+}
+
+const PROVIDERS: &[Provider] = &[
+    Provider {
+        domains: &["gmail.com", "googlemail.com"],
+        canonical_domain: Some("gmail.com"),
+        ignores_dots: true,
+        tag_separator: '+',
+    },
+    Provider {
+        domains: &["outlook.com", "hotmail.com", "live.com"],
+        canonical_domain: None,
+        ignores_dots: false,
+        tag_separator: '+',
+    },
+    Provider {
+        domains: &["icloud.com", "me.com", "mac.com"],
+        canonical_domain: None,
+        ignores_dots: false,
+        tag_separator: '+',
+    },
+    Provider {
+        domains: &["fastmail.com", "proton.me", "protonmail.com"],
+        canonical_domain: None,
+        ignores_dots: false,
+        tag_separator: '+',
+    },
+];
+
+/// Domains of the providers whose aliases [`normalize`] can fold together,
+/// lowercase
+pub fn provider_domains() -> Vec<&'static str> {
+    PROVIDERS
+        .iter()
+        .flat_map(|provider| provider.domains.iter().copied())
+        .collect()
+}
+
+/// Normalize an email address: trim it, compose it to Unicode NFC and
+/// lowercase the domain.
+///
+/// With `canonicalize_providers`, aliases of known providers are folded into
+/// one address as well (`J.Doe+news@googlemail.com` becomes `jdoe@gmail.com`).
+pub fn normalize(email: &str, canonicalize_providers: bool) -> String {
+    let email: String = email.trim().nfc().collect();
+    let Some((local, domain)) = email.rsplit_once('@') else {
+        return email;
+    };
+    let domain = domain.to_lowercase();
+
+    if canonicalize_providers
+        && let Some(provider) = PROVIDERS
+            .iter()
+            .find(|provider| provider.domains.contains(&domain.as_str()))
+    {
+        let local = local
+            .split(provider.tag_separator)
+            .next()
+            .unwrap_or_default();
+        let local = if provider.ignores_dots {
+            local.replace('.', "")
+        } else {
+            local.to_string()
+        };
+        // These providers match the local part ignoring case
+        let domain = provider.canonical_domain.unwrap_or(&domain);
+        return format!("{}@{domain}", local.to_lowercase());
+    }
+
+    format!("{local}@{domain}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(
+            normalize("  Jane.Doe@Example.COM ", false),
+            "Jane.Doe@example.com"
+        );
+        // "é" typed as "e" + combining acute accent
+        assert_eq!(
+            normalize("Jose\u{0301}@example.com", false),
+            "Jos\u{00E9}@example.com"
+        );
+        // Provider aliases are kept unless canonicalization is on
+        assert_eq!(
+            normalize("j.doe+news@gmail.com", false),
+            "j.doe+news@gmail.com"
+        );
+        assert_eq!(normalize("not-an-email", false), "not-an-email");
+    }
+
+    #[test]
+    fn test_normalize_providers() {
+        assert_eq!(
+            normalize("J.Doe+news@GoogleMail.com", true),
+            "jdoe@gmail.com"
+        );
+        assert_eq!(
+            normalize("j.doe+shop@outlook.com", true),
+            "j.doe@outlook.com"
+        );
+        // Other domains may treat dots and tags as significant
+        assert_eq!(
+            normalize("J.Doe+news@example.com", true),
+            "J.Doe+news@example.com"
+        );
+    }
+}
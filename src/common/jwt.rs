@@ -1,56 +1,200 @@
 //! JWT utilities
 
 use chrono::{Duration, Utc};
-use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, decode, encode};
-use serde::{Deserialize, Serialize};
+use jsonwebtoken::{
+    Algorithm, Header, Validation, decode, decode_header, encode, errors::ErrorKind,
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use uuid::Uuid;
 
+use super::jwt_keys::{JwtKeys, TokenPolicy};
+
 /// JWT Claims
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Claims {
     /// Subject (user ID)
     pub sub: Uuid,
+    /// JWT ID, used to revoke individual tokens
+    pub jti: Uuid,
+    /// Session ID (the refresh token family the token was issued from)
+    pub sid: Uuid,
+    /// Organization the session is switched to, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub org_id: Option<Uuid>,
+    /// Role names
+    #[serde(default)]
+    pub roles: Vec<String>,
+    /// Permission names granted through the roles
+    #[serde(default)]
+    pub perms: Vec<String>,
+    /// Issuer
+    pub iss: String,
+    /// Audience
+    pub aud: Vec<String>,
+    /// Issued at
+    pub iat: i64,
+    /// Issued at, in milliseconds, so revocations apply within the second of
+    /// `iat`. Absent from tokens issued before it was added.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iat_ms: Option<i64>,
+    /// Not before
+    pub nbf: i64,
+    /// Expiration
+    pub exp: i64,
+}
+
+impl Claims {
+    /// When the token was issued, in milliseconds since the epoch. Tokens
+    /// without `iat_ms` count from the start of their `iat` second.
+    pub fn issued_at_millis(&self) -> i64 {
+        self.iat_ms.unwrap_or(self.iat * 1000)
+    }
+}
+
+/// Claims of the short-lived token issued after a correct password when the
+/// account has two-factor authentication enabled
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MfaClaims {
+    /// Subject (user ID)
+    pub sub: Uuid,
+    /// JWT ID
+    pub jti: Uuid,
+    /// Issuer
+    pub iss: String,
+    /// Audience
+    pub aud: Vec<String>,
     /// Issued at
     pub iat: i64,
+    /// Not before
+    pub nbf: i64,
     /// Expiration
     pub exp: i64,
 }
 
+/// `typ` header of access tokens
+const ACCESS_TOKEN_TYPE: &str = "JWT";
+/// `typ` header of MFA pending tokens, so they are never accepted as access tokens
+const MFA_TOKEN_TYPE: &str = "mfa+jwt";
+
 /// Create a new JWT token
 pub fn create_token(
     user_id: Uuid,
-    secret: &str,
-    expiration_hours: i64,
+    session_id: Uuid,
+    org_id: Option<Uuid>,
+    roles: Vec<String>,
+    permissions: Vec<String>,
+    keys: &JwtKeys,
+    expiration_minutes: i64,
 ) -> Result<String, JwtError> {
     let now = Utc::now();
-    let exp = now + Duration::hours(expiration_hours);
+    let exp = now + Duration::minutes(expiration_minutes);
 
     let claims = Claims {
         sub: user_id,
+        jti: Uuid::new_v4(),
+        sid: session_id,
+        org_id,
+        roles,
+        perms: permissions,
+        iss: keys.policy().issuer.clone(),
+        aud: keys.policy().audiences.clone(),
         iat: now.timestamp(),
+        iat_ms: Some(now.timestamp_millis()),
+        nbf: now.timestamp(),
         exp: exp.timestamp(),
     };
 
-    encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(secret.as_bytes()),
-    )
-    .map_err(|_| JwtError::TokenCreationFailed)
+    encode_typed(&claims, keys, ACCESS_TOKEN_TYPE)
 }
 
 /// Verify and decode a JWT token
-pub fn verify_token(token: &str, secret: &str) -> Result<Claims, JwtError> {
-    decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(secret.as_bytes()),
-        &Validation::default(),
-    )
-    .map(|data| data.claims)
-    .map_err(|err| match err.kind() {
-        jsonwebtoken::errors::ErrorKind::ExpiredSignature => JwtError::TokenExpired,
-        _ => JwtError::InvalidToken,
-    })
+pub fn verify_token(token: &str, keys: &JwtKeys) -> Result<Claims, JwtError> {
+    decode_typed(token, keys, ACCESS_TOKEN_TYPE)
+}
+
+/// Create a token proving the password step of a two-factor login succeeded
+pub fn create_mfa_token(
+    user_id: Uuid,
+    keys: &JwtKeys,
+    expiration_minutes: i64,
+) -> Result<String, JwtError> {
+    let now = Utc::now();
+    let exp = now + Duration::minutes(expiration_minutes);
+
+    let claims = MfaClaims {
+        sub: user_id,
+        jti: Uuid::new_v4(),
+        iss: keys.policy().issuer.clone(),
+        aud: keys.policy().audiences.clone(),
+        iat: now.timestamp(),
+        nbf: now.timestamp(),
+        exp: exp.timestamp(),
+    };
+
+    encode_typed(&claims, keys, MFA_TOKEN_TYPE)
+}
+
+/// Verify and decode an MFA pending token
+pub fn verify_mfa_token(token: &str, keys: &JwtKeys) -> Result<MfaClaims, JwtError> {
+    decode_typed(token, keys, MFA_TOKEN_TYPE)
+}
+
+/// Sign a token with the active key, setting the `kid` and `typ` headers
+fn encode_typed<T: Serialize>(
+    claims: &T,
+    keys: &JwtKeys,
+    token_type: &str,
+) -> Result<String, JwtError> {
+    let (kid, algorithm, encoding_key) = keys.signing_key();
+
+    let header = Header {
+        typ: Some(token_type.to_string()),
+        kid: Some(kid.to_string()),
+        ..Header::new(algorithm)
+    };
+
+    encode(&header, claims, encoding_key).map_err(|_| JwtError::TokenCreationFailed)
+}
+
+/// Decode a token with the key named by its `kid` header, requiring its `typ`
+/// header to match
+fn decode_typed<T: DeserializeOwned>(
+    token: &str,
+    keys: &JwtKeys,
+    token_type: &str,
+) -> Result<T, JwtError> {
+    let header = decode_header(token).map_err(|_| JwtError::InvalidToken)?;
+    let (algorithm, decoding_key) = keys
+        .decoding_key(header.kid.as_deref())
+        .ok_or(JwtError::InvalidToken)?;
+
+    // The algorithm comes from the key, never from the token header
+    let data = decode::<T>(token, decoding_key, &validation(*algorithm, keys.policy())).map_err(
+        |err| match err.kind() {
+            ErrorKind::ExpiredSignature => JwtError::TokenExpired,
+            ErrorKind::ImmatureSignature => JwtError::TokenNotYetValid,
+            ErrorKind::InvalidIssuer => JwtError::InvalidIssuer,
+            ErrorKind::InvalidAudience => JwtError::InvalidAudience,
+            _ => JwtError::InvalidToken,
+        },
+    )?;
+
+    if data.header.typ.as_deref() != Some(token_type) {
+        return Err(JwtError::InvalidToken);
+    }
+
+    Ok(data.claims)
+}
+
+/// Validation requiring `exp`, `nbf`, `iss` and `aud` to match the policy
+fn validation(algorithm: Algorithm, policy: &TokenPolicy) -> Validation {
+    let mut validation = Validation::new(algorithm);
+    validation.leeway = policy.leeway_secs;
+    validation.validate_nbf = true;
+    validation.set_required_spec_claims(&["exp", "nbf", "iss", "aud"]);
+    validation.set_issuer(&[&policy.issuer]);
+    validation.set_audience(&policy.audiences);
+    validation
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -61,4 +205,125 @@ pub enum JwtError {
     InvalidToken,
     #[error("Token has expired")]
     TokenExpired,
+    #[error("Token is not valid yet")]
+    TokenNotYetValid,
+    #[error("Token was issued by an unexpected issuer")]
+    InvalidIssuer,
+    #[error("Token is not intended for this audience")]
+    InvalidAudience,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_types_are_not_interchangeable() {
+        let keys = JwtKeys::hmac("default", b"secret");
+        let user_id = Uuid::new_v4();
+        let access =
+            create_token(user_id, Uuid::new_v4(), None, vec![], vec![], &keys, 15).unwrap();
+        let mfa = create_mfa_token(user_id, &keys, 5).unwrap();
+
+        assert_eq!(verify_token(&access, &keys).unwrap().sub, user_id);
+        assert_eq!(verify_mfa_token(&mfa, &keys).unwrap().sub, user_id);
+        assert!(verify_token(&mfa, &keys).is_err());
+        assert!(verify_mfa_token(&access, &keys).is_err());
+    }
+
+    #[test]
+    fn test_tokens_signed_by_other_keys_are_rejected() {
+        let keys = JwtKeys::hmac("default", b"secret");
+        let other = JwtKeys::hmac("default", b"other-secret");
+        let unknown_kid = JwtKeys::hmac("retired", b"secret");
+
+        let token = create_token(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            None,
+            vec![],
+            vec![],
+            &other,
+            15,
+        )
+        .unwrap();
+        assert!(verify_token(&token, &keys).is_err());
+
+        let token = create_token(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            None,
+            vec![],
+            vec![],
+            &unknown_kid,
+            15,
+        )
+        .unwrap();
+        assert!(verify_token(&token, &keys).is_err());
+    }
+
+    fn keys_with_policy(issuer: &str, audiences: &[&str]) -> JwtKeys {
+        JwtKeys::hmac("default", b"secret").with_policy(TokenPolicy {
+            issuer: issuer.to_string(),
+            audiences: audiences.iter().map(|a| a.to_string()).collect(),
+            leeway_secs: 30,
+        })
+    }
+
+    #[test]
+    fn test_issuer_and_audience_are_enforced() {
+        let keys = keys_with_policy("api-prod", &["web", "mobile"]);
+        let token = |keys: &JwtKeys| {
+            create_token(
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+                None,
+                vec![],
+                vec![],
+                keys,
+                15,
+            )
+            .unwrap()
+        };
+
+        // Any one shared audience is enough
+        let mobile = keys_with_policy("api-prod", &["mobile"]);
+        assert!(verify_token(&token(&mobile), &keys).is_ok());
+
+        let staging = keys_with_policy("api-staging", &["web"]);
+        assert!(matches!(
+            verify_token(&token(&staging), &keys),
+            Err(JwtError::InvalidIssuer)
+        ));
+
+        let admin = keys_with_policy("api-prod", &["admin"]);
+        assert!(matches!(
+            verify_token(&token(&admin), &keys),
+            Err(JwtError::InvalidAudience)
+        ));
+    }
+
+    #[test]
+    fn test_not_before_respects_leeway() {
+        let keys = JwtKeys::hmac("default", b"secret");
+        let now = Utc::now().timestamp();
+        let claims = |nbf: i64| MfaClaims {
+            sub: Uuid::new_v4(),
+            jti: Uuid::new_v4(),
+            iss: keys.policy().issuer.clone(),
+            aud: keys.policy().audiences.clone(),
+            iat: now,
+            nbf,
+            exp: now + 600,
+        };
+
+        let skewed = encode_typed(&claims(now + 10), &keys, MFA_TOKEN_TYPE).unwrap();
+        assert!(verify_mfa_token(&skewed, &keys).is_ok());
+
+        let future = encode_typed(&claims(now + 300), &keys, MFA_TOKEN_TYPE).unwrap();
+        assert!(matches!(
+            verify_mfa_token(&future, &keys),
+            Err(JwtError::TokenNotYetValid)
+        ));
+    }
 }
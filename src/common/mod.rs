@@ -1,5 +1,14 @@
 //! Common utilities shared across the application
 
+pub mod crypto;
+pub mod email;
 pub mod jwt;
+pub mod jwt_keys;
+pub mod pagination;
 pub mod password;
+pub mod password_policy;
+pub mod search;
+pub mod signing;
+pub mod token;
+pub mod totp;
 pub mod validation;
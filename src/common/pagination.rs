@@ -0,0 +1,193 @@
+//! Pagination primitives shared by list endpoints
+//!
+//! Lists are paged with an opaque cursor by default: it records the sort key
+//! and id of the last item returned, and the next page starts strictly after
+//! it. Unlike offsets, cursors don't skip or repeat items when rows are
+//! inserted between requests. Offset paging is still available for clients
+//! that need to jump to a page.
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Sort order of a list
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SortDirection {
+    Asc,
+    #[default]
+    Desc,
+}
+
+impl SortDirection {
+    /// SQL keyword for `ORDER BY`
+    pub fn sql(self) -> &'static str {
+        match self {
+            Self::Asc => "ASC",
+            Self::Desc => "DESC",
+        }
+    }
+
+    /// SQL comparison selecting rows that come after a cursor in this order
+    pub fn after_operator(self) -> &'static str {
+        match self {
+            Self::Asc => ">",
+            Self::Desc => "<",
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Asc => "asc",
+            Self::Desc => "desc",
+        }
+    }
+}
+
+/// Position after the last item of a page
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cursor {
+    /// Sort the cursor was created for, e.g. `created_at:desc`
+    sort: String,
+    /// Sort key of the last item, as text
+    pub value: String,
+    /// Id of the last item, breaking ties between equal sort keys
+    pub id: Uuid,
+}
+
+impl Cursor {
+    pub fn new(sort_field: &str, direction: SortDirection, value: String, id: Uuid) -> Self {
+        Self {
+            sort: sort_key(sort_field, direction),
+            value,
+            id,
+        }
+    }
+
+    /// Opaque, URL-safe form handed to clients
+    pub fn encode(&self) -> String {
+        let json = serde_json::to_vec(self).expect("Cursor serializes to JSON");
+        URL_SAFE_NO_PAD.encode(json)
+    }
+
+    /// Parse a cursor, checking it was created for the requested sort
+    pub fn decode(
+        encoded: &str,
+        sort_field: &str,
+        direction: SortDirection,
+    ) -> Result<Self, InvalidCursor> {
+        let cursor: Self = URL_SAFE_NO_PAD
+            .decode(encoded)
+            .ok()
+            .and_then(|json| serde_json::from_slice(&json).ok())
+            .ok_or(InvalidCursor)?;
+
+        if cursor.sort != sort_key(sort_field, direction) {
+            return Err(InvalidCursor);
+        }
+
+        Ok(cursor)
+    }
+}
+
+fn sort_key(sort_field: &str, direction: SortDirection) -> String {
+    format!("{sort_field}:{}", direction.name())
+}
+
+/// The cursor is malformed or belongs to a different sort
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid pagination cursor")]
+pub struct InvalidCursor;
+
+/// Which page of a list to return
+#[derive(Debug, Clone)]
+pub enum PageRequest {
+    /// Items after the cursor, or the first page when `None`
+    Cursor { after: Option<String>, limit: i64 },
+    /// Items after skipping `offset`
+    Offset { offset: i64, limit: i64 },
+}
+
+impl PageRequest {
+    pub fn limit(&self) -> i64 {
+        match self {
+            Self::Cursor { limit, .. } | Self::Offset { limit, .. } => *limit,
+        }
+    }
+}
+
+/// One page of a list
+#[derive(Debug)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Cursor for the next page, present when `has_more`
+    pub next_cursor: Option<String>,
+    pub has_more: bool,
+    /// Number of items in the whole list, when requested
+    pub total: Option<i64>,
+}
+
+impl<T> Page<T> {
+    /// Build a page from a query that fetched up to `limit + 1` items; the
+    /// extra item only tells whether there are more.
+    pub fn from_overfetched(
+        mut items: Vec<T>,
+        limit: i64,
+        cursor_of: impl Fn(&T) -> Cursor,
+        total: Option<i64>,
+    ) -> Self {
+        let limit = usize::try_from(limit).unwrap_or(0);
+        let has_more = items.len() > limit;
+        items.truncate(limit);
+        let next_cursor = if has_more {
+            items.last().map(|item| cursor_of(item).encode())
+        } else {
+            None
+        };
+
+        Self {
+            items,
+            next_cursor,
+            has_more,
+            total,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let id = Uuid::new_v4();
+        let cursor = Cursor::new("email", SortDirection::Asc, "a@example.com".to_string(), id);
+        let encoded = cursor.encode();
+
+        assert_eq!(
+            Cursor::decode(&encoded, "email", SortDirection::Asc).unwrap(),
+            cursor
+        );
+        // A cursor only continues the sort it was created for
+        assert!(Cursor::decode(&encoded, "email", SortDirection::Desc).is_err());
+        assert!(Cursor::decode(&encoded, "name", SortDirection::Asc).is_err());
+        assert!(Cursor::decode("not-a-cursor", "email", SortDirection::Asc).is_err());
+    }
+
+    #[test]
+    fn test_page_from_overfetched() {
+        let cursor_of = |n: &i32| Cursor::new("n", SortDirection::Asc, n.to_string(), Uuid::nil());
+
+        let page = Page::from_overfetched(vec![1, 2, 3], 2, cursor_of, None);
+        assert_eq!(page.items, vec![1, 2]);
+        assert!(page.has_more);
+        let next = Cursor::decode(&page.next_cursor.unwrap(), "n", SortDirection::Asc).unwrap();
+        assert_eq!(next.value, "2");
+
+        let last = Page::from_overfetched(vec![3], 2, cursor_of, Some(3));
+        assert!(!last.has_more);
+        assert_eq!(last.next_cursor, None);
+        assert_eq!(last.total, Some(3));
+    }
+}
@@ -1,48 +1,487 @@
 //! Password hashing utilities using Argon2
+//!
+//! New hashes are always Argon2. Hashes imported from older systems may also be
+//! bcrypt or PBKDF2-SHA256; they are accepted by [`verify`] and reported by
+//! [`HashParams::needs_rehash`] so they get replaced on the next login.
+//!
+//! Hashing is CPU- and memory-heavy, so the async functions run it on the
+//! blocking thread pool instead of stalling the runtime.
+
+use std::sync::Mutex;
 
 use argon2::{
-    Argon2,
-    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString, rand_core::OsRng},
+    Algorithm, Argon2, Params, Version,
+    password_hash::{
+        Output, PasswordHash, PasswordHasher, PasswordVerifier, SaltString, rand_core::OsRng,
+    },
 };
+use base64::{Engine, engine::general_purpose::STANDARD};
+use pbkdf2::Pbkdf2;
+use sha2::Sha256;
+
+// Imported hashes are checked on every login attempt, so arbitrary costs would
+// let a single hash tie up a blocking thread for minutes or exhaust memory.
+// Hashes above these limits are neither imported nor verified.
+
+/// Highest PBKDF2 iteration count accepted, Django's default as of 5.2
+pub const MAX_PBKDF2_ITERATIONS: u32 = 1_000_000;
+/// Highest bcrypt cost accepted; each step doubles the work, and 14 takes
+/// around a second
+pub const MAX_BCRYPT_COST: u32 = 14;
+/// Highest Argon2 memory cost accepted, in KiB (256 MiB)
+pub const MAX_ARGON2_MEMORY_KIB: u32 = 262_144;
+/// Highest number of Argon2 passes accepted
+pub const MAX_ARGON2_ITERATIONS: u32 = 10;
+/// Highest degree of Argon2 parallelism accepted
+pub const MAX_ARGON2_PARALLELISM: u32 = 16;
+
+/// Supported stored hash formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HashFormat {
+    /// PHC string, e.g. `$argon2id$v=19$m=19456,t=2,p=1$...`
+    Argon2,
+    /// Modular crypt format, e.g. `$2b$12$...`
+    Bcrypt,
+    /// PHC string, e.g. `$pbkdf2-sha256$i=600000,l=32$...`
+    Pbkdf2,
+    /// Django format: `pbkdf2_sha256$<iterations>$<salt>$<base64 hash>`
+    DjangoPbkdf2Sha256,
+}
+
+impl HashFormat {
+    fn detect(hash: &str) -> Option<Self> {
+        if ["$2a$", "$2b$", "$2x$", "$2y$"]
+            .iter()
+            .any(|prefix| hash.starts_with(prefix))
+        {
+            return Some(Self::Bcrypt);
+        }
+        if hash.starts_with("pbkdf2_sha256$") {
+            return Some(Self::DjangoPbkdf2Sha256);
+        }
+
+        let algorithm = PasswordHash::new(hash).ok()?.algorithm;
+        if Algorithm::try_from(algorithm).is_ok() {
+            Some(Self::Argon2)
+        } else if pbkdf2::Algorithm::try_from(algorithm).is_ok() {
+            Some(Self::Pbkdf2)
+        } else {
+            None
+        }
+    }
+
+    /// Whether verifying `hash` costs no more than we allow
+    fn within_cost_limit(self, hash: &str) -> bool {
+        match self {
+            Self::Argon2 => PasswordHash::new(hash)
+                .ok()
+                .and_then(|parsed| Params::try_from(&parsed).ok())
+                .is_some_and(|params| {
+                    argon2_within_cost_limit(params.m_cost(), params.t_cost(), params.p_cost())
+                }),
+            Self::Bcrypt => hash
+                .split('$')
+                .nth(2)
+                .and_then(|cost| cost.parse::<u32>().ok())
+                .is_some_and(|cost| cost <= MAX_BCRYPT_COST),
+            Self::Pbkdf2 => PasswordHash::new(hash)
+                .ok()
+                .and_then(|parsed| parsed.params.get_decimal("i"))
+                .is_none_or(|iterations| iterations <= MAX_PBKDF2_ITERATIONS),
+            Self::DjangoPbkdf2Sha256 => hash
+                .split('$')
+                .nth(1)
+                .and_then(|iterations| iterations.parse::<u32>().ok())
+                .is_none_or(|iterations| iterations <= MAX_PBKDF2_ITERATIONS),
+        }
+    }
+}
+
+fn argon2_within_cost_limit(memory_kib: u32, iterations: u32, parallelism: u32) -> bool {
+    memory_kib <= MAX_ARGON2_MEMORY_KIB
+        && iterations <= MAX_ARGON2_ITERATIONS
+        && parallelism <= MAX_ARGON2_PARALLELISM
+}
+
+/// Whether `hash` is in a format [`verify`] understands, at an affordable cost
+pub fn is_supported(hash: &str) -> bool {
+    HashFormat::detect(hash).is_some_and(|format| format.within_cost_limit(hash))
+}
+
+/// Argon2 variant and cost parameters for new hashes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HashParams {
+    pub algorithm: Algorithm,
+    /// Memory cost in KiB
+    pub memory_kib: u32,
+    /// Number of passes
+    pub iterations: u32,
+    /// Degree of parallelism
+    pub parallelism: u32,
+}
+
+impl Default for HashParams {
+    fn default() -> Self {
+        Self {
+            algorithm: Algorithm::Argon2id,
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+impl HashParams {
+    fn argon2(&self) -> Result<Argon2<'static>, PasswordError> {
+        let params = Params::new(self.memory_kib, self.iterations, self.parallelism, None)
+            .map_err(|_| PasswordError::InvalidParams)?;
+
+        Ok(Argon2::new(self.algorithm, Version::V0x13, params))
+    }
+
+    /// Check that the parameters are accepted by Argon2 and within the cost
+    /// limits hashes are verified up to
+    pub fn validate(&self) -> Result<(), PasswordError> {
+        if !argon2_within_cost_limit(self.memory_kib, self.iterations, self.parallelism) {
+            return Err(PasswordError::InvalidParams);
+        }
+        self.argon2().map(|_| ())
+    }
+
+    /// Whether `hash` is a legacy format or was created with a different variant
+    /// or weaker costs than these parameters. Unknown formats are left alone.
+    pub fn needs_rehash(&self, hash: &str) -> bool {
+        match HashFormat::detect(hash) {
+            Some(HashFormat::Argon2) => {}
+            Some(_) => return true,
+            None => return false,
+        }
+
+        let Ok(parsed) = PasswordHash::new(hash) else {
+            return false;
+        };
+        let Ok(params) = Params::try_from(&parsed) else {
+            return false;
+        };
+
+        parsed.algorithm != self.algorithm.ident()
+            || parsed.version != Some(Version::V0x13.into())
+            || params.m_cost() < self.memory_kib
+            || params.t_cost() < self.iterations
+            || params.p_cost() < self.parallelism
+    }
+}
 
 /// Hash a password using Argon2
-pub fn hash(password: &str) -> Result<String, PasswordError> {
+pub async fn hash(password: &str, params: &HashParams) -> Result<String, PasswordError> {
+    let password = password.to_string();
+    let params = *params;
+
+    tokio::task::spawn_blocking(move || hash_blocking(&password, &params))
+        .await
+        .map_err(|_| PasswordError::HashingFailed)?
+}
+
+/// Verify a password against a hash, using the parameters stored in the hash
+pub async fn verify(password: &str, hash: &str) -> Result<bool, PasswordError> {
+    let password = password.to_string();
+    let hash = hash.to_string();
+
+    tokio::task::spawn_blocking(move || verify_blocking(&password, &hash))
+        .await
+        .map_err(|_| PasswordError::HashingFailed)?
+}
+
+/// [`verify`] that takes at least as long as checking a current hash.
+///
+/// Hashes weaker than `params` (e.g. imported bcrypt or PBKDF2) verify faster
+/// than the dummy check run for unknown emails, so they are padded with one.
+pub async fn verify_padded(
+    password: &str,
+    hash: &str,
+    params: &HashParams,
+) -> Result<bool, PasswordError> {
+    if !params.needs_rehash(hash) {
+        return verify(password, hash).await;
+    }
+
+    let (verified, ()) = tokio::join!(verify(password, hash), verify_dummy(password, params));
+    verified
+}
+
+/// Spend the same time as [`verify`] on a password that cannot match.
+///
+/// Call when there is no stored hash to check against (e.g. unknown email)
+/// so the response time does not reveal whether the account exists.
+pub async fn verify_dummy(password: &str, params: &HashParams) {
+    let password = password.to_string();
+    let params = *params;
+
+    let _ = tokio::task::spawn_blocking(move || {
+        dummy_hash(&params).and_then(|hash| verify_blocking(&password, &hash))
+    })
+    .await;
+}
+
+fn hash_blocking(password: &str, params: &HashParams) -> Result<String, PasswordError> {
     let salt = SaltString::generate(&mut OsRng);
-    let argon2 = Argon2::default();
 
-    argon2
+    params
+        .argon2()?
         .hash_password(password.as_bytes(), &salt)
         .map(|hash| hash.to_string())
         .map_err(|_| PasswordError::HashingFailed)
 }
 
-/// Verify a password against a hash
-pub fn verify(password: &str, hash: &str) -> Result<bool, PasswordError> {
+fn verify_blocking(password: &str, hash: &str) -> Result<bool, PasswordError> {
+    let format = HashFormat::detect(hash).ok_or(PasswordError::InvalidHash)?;
+    if !format.within_cost_limit(hash) {
+        return Err(PasswordError::InvalidHash);
+    }
+
+    match format {
+        HashFormat::Argon2 => verify_phc(&Argon2::default(), password, hash),
+        HashFormat::Pbkdf2 => verify_phc(&Pbkdf2, password, hash),
+        HashFormat::Bcrypt => {
+            bcrypt::verify(password, hash).map_err(|_| PasswordError::InvalidHash)
+        }
+        HashFormat::DjangoPbkdf2Sha256 => verify_django_pbkdf2(password, hash),
+    }
+}
+
+fn verify_phc(
+    verifier: &impl PasswordVerifier,
+    password: &str,
+    hash: &str,
+) -> Result<bool, PasswordError> {
     let parsed_hash = PasswordHash::new(hash).map_err(|_| PasswordError::InvalidHash)?;
 
-    Ok(Argon2::default()
+    Ok(verifier
         .verify_password(password.as_bytes(), &parsed_hash)
         .is_ok())
 }
 
+fn verify_django_pbkdf2(password: &str, hash: &str) -> Result<bool, PasswordError> {
+    let mut parts = hash.split('$').skip(1);
+    let (Some(iterations), Some(salt), Some(expected), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(PasswordError::InvalidHash);
+    };
+    let iterations: u32 = iterations.parse().map_err(|_| PasswordError::InvalidHash)?;
+    let expected = STANDARD
+        .decode(expected)
+        .ok()
+        .and_then(|bytes| Output::new(&bytes).ok())
+        .ok_or(PasswordError::InvalidHash)?;
+
+    let mut derived = vec![0u8; expected.len()];
+    pbkdf2::pbkdf2_hmac::<Sha256>(
+        password.as_bytes(),
+        salt.as_bytes(),
+        iterations,
+        &mut derived,
+    );
+
+    // `Output` compares in constant time
+    Ok(Output::new(&derived).map_err(|_| PasswordError::InvalidHash)? == expected)
+}
+
+/// Hashes of a random password, one per parameter set in use
+static DUMMY_HASHES: Mutex<Vec<(HashParams, String)>> = Mutex::new(Vec::new());
+
+/// Hash of a random password created with `params`, computed once
+fn dummy_hash(params: &HashParams) -> Result<String, PasswordError> {
+    let mut hashes = DUMMY_HASHES.lock().unwrap_or_else(|err| err.into_inner());
+    if let Some((_, hash)) = hashes.iter().find(|(p, _)| p == params) {
+        return Ok(hash.clone());
+    }
+
+    let password = SaltString::generate(&mut OsRng);
+    let hash = hash_blocking(password.as_str(), params)?;
+    hashes.push((*params, hash.clone()));
+
+    Ok(hash)
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum PasswordError {
     #[error("Failed to hash password")]
     HashingFailed,
     #[error("Invalid password hash format")]
     InvalidHash,
+    #[error("Invalid Argon2 parameters")]
+    InvalidParams,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_hash_and_verify() {
+    /// Cheap parameters to keep tests fast
+    fn test_params() -> HashParams {
+        HashParams {
+            memory_kib: 1024,
+            iterations: 1,
+            ..HashParams::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hash_and_verify() {
         let password = "secure_password123";
-        let hashed = hash(password).expect("Failed to hash password");
+        let hashed = hash(password, &test_params())
+            .await
+            .expect("Failed to hash password");
+
+        assert!(verify(password, &hashed).await.expect("Failed to verify"));
+        assert!(
+            !verify("wrong_password", &hashed)
+                .await
+                .expect("Failed to verify")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dummy_hash_costs_the_same_as_real_hashes() {
+        let params = test_params();
+        let real = hash("secure_password123", &params)
+            .await
+            .expect("Failed to hash password");
+        let real = PasswordHash::new(&real).unwrap();
+        let dummy = dummy_hash(&params).unwrap();
+        let dummy = PasswordHash::new(&dummy).unwrap();
+
+        assert_eq!(dummy.algorithm, real.algorithm);
+        assert_eq!(dummy.version, real.version);
+        assert_eq!(dummy.params, real.params);
+        assert!(!verify_blocking("secure_password123", &dummy.to_string()).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_verify_legacy_formats() {
+        let bcrypt = bcrypt::hash("secure_password123", 4).unwrap();
+        // Format of Django's PBKDF2PasswordHasher
+        let django = "pbkdf2_sha256$1000$saltsalt$nmRtMaaws8W3ThsEvjnelfmKw8lOAXbrZLA52KMfwy4=";
+        let phc = pbkdf2::Pbkdf2
+            .hash_password_customized(
+                b"secure_password123",
+                Some(pbkdf2::Algorithm::Pbkdf2Sha256.ident()),
+                None,
+                pbkdf2::Params {
+                    rounds: 1000,
+                    output_length: 32,
+                },
+                &SaltString::generate(&mut OsRng),
+            )
+            .unwrap()
+            .to_string();
+
+        for legacy in [bcrypt.as_str(), django, phc.as_str()] {
+            assert!(is_supported(legacy));
+            assert!(verify("secure_password123", legacy).await.unwrap());
+            assert!(!verify("wrong_password", legacy).await.unwrap());
+            assert!(test_params().needs_rehash(legacy));
+        }
+
+        assert!(!is_supported("5f4dcc3b5aa765d61d8327deb882cf99"));
+        assert!(
+            verify("password", "5f4dcc3b5aa765d61d8327deb882cf99")
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rejects_excessive_pbkdf2_iterations() {
+        let django =
+            "pbkdf2_sha256$4000000000$saltsalt$nmRtMaaws8W3ThsEvjnelfmKw8lOAXbrZLA52KMfwy4=";
+        let phc = "$pbkdf2-sha256$i=4000000000,l=32$c2FsdHNhbHQ$bm1SdE1hYXdzOFczVGhzRXZqbmVsZm1LdzhsT0FYYnI";
+
+        for hash in [django, phc] {
+            assert!(!is_supported(hash));
+            assert!(verify("secure_password123", hash).await.is_err());
+        }
+        assert!(is_supported(
+            &django.replace("4000000000", &MAX_PBKDF2_ITERATIONS.to_string())
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_excessive_bcrypt_and_argon2_costs() {
+        let bcrypt = bcrypt::hash("secure_password123", 4).unwrap();
+        let expensive_bcrypt = bcrypt.replacen("$04$", "$31$", 1);
+        assert!(!is_supported(&expensive_bcrypt));
+        assert!(
+            verify("secure_password123", &expensive_bcrypt)
+                .await
+                .is_err()
+        );
+        assert!(is_supported(&bcrypt.replacen(
+            "$04$",
+            &format!("${MAX_BCRYPT_COST}$"),
+            1
+        )));
+
+        let argon2 = hash("secure_password123", &test_params()).await.unwrap();
+        assert!(is_supported(&argon2));
+        for (cheap, expensive) in [("m=1024", "m=4194304"), ("t=1", "t=1000"), ("p=1", "p=255")] {
+            let expensive_argon2 = argon2.replacen(cheap, expensive, 1);
+            assert_ne!(expensive_argon2, argon2);
+            assert!(!is_supported(&expensive_argon2), "{expensive}");
+            assert!(
+                verify("secure_password123", &expensive_argon2)
+                    .await
+                    .is_err()
+            );
+        }
+
+        // New hashes must stay verifiable
+        assert!(
+            HashParams {
+                memory_kib: MAX_ARGON2_MEMORY_KIB + 1,
+                ..HashParams::default()
+            }
+            .validate()
+            .is_err()
+        );
+        assert!(HashParams::default().validate().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_needs_rehash_when_policy_is_stronger() {
+        let weak = test_params();
+        let hashed = hash("secure_password123", &weak).await.unwrap();
 
-        assert!(verify(password, &hashed).expect("Failed to verify"));
-        assert!(!verify("wrong_password", &hashed).expect("Failed to verify"));
+        assert!(!weak.needs_rehash(&hashed));
+        assert!(
+            HashParams {
+                memory_kib: 2048,
+                ..weak
+            }
+            .needs_rehash(&hashed)
+        );
+        assert!(
+            HashParams {
+                iterations: 2,
+                ..weak
+            }
+            .needs_rehash(&hashed)
+        );
+        assert!(
+            HashParams {
+                algorithm: Algorithm::Argon2i,
+                ..weak
+            }
+            .needs_rehash(&hashed)
+        );
+        // Weaker policies never downgrade existing hashes
+        assert!(
+            !HashParams {
+                memory_kib: 512,
+                ..weak
+            }
+            .needs_rehash(&hashed)
+        );
     }
 }
@@ -0,0 +1,298 @@
+//! Password strength rules and the breached-password list
+
+use std::collections::HashSet;
+
+use sha1::{Digest, Sha1};
+
+/// Shortest name or email fragment checked by [`PasswordPolicy::reject_personal_info`]
+const MIN_PERSONAL_FRAGMENT_LEN: usize = 4;
+
+/// Rules a new password must satisfy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PasswordPolicy {
+    /// Minimum length in characters
+    pub min_length: usize,
+    /// Maximum length in characters
+    pub max_length: usize,
+    pub require_lowercase: bool,
+    pub require_uppercase: bool,
+    pub require_digit: bool,
+    /// Require a character that is neither a letter nor a digit
+    pub require_symbol: bool,
+    /// Longest allowed run of one repeated character; 0 allows any
+    pub max_repeated_chars: usize,
+    /// Reject passwords containing the email's local part or a word of the name
+    pub reject_personal_info: bool,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            max_length: 128,
+            require_lowercase: false,
+            require_uppercase: false,
+            require_digit: false,
+            require_symbol: false,
+            max_repeated_chars: 3,
+            reject_personal_info: true,
+        }
+    }
+}
+
+/// A password policy rule that a password failed
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PolicyViolation {
+    #[error("Password must be at least {min} characters")]
+    TooShort { min: usize },
+    #[error("Password must be at most {max} characters")]
+    TooLong { max: usize },
+    #[error("Password must contain a lowercase letter")]
+    MissingLowercase,
+    #[error("Password must contain an uppercase letter")]
+    MissingUppercase,
+    #[error("Password must contain a digit")]
+    MissingDigit,
+    #[error("Password must contain a symbol")]
+    MissingSymbol,
+    #[error("Password must not repeat a character more than {max} times in a row")]
+    RepeatedCharacters { max: usize },
+    #[error("Password must not contain your name or email")]
+    ContainsPersonalInfo,
+    #[error("Password has appeared in a data breach; choose another one")]
+    Breached,
+}
+
+impl PolicyViolation {
+    /// Machine-readable code of the rule
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::TooShort { .. } => "PASSWORD_TOO_SHORT",
+            Self::TooLong { .. } => "PASSWORD_TOO_LONG",
+            Self::MissingLowercase => "PASSWORD_MISSING_LOWERCASE",
+            Self::MissingUppercase => "PASSWORD_MISSING_UPPERCASE",
+            Self::MissingDigit => "PASSWORD_MISSING_DIGIT",
+            Self::MissingSymbol => "PASSWORD_MISSING_SYMBOL",
+            Self::RepeatedCharacters { .. } => "PASSWORD_REPEATED_CHARACTERS",
+            Self::ContainsPersonalInfo => "PASSWORD_CONTAINS_PERSONAL_INFO",
+            Self::Breached => "PASSWORD_BREACHED",
+        }
+    }
+}
+
+impl PasswordPolicy {
+    /// Every rule `password` breaks. `personal_info` holds the user's email and
+    /// name, which the password must not contain.
+    pub fn check(&self, password: &str, personal_info: &[&str]) -> Vec<PolicyViolation> {
+        let mut violations = Vec::new();
+        let length = password.chars().count();
+
+        if length < self.min_length {
+            violations.push(PolicyViolation::TooShort {
+                min: self.min_length,
+            });
+        }
+        if length > self.max_length {
+            violations.push(PolicyViolation::TooLong {
+                max: self.max_length,
+            });
+        }
+        if self.require_lowercase && !password.chars().any(char::is_lowercase) {
+            violations.push(PolicyViolation::MissingLowercase);
+        }
+        if self.require_uppercase && !password.chars().any(char::is_uppercase) {
+            violations.push(PolicyViolation::MissingUppercase);
+        }
+        if self.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+            violations.push(PolicyViolation::MissingDigit);
+        }
+        if self.require_symbol && password.chars().all(char::is_alphanumeric) {
+            violations.push(PolicyViolation::MissingSymbol);
+        }
+        if self.max_repeated_chars > 0 && longest_run(password) > self.max_repeated_chars {
+            violations.push(PolicyViolation::RepeatedCharacters {
+                max: self.max_repeated_chars,
+            });
+        }
+        if self.reject_personal_info && contains_personal_info(password, personal_info) {
+            violations.push(PolicyViolation::ContainsPersonalInfo);
+        }
+
+        violations
+    }
+}
+
+/// Length of the longest run of one repeated character
+fn longest_run(password: &str) -> usize {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous = None;
+
+    for c in password.chars() {
+        run = if previous == Some(c) { run + 1 } else { 1 };
+        longest = longest.max(run);
+        previous = Some(c);
+    }
+
+    longest
+}
+
+/// Whether the password contains an email local part, or a word of it or of a name
+fn contains_personal_info(password: &str, personal_info: &[&str]) -> bool {
+    let password = password.to_lowercase();
+
+    personal_info
+        .iter()
+        .map(|info| {
+            // Only the local part of an email is personal; the domain is shared
+            info.split_once('@')
+                .map_or(*info, |(local, _)| local)
+                .to_lowercase()
+        })
+        .flat_map(|info| {
+            let words: Vec<String> = info
+                .split(|c: char| !c.is_alphanumeric())
+                .map(str::to_string)
+                .collect();
+            std::iter::once(info).chain(words)
+        })
+        .filter(|fragment| fragment.chars().count() >= MIN_PERSONAL_FRAGMENT_LEN)
+        .any(|fragment| password.contains(&fragment))
+}
+
+/// Uppercase hex SHA-1 prefixes of passwords known from data breaches.
+///
+/// Lists hold one prefix per line, optionally followed by `:count` as in
+/// Have I Been Pwned downloads; blank lines and `#` comments are skipped. All
+/// prefixes in a list must have the same length.
+#[derive(Debug, Clone, Default)]
+pub struct BreachedPasswords {
+    prefixes: HashSet<String>,
+    prefix_len: usize,
+}
+
+impl BreachedPasswords {
+    /// The list shipped with the application: the most common leaked passwords
+    pub fn bundled() -> Self {
+        Self::parse(include_str!("breached_passwords.txt")).expect("Invalid bundled password list")
+    }
+
+    /// A list that matches nothing, for when the check is disabled
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    /// Parse a list of SHA-1 prefixes
+    pub fn parse(list: &str) -> Result<Self, BreachedListError> {
+        let mut prefixes = HashSet::new();
+        let mut prefix_len = 0;
+
+        for (index, line) in list.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let prefix = line.split(':').next().unwrap_or_default().trim();
+            if prefix_len == 0 {
+                prefix_len = prefix.len();
+            }
+            if !(5..=40).contains(&prefix.len())
+                || prefix.len() != prefix_len
+                || !prefix.chars().all(|c| c.is_ascii_hexdigit())
+            {
+                return Err(BreachedListError::InvalidLine(index + 1));
+            }
+
+            prefixes.insert(prefix.to_ascii_uppercase());
+        }
+
+        Ok(Self {
+            prefixes,
+            prefix_len,
+        })
+    }
+
+    /// Whether the SHA-1 of `password` starts with a listed prefix
+    pub fn contains(&self, password: &str) -> bool {
+        if self.prefixes.is_empty() {
+            return false;
+        }
+
+        let digest = hex_upper(&Sha1::digest(password.as_bytes()));
+        self.prefixes.contains(&digest[..self.prefix_len])
+    }
+}
+
+fn hex_upper(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02X}")).collect()
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum BreachedListError {
+    #[error("Invalid SHA-1 prefix on line {0}")]
+    InvalidLine(usize),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_lists_every_failed_rule() {
+        let policy = PasswordPolicy {
+            require_uppercase: true,
+            require_digit: true,
+            require_symbol: true,
+            ..PasswordPolicy::default()
+        };
+
+        assert_eq!(
+            policy.check("aaaab", &[]),
+            vec![
+                PolicyViolation::TooShort { min: 8 },
+                PolicyViolation::MissingUppercase,
+                PolicyViolation::MissingDigit,
+                PolicyViolation::MissingSymbol,
+                PolicyViolation::RepeatedCharacters { max: 3 },
+            ]
+        );
+        assert!(policy.check("Correct-Horse-7", &[]).is_empty());
+        assert_eq!(
+            policy.check(&"Ab1!".repeat(40), &[]),
+            vec![PolicyViolation::TooLong { max: 128 }]
+        );
+    }
+
+    #[test]
+    fn test_policy_rejects_personal_info() {
+        let policy = PasswordPolicy::default();
+        let personal = ["jane.doe@example.com", "Jane Doe"];
+
+        assert_eq!(
+            policy.check("my-JANE-secret", &personal),
+            vec![PolicyViolation::ContainsPersonalInfo]
+        );
+        // Short fragments and the email domain are not personal
+        assert!(policy.check("doe-example-password", &personal).is_empty());
+    }
+
+    #[test]
+    fn test_breached_passwords() {
+        let bundled = BreachedPasswords::bundled();
+        assert!(bundled.contains("password123"));
+        assert!(bundled.contains("qwerty"));
+        assert!(!bundled.contains("Correct-Horse-7-Battery"));
+        assert!(!BreachedPasswords::empty().contains("password123"));
+
+        // Have I Been Pwned format with counts
+        let list = BreachedPasswords::parse("# comment\nCBFDA:2\n\n7C4A8:10\n").unwrap();
+        assert!(list.contains("password123"));
+        assert!(list.contains("123456"));
+
+        assert!(matches!(
+            BreachedPasswords::parse("CBFDA\n7C4A8D09"),
+            Err(BreachedListError::InvalidLine(2))
+        ));
+    }
+}
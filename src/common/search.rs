@@ -0,0 +1,171 @@
+//! Text search helpers: query normalization and match highlighting
+//!
+//! Search must work for scripts written without spaces between words, such as
+//! Thai, where full-text search sees a whole name as one word. Matching
+//! therefore also falls back to plain substrings, and highlighting marks
+//! substrings rather than words.
+
+/// Thai SARA AM, and the NIKHAHIT + SARA AA pair it is often typed as
+const SARA_AM: char = '\u{0E33}';
+const NIKHAHIT: char = '\u{0E4D}';
+const SARA_AA: char = '\u{0E32}';
+
+/// Normalize text for matching: lowercase, single spaces, and Thai SARA AM in
+/// its composed form (a tone mark typed between NIKHAHIT and SARA AA is moved
+/// before it, as in the composed spelling).
+///
+/// The `search_text` column of `users` applies the same rules in SQL.
+pub fn normalize(text: &str) -> String {
+    let lowered = text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+
+    let chars: Vec<char> = lowered.chars().collect();
+    let mut normalized = String::with_capacity(lowered.len());
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] == NIKHAHIT {
+            if chars.get(i + 1) == Some(&SARA_AA) {
+                normalized.push(SARA_AM);
+                i += 2;
+                continue;
+            }
+            if let Some(&tone) = chars.get(i + 1)
+                && is_thai_tone_mark(tone)
+                && chars.get(i + 2) == Some(&SARA_AA)
+            {
+                normalized.push(tone);
+                normalized.push(SARA_AM);
+                i += 3;
+                continue;
+            }
+        }
+        normalized.push(chars[i]);
+        i += 1;
+    }
+
+    normalized
+}
+
+fn is_thai_tone_mark(c: char) -> bool {
+    ('\u{0E48}'..='\u{0E4B}').contains(&c)
+}
+
+/// Words of a normalized query
+pub fn terms(query: &str) -> Vec<&str> {
+    query.split(' ').filter(|term| !term.is_empty()).collect()
+}
+
+/// A piece of a highlighted field
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fragment {
+    pub text: String,
+    /// Whether the piece matched a search term
+    pub matched: bool,
+}
+
+/// Split `text` into fragments, marking every occurrence of a term.
+///
+/// Matching ignores case. Returns `None` when no term occurs in the text.
+pub fn highlight(text: &str, terms: &[&str]) -> Option<Vec<Fragment>> {
+    let chars: Vec<char> = text.chars().collect();
+    let folded: Vec<char> = chars.iter().map(|&c| fold(c)).collect();
+    let mut matched = vec![false; chars.len()];
+
+    for term in terms {
+        let term: Vec<char> = normalize(term).chars().map(fold).collect();
+        if term.is_empty() || term.len() > folded.len() {
+            continue;
+        }
+        for start in 0..=folded.len() - term.len() {
+            if folded[start..start + term.len()] == term[..] {
+                matched[start..start + term.len()].fill(true);
+            }
+        }
+    }
+
+    if !matched.contains(&true) {
+        return None;
+    }
+
+    let mut fragments: Vec<Fragment> = Vec::new();
+    for (c, is_match) in chars.into_iter().zip(matched) {
+        match fragments.last_mut() {
+            Some(last) if last.matched == is_match => last.text.push(c),
+            _ => fragments.push(Fragment {
+                text: c.to_string(),
+                matched: is_match,
+            }),
+        }
+    }
+
+    Some(fragments)
+}
+
+/// Lowercase a single character, keeping characters whose lowercase form is
+/// longer so positions stay aligned with the original text
+fn fold(c: char) -> char {
+    let mut lower = c.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(l), None) => l,
+        _ => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("  Jane   DOE "), "jane doe");
+        // น้ำ typed with NIKHAHIT + SARA AA, in either mark order
+        assert_eq!(normalize("น\u{0E49}\u{0E4D}\u{0E32}"), "น\u{0E49}\u{0E33}");
+        assert_eq!(normalize("น\u{0E4D}\u{0E49}\u{0E32}"), "น\u{0E49}\u{0E33}");
+        assert_eq!(normalize("กำไร"), "กำไร");
+    }
+
+    #[test]
+    fn test_highlight() {
+        let fragments = highlight("Jane Doe", &["doe", "ja"]).unwrap();
+        assert_eq!(
+            fragments,
+            vec![
+                Fragment {
+                    text: "Ja".to_string(),
+                    matched: true
+                },
+                Fragment {
+                    text: "ne ".to_string(),
+                    matched: false
+                },
+                Fragment {
+                    text: "Doe".to_string(),
+                    matched: true
+                },
+            ]
+        );
+        assert_eq!(highlight("Jane Doe", &["smith"]), None);
+    }
+
+    #[test]
+    fn test_highlight_thai_inside_word() {
+        // Thai names have no spaces between words
+        let fragments = highlight("สมชายใจดี", &["ใจดี"]).unwrap();
+        assert_eq!(
+            fragments,
+            vec![
+                Fragment {
+                    text: "สมชาย".to_string(),
+                    matched: false
+                },
+                Fragment {
+                    text: "ใจดี".to_string(),
+                    matched: true
+                },
+            ]
+        );
+    }
+}
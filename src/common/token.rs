@@ -0,0 +1,42 @@
+//! Opaque token utilities
+//!
+//! Opaque tokens are random strings handed to clients once. Only their
+//! SHA-256 digest is persisted, so a database leak does not expose usable tokens.
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use rand::{RngCore, rngs::OsRng};
+use sha2::{Digest, Sha256};
+
+/// Number of random bytes in a generated token
+const TOKEN_BYTES: usize = 32;
+
+/// Generate a new URL-safe opaque token
+pub fn generate() -> String {
+    let mut bytes = [0u8; TOKEN_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Hash a token for storage and lookup (hex-encoded SHA-256)
+pub fn hash(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_and_hash() {
+        let token = generate();
+        let other = generate();
+
+        assert_ne!(token, other);
+        assert_eq!(hash(&token), hash(&token));
+        assert_ne!(hash(&token), hash(&other));
+        assert_eq!(hash(&token).len(), 64);
+    }
+}
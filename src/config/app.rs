@@ -1,6 +1,23 @@
 //! Application configuration
 
-use std::env;
+use std::{env, fs, sync::Arc};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+
+use argon2::Algorithm;
+
+use crate::{
+    common::{
+        email,
+        jwt_keys::{JwtAlgorithm, JwtKeys, TokenPolicy},
+        password::HashParams,
+        password_policy::{BreachedPasswords, PasswordPolicy},
+    },
+    infrastructure::{
+        mail::{LogMailSender, MailSender, MailTransport, SmtpMailSender, SmtpSettings},
+        repositories::TenantIsolation,
+    },
+};
 
 /// Main application configuration
 #[derive(Debug, Clone)]
@@ -11,10 +28,89 @@ pub struct AppConfig {
     pub port: u16,
     /// Environment (development, staging, production)
     pub environment: Environment,
-    /// JWT secret key
-    pub jwt_secret: String,
-    /// JWT expiration time in hours
-    pub jwt_expiration_hours: i64,
+    /// Token signing algorithm
+    pub jwt_algorithm: JwtAlgorithm,
+    /// Shared secret for HS256
+    pub jwt_secret: Option<String>,
+    /// PEM private key for RS256, ES256 and EdDSA
+    pub jwt_private_key_path: Option<String>,
+    /// `kid` header of issued tokens
+    pub jwt_key_id: String,
+    /// Retired public keys still accepted for verification, as (kid, PEM path)
+    pub jwt_verification_keys: Vec<(String, String)>,
+    /// `iss` claim of issued tokens, required on verified tokens
+    pub jwt_issuer: String,
+    /// `aud` claim of issued tokens; verified tokens must name one of them
+    pub jwt_audiences: Vec<String>,
+    /// Clock skew tolerated when checking `exp` and `nbf`, in seconds
+    pub jwt_leeway_secs: u64,
+    /// Argon2 variant for new password hashes
+    pub argon2_algorithm: Algorithm,
+    /// Argon2 memory cost in KiB
+    pub argon2_memory_kib: u32,
+    /// Argon2 number of passes
+    pub argon2_iterations: u32,
+    /// Argon2 degree of parallelism
+    pub argon2_parallelism: u32,
+    /// Rules new passwords must satisfy
+    pub password_policy: PasswordPolicy,
+    /// Reject passwords found in the breached-password list
+    pub password_check_breached: bool,
+    /// File of breached password SHA-1 prefixes; the bundled list is used if unset
+    pub breached_passwords_path: Option<String>,
+    /// Access token (JWT) expiration time in minutes
+    pub jwt_expiration_minutes: i64,
+    /// Refresh token expiration time in days
+    pub refresh_token_expiration_days: i64,
+    /// How long token revocation lookups are cached in-process, in seconds
+    pub revocation_cache_ttl_secs: u64,
+    /// Base URL of the frontend, used to build links in emails
+    pub frontend_url: String,
+    /// Password reset token expiration time in minutes
+    pub password_reset_expiration_minutes: i64,
+    /// How unverified email addresses are handled
+    pub email_verification_mode: EmailVerificationMode,
+    /// How registration responds when the email is already taken
+    pub registration_mode: RegistrationMode,
+    /// Email verification token expiration time in hours
+    pub email_verification_expiration_hours: i64,
+    /// Minimum time between verification emails for the same address, in seconds
+    pub email_verification_resend_cooldown_secs: u64,
+    /// 256-bit key encrypting two-factor secrets at rest
+    pub mfa_encryption_key: [u8; 32],
+    /// Issuer name shown in authenticator apps
+    pub mfa_issuer: String,
+    /// Consecutive failed logins that lock an account
+    pub login_max_failed_attempts: i32,
+    /// How long a locked account stays locked, in minutes
+    pub login_lockout_minutes: i64,
+    /// Consecutive failed logins before each attempt must wait a growing delay
+    pub login_delay_after_attempts: i32,
+    /// Upper bound of the progressive delay, in seconds
+    pub login_max_delay_secs: u64,
+    /// Failed logins allowed from one address within the window
+    pub login_ip_max_failures: u32,
+    /// Window over which failed logins per address are counted, in seconds
+    pub login_ip_window_secs: u64,
+    /// Take the client address from `X-Forwarded-For` (only behind a trusted proxy)
+    pub trust_proxy_headers: bool,
+    /// Also fold provider-specific address aliases (e.g. Gmail dots and `+tags`)
+    /// when normalizing emails
+    pub email_canonicalize_providers: bool,
+    /// Days a deleted account can still be restored before it is purged
+    pub user_deletion_grace_days: i64,
+    /// What purging a deleted account does
+    pub user_purge_mode: UserPurgeMode,
+    /// Key signing personal data export download links (32 bytes)
+    pub data_export_signing_key: [u8; 32],
+    /// Hours a finished data export can be downloaded before it is deleted
+    pub data_export_expiration_hours: i64,
+    /// Hours an organization invitation can be accepted
+    pub org_invitation_expiration_hours: i64,
+    /// How organizations' data is kept apart in the database
+    pub tenant_isolation: TenantIsolation,
+    /// How outgoing mail is delivered
+    pub mail_transport: MailTransport,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -24,24 +120,270 @@ pub enum Environment {
     Production,
 }
 
+/// Registration behavior for emails that already have an account
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RegistrationMode {
+    /// Reject the registration with 409
+    Standard,
+    /// Respond as for a new account and email the existing owner instead
+    EnumerationResistant,
+}
+
+/// Enforcement of email verification
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EmailVerificationMode {
+    /// Unverified accounts cannot log in
+    BlockLogin,
+    /// Unverified accounts can log in but selected routes reject them
+    RestrictRoutes,
+}
+
+/// How deleted accounts are purged after the grace period
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UserPurgeMode {
+    /// Remove the account and all of its data
+    Delete,
+    /// Keep the account row, stripped of personal data, and remove the rest
+    Anonymize,
+}
+
 impl AppConfig {
     /// Load configuration from environment variables
     pub fn from_env() -> Result<Self, ConfigError> {
+        let jwt_algorithm: JwtAlgorithm = env::var("JWT_ALGORITHM")
+            .unwrap_or_else(|_| "HS256".to_string())
+            .parse()
+            .map_err(|_| ConfigError::InvalidJwtAlgorithm)?;
+
+        let hash_params = HashParams {
+            algorithm: env::var("ARGON2_ALGORITHM")
+                .unwrap_or_else(|_| "argon2id".to_string())
+                .to_lowercase()
+                .parse()
+                .map_err(|_| ConfigError::InvalidArgon2Params)?,
+            memory_kib: env::var("ARGON2_MEMORY_KIB")
+                .unwrap_or_else(|_| "19456".to_string())
+                .parse()
+                .map_err(|_| ConfigError::InvalidArgon2Params)?,
+            iterations: env::var("ARGON2_ITERATIONS")
+                .unwrap_or_else(|_| "2".to_string())
+                .parse()
+                .map_err(|_| ConfigError::InvalidArgon2Params)?,
+            parallelism: env::var("ARGON2_PARALLELISM")
+                .unwrap_or_else(|_| "1".to_string())
+                .parse()
+                .map_err(|_| ConfigError::InvalidArgon2Params)?,
+        };
+        hash_params
+            .validate()
+            .map_err(|_| ConfigError::InvalidArgon2Params)?;
+
+        let password_policy = PasswordPolicy {
+            min_length: parse_policy_var("PASSWORD_MIN_LENGTH", "8")?,
+            max_length: parse_policy_var("PASSWORD_MAX_LENGTH", "128")?,
+            require_lowercase: parse_policy_var("PASSWORD_REQUIRE_LOWERCASE", "false")?,
+            require_uppercase: parse_policy_var("PASSWORD_REQUIRE_UPPERCASE", "false")?,
+            require_digit: parse_policy_var("PASSWORD_REQUIRE_DIGIT", "false")?,
+            require_symbol: parse_policy_var("PASSWORD_REQUIRE_SYMBOL", "false")?,
+            max_repeated_chars: parse_policy_var("PASSWORD_MAX_REPEATED_CHARS", "3")?,
+            reject_personal_info: parse_policy_var("PASSWORD_REJECT_PERSONAL_INFO", "true")?,
+        };
+        if password_policy.min_length == 0
+            || password_policy.min_length > password_policy.max_length
+        {
+            return Err(ConfigError::InvalidPasswordPolicy("PASSWORD_MIN_LENGTH"));
+        }
+
+        let environment: Environment = env::var("ENVIRONMENT")
+            .unwrap_or_else(|_| "development".to_string())
+            .parse()?;
+
+        let mail_transport = match env::var("MAIL_TRANSPORT")
+            .unwrap_or_else(|_| "log".to_string())
+            .to_lowercase()
+            .as_str()
+        {
+            "log" if environment == Environment::Production => {
+                return Err(ConfigError::MailTransportRequired);
+            }
+            "log" => MailTransport::Log,
+            "smtp" => MailTransport::Smtp(SmtpSettings {
+                host: env::var("SMTP_HOST")
+                    .ok()
+                    .filter(|value| !value.is_empty())
+                    .ok_or(ConfigError::MissingEnvVar("SMTP_HOST"))?,
+                port: env::var("SMTP_PORT")
+                    .unwrap_or_else(|_| "587".to_string())
+                    .parse()
+                    .map_err(|_| ConfigError::InvalidSmtpPort)?,
+                security: env::var("SMTP_SECURITY")
+                    .unwrap_or_else(|_| "starttls".to_string())
+                    .parse()
+                    .map_err(|_| ConfigError::InvalidSmtpSecurity)?,
+                username: env::var("SMTP_USERNAME")
+                    .ok()
+                    .filter(|value| !value.is_empty()),
+                password: env::var("SMTP_PASSWORD")
+                    .ok()
+                    .filter(|value| !value.is_empty()),
+                from: env::var("MAIL_FROM")
+                    .ok()
+                    .filter(|value| !value.is_empty())
+                    .ok_or(ConfigError::MissingEnvVar("MAIL_FROM"))?,
+            }),
+            _ => return Err(ConfigError::InvalidMailTransport),
+        };
+
         Ok(Self {
             host: env::var("HOST").unwrap_or_else(|_| "0.0.0.0".to_string()),
             port: env::var("PORT")
                 .unwrap_or_else(|_| "3000".to_string())
                 .parse()
                 .map_err(|_| ConfigError::InvalidPort)?,
-            environment: env::var("ENVIRONMENT")
-                .unwrap_or_else(|_| "development".to_string())
+            environment,
+            jwt_secret: match jwt_algorithm {
+                JwtAlgorithm::HS256 => Some(
+                    env::var("JWT_SECRET").map_err(|_| ConfigError::MissingEnvVar("JWT_SECRET"))?,
+                ),
+                _ => None,
+            },
+            jwt_private_key_path: match jwt_algorithm {
+                JwtAlgorithm::HS256 => None,
+                _ => Some(
+                    env::var("JWT_PRIVATE_KEY_PATH")
+                        .map_err(|_| ConfigError::MissingEnvVar("JWT_PRIVATE_KEY_PATH"))?,
+                ),
+            },
+            jwt_algorithm,
+            jwt_key_id: env::var("JWT_KEY_ID").unwrap_or_else(|_| "default".to_string()),
+            jwt_verification_keys: parse_verification_keys(
+                &env::var("JWT_VERIFICATION_KEYS").unwrap_or_default(),
+            )?,
+            jwt_issuer: env::var("JWT_ISSUER").unwrap_or_else(|_| "axum-api".to_string()),
+            jwt_audiences: parse_audiences(
+                &env::var("JWT_AUDIENCE").unwrap_or_else(|_| "axum-api".to_string()),
+            )?,
+            jwt_leeway_secs: env::var("JWT_LEEWAY_SECS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .map_err(|_| ConfigError::InvalidJwtLeeway)?,
+            argon2_algorithm: hash_params.algorithm,
+            argon2_memory_kib: hash_params.memory_kib,
+            argon2_iterations: hash_params.iterations,
+            argon2_parallelism: hash_params.parallelism,
+            password_policy,
+            password_check_breached: env::var("PASSWORD_CHECK_BREACHED")
+                .unwrap_or_else(|_| "true".to_string())
+                .parse()
+                .map_err(|_| ConfigError::InvalidPasswordPolicy("PASSWORD_CHECK_BREACHED"))?,
+            breached_passwords_path: env::var("BREACHED_PASSWORDS_PATH").ok(),
+            jwt_expiration_minutes: env::var("JWT_EXPIRATION_MINUTES")
+                .unwrap_or_else(|_| "15".to_string())
+                .parse()
+                .map_err(|_| ConfigError::InvalidJwtExpiration)?,
+            refresh_token_expiration_days: env::var("REFRESH_TOKEN_EXPIRATION_DAYS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .map_err(|_| ConfigError::InvalidRefreshTokenExpiration)?,
+            revocation_cache_ttl_secs: env::var("REVOCATION_CACHE_TTL_SECS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .map_err(|_| ConfigError::InvalidRevocationCacheTtl)?,
+            frontend_url: env::var("FRONTEND_URL")
+                .unwrap_or_else(|_| "http://localhost:3000".to_string())
+                .trim_end_matches('/')
+                .to_string(),
+            password_reset_expiration_minutes: env::var("PASSWORD_RESET_EXPIRATION_MINUTES")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .map_err(|_| ConfigError::InvalidPasswordResetExpiration)?,
+            email_verification_mode: env::var("EMAIL_VERIFICATION_MODE")
+                .unwrap_or_else(|_| "restrict_routes".to_string())
+                .parse()?,
+            registration_mode: env::var("REGISTRATION_MODE")
+                .unwrap_or_else(|_| "standard".to_string())
                 .parse()?,
-            jwt_secret: env::var("JWT_SECRET")
-                .map_err(|_| ConfigError::MissingEnvVar("JWT_SECRET"))?,
-            jwt_expiration_hours: env::var("JWT_EXPIRATION_HOURS")
+            email_verification_expiration_hours: env::var("EMAIL_VERIFICATION_EXPIRATION_HOURS")
                 .unwrap_or_else(|_| "24".to_string())
                 .parse()
-                .map_err(|_| ConfigError::InvalidJwtExpiration)?,
+                .map_err(|_| ConfigError::InvalidEmailVerificationExpiration)?,
+            email_verification_resend_cooldown_secs: env::var(
+                "EMAIL_VERIFICATION_RESEND_COOLDOWN_SECS",
+            )
+            .unwrap_or_else(|_| "60".to_string())
+            .parse()
+            .map_err(|_| ConfigError::InvalidEmailVerificationCooldown)?,
+            mfa_encryption_key: parse_key(
+                &env::var("MFA_ENCRYPTION_KEY")
+                    .map_err(|_| ConfigError::MissingEnvVar("MFA_ENCRYPTION_KEY"))?,
+            )
+            .ok_or(ConfigError::InvalidMfaEncryptionKey)?,
+            mfa_issuer: env::var("MFA_ISSUER").unwrap_or_else(|_| "Axum API".to_string()),
+            login_max_failed_attempts: env::var("LOGIN_MAX_FAILED_ATTEMPTS")
+                .unwrap_or_else(|_| "10".to_string())
+                .parse()
+                .ok()
+                .filter(|attempts| *attempts > 0)
+                .ok_or(ConfigError::InvalidLoginMaxFailedAttempts)?,
+            login_lockout_minutes: env::var("LOGIN_LOCKOUT_MINUTES")
+                .unwrap_or_else(|_| "15".to_string())
+                .parse()
+                .map_err(|_| ConfigError::InvalidLoginLockoutMinutes)?,
+            login_delay_after_attempts: env::var("LOGIN_DELAY_AFTER_ATTEMPTS")
+                .unwrap_or_else(|_| "3".to_string())
+                .parse()
+                .map_err(|_| ConfigError::InvalidLoginDelayAfterAttempts)?,
+            login_max_delay_secs: env::var("LOGIN_MAX_DELAY_SECS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .map_err(|_| ConfigError::InvalidLoginMaxDelay)?,
+            login_ip_max_failures: env::var("LOGIN_IP_MAX_FAILURES")
+                .unwrap_or_else(|_| "100".to_string())
+                .parse()
+                .map_err(|_| ConfigError::InvalidLoginIpMaxFailures)?,
+            login_ip_window_secs: env::var("LOGIN_IP_WINDOW_SECS")
+                .unwrap_or_else(|_| "900".to_string())
+                .parse()
+                .map_err(|_| ConfigError::InvalidLoginIpWindow)?,
+            trust_proxy_headers: env::var("TRUST_PROXY_HEADERS")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .map_err(|_| ConfigError::InvalidTrustProxyHeaders)?,
+            email_canonicalize_providers: env::var("EMAIL_CANONICALIZE_PROVIDERS")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .map_err(|_| ConfigError::InvalidEmailCanonicalizeProviders)?,
+            user_deletion_grace_days: env::var("USER_DELETION_GRACE_DAYS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .ok()
+                .filter(|days| *days >= 0)
+                .ok_or(ConfigError::InvalidUserDeletionGraceDays)?,
+            user_purge_mode: env::var("USER_PURGE_MODE")
+                .unwrap_or_else(|_| "delete".to_string())
+                .parse()?,
+            data_export_signing_key: parse_key(
+                &env::var("DATA_EXPORT_SIGNING_KEY")
+                    .map_err(|_| ConfigError::MissingEnvVar("DATA_EXPORT_SIGNING_KEY"))?,
+            )
+            .ok_or(ConfigError::InvalidDataExportSigningKey)?,
+            data_export_expiration_hours: env::var("DATA_EXPORT_EXPIRATION_HOURS")
+                .unwrap_or_else(|_| "24".to_string())
+                .parse()
+                .ok()
+                .filter(|hours| *hours > 0)
+                .ok_or(ConfigError::InvalidDataExportExpiration)?,
+            org_invitation_expiration_hours: env::var("ORG_INVITATION_EXPIRATION_HOURS")
+                .unwrap_or_else(|_| "168".to_string())
+                .parse()
+                .ok()
+                .filter(|hours| *hours > 0)
+                .ok_or(ConfigError::InvalidOrgInvitationExpiration)?,
+            tenant_isolation: env::var("TENANT_ISOLATION")
+                .unwrap_or_else(|_| "filter".to_string())
+                .parse()
+                .map_err(|_| ConfigError::InvalidTenantIsolation)?,
+            mail_transport,
         })
     }
 
@@ -50,6 +392,73 @@ impl AppConfig {
         format!("{}:{}", self.host, self.port)
     }
 
+    /// Load the token signing and verification keys
+    pub fn load_jwt_keys(&self) -> Result<JwtKeys, ConfigError> {
+        let mut keys = match (&self.jwt_secret, &self.jwt_private_key_path) {
+            (Some(secret), _) => JwtKeys::hmac(&self.jwt_key_id, secret.as_bytes()),
+            (None, Some(path)) => {
+                JwtKeys::from_private_pem(self.jwt_algorithm, &self.jwt_key_id, &read_key(path)?)
+                    .map_err(|err| ConfigError::InvalidJwtKey(err.to_string()))?
+            }
+            (None, None) => return Err(ConfigError::MissingEnvVar("JWT_PRIVATE_KEY_PATH")),
+        };
+
+        for (kid, path) in &self.jwt_verification_keys {
+            keys = keys
+                .with_verification_key(kid, &read_key(path)?)
+                .map_err(|err| ConfigError::InvalidJwtKey(err.to_string()))?;
+        }
+
+        Ok(keys.with_policy(TokenPolicy {
+            issuer: self.jwt_issuer.clone(),
+            audiences: self.jwt_audiences.clone(),
+            leeway_secs: self.jwt_leeway_secs,
+        }))
+    }
+
+    /// Set up the mail transport. Message bodies, which carry tokens, are only
+    /// logged in development.
+    pub fn load_mailer(&self) -> Result<Arc<dyn MailSender>, ConfigError> {
+        match &self.mail_transport {
+            MailTransport::Log => Ok(Arc::new(LogMailSender::new(
+                self.environment == Environment::Development,
+            ))),
+            MailTransport::Smtp(settings) => SmtpMailSender::new(settings)
+                .map(|sender| Arc::new(sender) as Arc<dyn MailSender>)
+                .map_err(|err| ConfigError::InvalidMailSettings(err.to_string())),
+        }
+    }
+
+    /// Parameters for new password hashes
+    pub fn hash_params(&self) -> HashParams {
+        HashParams {
+            algorithm: self.argon2_algorithm,
+            memory_kib: self.argon2_memory_kib,
+            iterations: self.argon2_iterations,
+            parallelism: self.argon2_parallelism,
+        }
+    }
+
+    /// Normalize an email address for storage and lookup
+    pub fn normalize_email(&self, email: &str) -> String {
+        email::normalize(email, self.email_canonicalize_providers)
+    }
+
+    /// Load the breached-password list
+    pub fn load_breached_passwords(&self) -> Result<BreachedPasswords, ConfigError> {
+        if !self.password_check_breached {
+            return Ok(BreachedPasswords::empty());
+        }
+
+        match &self.breached_passwords_path {
+            Some(path) => fs::read_to_string(path)
+                .map_err(|err| err.to_string())
+                .and_then(|list| BreachedPasswords::parse(&list).map_err(|err| err.to_string()))
+                .map_err(|err| ConfigError::InvalidBreachedPasswords(format!("{path}: {err}"))),
+            None => Ok(BreachedPasswords::bundled()),
+        }
+    }
+
     /// Check if running in production
     #[allow(dead_code)]
     pub fn is_production(&self) -> bool {
@@ -57,6 +466,61 @@ impl AppConfig {
     }
 }
 
+/// Parse a password policy setting, falling back to `default`
+fn parse_policy_var<T: std::str::FromStr>(
+    name: &'static str,
+    default: &str,
+) -> Result<T, ConfigError> {
+    env::var(name)
+        .unwrap_or_else(|_| default.to_string())
+        .parse()
+        .map_err(|_| ConfigError::InvalidPasswordPolicy(name))
+}
+
+/// Parse a base64-encoded 256-bit key
+fn parse_key(encoded: &str) -> Option<[u8; 32]> {
+    STANDARD
+        .decode(encoded.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+}
+
+/// Parse `kid=path` pairs separated by commas
+fn parse_verification_keys(value: &str) -> Result<Vec<(String, String)>, ConfigError> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            entry
+                .split_once('=')
+                .map(|(kid, path)| (kid.trim().to_string(), path.trim().to_string()))
+                .filter(|(kid, path)| !kid.is_empty() && !path.is_empty())
+                .ok_or(ConfigError::InvalidJwtVerificationKeys)
+        })
+        .collect()
+}
+
+/// Parse a comma-separated, non-empty list of audiences
+fn parse_audiences(value: &str) -> Result<Vec<String>, ConfigError> {
+    let audiences: Vec<String> = value
+        .split(',')
+        .map(str::trim)
+        .filter(|audience| !audience.is_empty())
+        .map(str::to_string)
+        .collect();
+
+    if audiences.is_empty() {
+        return Err(ConfigError::InvalidJwtAudience);
+    }
+
+    Ok(audiences)
+}
+
+fn read_key(path: &str) -> Result<String, ConfigError> {
+    fs::read_to_string(path).map_err(|err| ConfigError::InvalidJwtKey(format!("{path}: {err}")))
+}
+
 impl std::str::FromStr for Environment {
     type Err = ConfigError;
 
@@ -70,6 +534,42 @@ impl std::str::FromStr for Environment {
     }
 }
 
+impl std::str::FromStr for EmailVerificationMode {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "block_login" | "login" => Ok(Self::BlockLogin),
+            "restrict_routes" | "routes" => Ok(Self::RestrictRoutes),
+            _ => Err(ConfigError::InvalidEmailVerificationMode),
+        }
+    }
+}
+
+impl std::str::FromStr for RegistrationMode {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "standard" => Ok(Self::Standard),
+            "enumeration_resistant" => Ok(Self::EnumerationResistant),
+            _ => Err(ConfigError::InvalidRegistrationMode),
+        }
+    }
+}
+
+impl std::str::FromStr for UserPurgeMode {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "delete" => Ok(Self::Delete),
+            "anonymize" => Ok(Self::Anonymize),
+            _ => Err(ConfigError::InvalidUserPurgeMode),
+        }
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum ConfigError {
     #[error("Missing environment variable: {0}")]
@@ -78,6 +578,78 @@ pub enum ConfigError {
     InvalidPort,
     #[error("Invalid environment (use: development, staging, production)")]
     InvalidEnvironment,
-    #[error("Invalid JWT expiration hours")]
+    #[error("Invalid JWT algorithm (use: HS256, RS256, ES256, EdDSA)")]
+    InvalidJwtAlgorithm,
+    #[error("Invalid JWT verification keys (use: kid=path,kid=path)")]
+    InvalidJwtVerificationKeys,
+    #[error("Invalid JWT key: {0}")]
+    InvalidJwtKey(String),
+    #[error("Invalid JWT audience (use: comma-separated list)")]
+    InvalidJwtAudience,
+    #[error("Invalid JWT leeway seconds")]
+    InvalidJwtLeeway,
+    #[error(
+        "Invalid Argon2 parameters (check ARGON2_ALGORITHM, ARGON2_MEMORY_KIB, ARGON2_ITERATIONS, ARGON2_PARALLELISM)"
+    )]
+    InvalidArgon2Params,
+    #[error("Invalid password policy setting: {0}")]
+    InvalidPasswordPolicy(&'static str),
+    #[error("Invalid breached password list: {0}")]
+    InvalidBreachedPasswords(String),
+    #[error("Invalid JWT expiration minutes")]
     InvalidJwtExpiration,
+    #[error("Invalid refresh token expiration days")]
+    InvalidRefreshTokenExpiration,
+    #[error("Invalid revocation cache TTL")]
+    InvalidRevocationCacheTtl,
+    #[error("Invalid password reset expiration minutes")]
+    InvalidPasswordResetExpiration,
+    #[error("Invalid email verification mode (use: block_login, restrict_routes)")]
+    InvalidEmailVerificationMode,
+    #[error("Invalid registration mode (use: standard, enumeration_resistant)")]
+    InvalidRegistrationMode,
+    #[error("Invalid email verification expiration hours")]
+    InvalidEmailVerificationExpiration,
+    #[error("Invalid email verification resend cooldown")]
+    InvalidEmailVerificationCooldown,
+    #[error("Invalid MFA encryption key (expected 32 bytes, base64-encoded)")]
+    InvalidMfaEncryptionKey,
+    #[error("Invalid login max failed attempts (must be at least 1)")]
+    InvalidLoginMaxFailedAttempts,
+    #[error("Invalid login lockout minutes")]
+    InvalidLoginLockoutMinutes,
+    #[error("Invalid login delay threshold")]
+    InvalidLoginDelayAfterAttempts,
+    #[error("Invalid login max delay seconds")]
+    InvalidLoginMaxDelay,
+    #[error("Invalid login max failures per IP")]
+    InvalidLoginIpMaxFailures,
+    #[error("Invalid login IP window seconds")]
+    InvalidLoginIpWindow,
+    #[error("Invalid trust proxy headers flag (use: true, false)")]
+    InvalidTrustProxyHeaders,
+    #[error("Invalid email provider canonicalization flag (use: true, false)")]
+    InvalidEmailCanonicalizeProviders,
+    #[error("Invalid user deletion grace period (use a number of days, 0 or more)")]
+    InvalidUserDeletionGraceDays,
+    #[error("Invalid user purge mode (use: delete, anonymize)")]
+    InvalidUserPurgeMode,
+    #[error("Invalid data export signing key (use 32 random bytes, base64-encoded)")]
+    InvalidDataExportSigningKey,
+    #[error("Invalid data export expiration hours")]
+    InvalidDataExportExpiration,
+    #[error("Invalid organization invitation expiration hours")]
+    InvalidOrgInvitationExpiration,
+    #[error("Invalid tenant isolation (use: filter, row_level_security)")]
+    InvalidTenantIsolation,
+    #[error("Invalid mail transport (use: log, smtp)")]
+    InvalidMailTransport,
+    #[error("MAIL_TRANSPORT=log does not deliver mail and cannot be used in production")]
+    MailTransportRequired,
+    #[error("Invalid SMTP port")]
+    InvalidSmtpPort,
+    #[error("Invalid SMTP security (use: starttls, tls, none)")]
+    InvalidSmtpSecurity,
+    #[error("Invalid mail settings: {0}")]
+    InvalidMailSettings(String),
 }
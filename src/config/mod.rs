@@ -5,23 +5,70 @@
 mod app;
 mod database;
 
-pub use app::AppConfig;
+#[cfg(test)]
+pub use app::Environment;
+pub use app::{AppConfig, EmailVerificationMode, RegistrationMode, UserPurgeMode};
 pub use database::DatabaseConfig;
 
-use std::sync::Arc;
+use std::{net::IpAddr, sync::Arc, time::Instant};
+
+use uuid::Uuid;
+
+use crate::{
+    common::{jwt_keys::JwtKeys, password_policy::BreachedPasswords},
+    infrastructure::{
+        cache::{RevocationCache, TtlCache},
+        export::{DataExporter, built_in_exporters},
+        mail::MailSender,
+    },
+};
 
 /// Application state shared across handlers
 #[derive(Clone)]
 pub struct AppState {
     pub db_pool: sqlx::PgPool,
     pub config: Arc<AppConfig>,
+    pub jwt_keys: Arc<JwtKeys>,
+    pub breached_passwords: Arc<BreachedPasswords>,
+    pub revocation_cache: Arc<RevocationCache>,
+    pub mailer: Arc<dyn MailSender>,
+    /// Sections of personal data exports
+    pub exporters: Arc<Vec<Arc<dyn DataExporter>>>,
+    /// When a verification email was last requested, by address
+    pub verification_resend_cooldowns: Arc<TtlCache<String, Instant>>,
+    /// Failed two-factor code attempts, by user
+    pub mfa_failed_attempts: Arc<TtlCache<Uuid, u32>>,
+    /// Failed logins, by client address
+    pub login_ip_failures: Arc<TtlCache<IpAddr, u32>>,
 }
 
 impl AppState {
-    pub fn new(db_pool: sqlx::PgPool, config: AppConfig) -> Self {
+    pub fn new(
+        db_pool: sqlx::PgPool,
+        config: AppConfig,
+        jwt_keys: JwtKeys,
+        breached_passwords: BreachedPasswords,
+        mailer: Arc<dyn MailSender>,
+    ) -> Self {
         Self {
             db_pool,
             config: Arc::new(config),
+            jwt_keys: Arc::new(jwt_keys),
+            breached_passwords: Arc::new(breached_passwords),
+            revocation_cache: Arc::new(RevocationCache::default()),
+            mailer,
+            exporters: Arc::new(built_in_exporters()),
+            verification_resend_cooldowns: Arc::new(TtlCache::new()),
+            mfa_failed_attempts: Arc::new(TtlCache::new()),
+            login_ip_failures: Arc::new(TtlCache::new()),
         }
     }
+
+    /// Add a section to personal data exports. Nothing in this crate registers
+    /// one beyond the built-in exporters; it is for tables added on top of it.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn with_exporter(mut self, exporter: Arc<dyn DataExporter>) -> Self {
+        Arc::make_mut(&mut self.exporters).push(exporter);
+        self
+    }
 }
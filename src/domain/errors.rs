@@ -2,7 +2,10 @@
 
 use thiserror::Error;
 
-use crate::api::error::ApiError;
+use crate::{
+    api::error::{ApiError, ErrorDetail},
+    common::password_policy::PolicyViolation,
+};
 
 #[derive(Debug, Error)]
 pub enum DomainError {
@@ -15,12 +18,96 @@ pub enum DomainError {
     #[error("Invalid credentials")]
     InvalidCredentials,
 
+    #[error("Current password is incorrect")]
+    IncorrectPassword,
+
+    #[error("Password does not meet the password policy")]
+    WeakPassword(Vec<PolicyViolation>),
+
     #[error("Password hashing failed")]
     PasswordHashingFailed,
 
     #[error("Token generation failed")]
     TokenGenerationFailed,
 
+    #[error("Invalid or expired refresh token")]
+    InvalidRefreshToken,
+
+    #[error("Refresh token has already been used")]
+    RefreshTokenReused,
+
+    #[error("Invalid or expired password reset token")]
+    InvalidResetToken,
+
+    #[error("Email address has not been verified")]
+    EmailNotVerified,
+
+    #[error("Invalid or expired email verification token")]
+    InvalidVerificationToken,
+
+    #[error("Too many requests, retry in {retry_after_secs} seconds")]
+    TooManyRequests { retry_after_secs: u64 },
+
+    #[error("Account is locked, retry in {retry_after_secs} seconds")]
+    AccountLocked { retry_after_secs: u64 },
+
+    #[error("Two-factor authentication is already enabled")]
+    MfaAlreadyEnabled,
+
+    #[error("Two-factor authentication enrollment not started")]
+    MfaNotEnrolled,
+
+    #[error("Invalid two-factor authentication code")]
+    InvalidMfaCode,
+
+    #[error("Invalid or expired two-factor authentication token")]
+    InvalidMfaToken,
+
+    #[error("Invalid, expired or revoked API key")]
+    InvalidApiKey,
+
+    #[error("API key not found")]
+    ApiKeyNotFound,
+
+    #[error("Cannot grant scope '{0}' to an API key")]
+    InvalidApiKeyScope(String),
+
+    #[error("Invalid pagination cursor")]
+    InvalidCursor,
+
+    #[error("Administrators cannot deactivate or delete their own account")]
+    CannotTargetSelf,
+
+    #[error("Data export not found")]
+    ExportNotFound,
+
+    #[error("Invalid or expired download link")]
+    InvalidDownloadLink,
+
+    #[error("Organization not found")]
+    OrganizationNotFound,
+
+    #[error("Member not found")]
+    MemberNotFound,
+
+    #[error("Invitation not found")]
+    InvitationNotFound,
+
+    #[error("Invalid or expired invitation")]
+    InvalidInvitation,
+
+    #[error("User is already a member of the organization")]
+    AlreadyMember,
+
+    #[error("Organization role does not allow this action")]
+    InsufficientOrgRole,
+
+    #[error("Organization must keep at least one owner")]
+    LastOrganizationOwner,
+
+    #[error("Encryption failed")]
+    EncryptionFailed,
+
     #[error("Database error: {0}")]
     DatabaseError(#[from] sqlx::Error),
 }
@@ -39,12 +126,131 @@ impl From<DomainError> for ApiError {
             DomainError::InvalidCredentials => {
                 ApiError::unauthorized("Invalid email or password").with_code("INVALID_CREDENTIALS")
             }
+            DomainError::IncorrectPassword => {
+                ApiError::bad_request("Current password is incorrect")
+                    .with_code("INVALID_CURRENT_PASSWORD")
+            }
+            DomainError::WeakPassword(violations) => {
+                ApiError::bad_request("Password does not meet the password policy")
+                    .with_code("VALIDATION_ERROR")
+                    .with_details(
+                        violations
+                            .iter()
+                            .map(|violation| ErrorDetail {
+                                field: "password".to_string(),
+                                code: violation.code().to_string(),
+                                message: violation.to_string(),
+                            })
+                            .collect(),
+                    )
+            }
             DomainError::PasswordHashingFailed => {
                 ApiError::internal("An error occurred during registration")
             }
             DomainError::TokenGenerationFailed => {
                 ApiError::internal("An error occurred during authentication")
             }
+            DomainError::InvalidRefreshToken => {
+                ApiError::unauthorized("Invalid or expired refresh token")
+                    .with_code("INVALID_REFRESH_TOKEN")
+            }
+            DomainError::RefreshTokenReused => {
+                ApiError::unauthorized("Refresh token has already been used; please log in again")
+                    .with_code("REFRESH_TOKEN_REUSED")
+            }
+            DomainError::InvalidResetToken => {
+                ApiError::bad_request("Invalid or expired password reset token")
+                    .with_code("INVALID_RESET_TOKEN")
+            }
+            DomainError::EmailNotVerified => {
+                ApiError::forbidden("Email address has not been verified")
+                    .with_code("EMAIL_NOT_VERIFIED")
+            }
+            DomainError::InvalidVerificationToken => {
+                ApiError::bad_request("Invalid or expired email verification token")
+                    .with_code("INVALID_VERIFICATION_TOKEN")
+            }
+            DomainError::TooManyRequests { retry_after_secs } => {
+                ApiError::too_many_requests("Too many requests, please try again later")
+                    .with_code("TOO_MANY_REQUESTS")
+                    .with_retry_after(retry_after_secs)
+            }
+            DomainError::AccountLocked { retry_after_secs } => ApiError::locked(
+                "Account is temporarily locked after too many failed logins; \
+                 reset your password or try again later",
+            )
+            .with_code("ACCOUNT_LOCKED")
+            .with_retry_after(retry_after_secs),
+            DomainError::MfaAlreadyEnabled => {
+                ApiError::conflict("Two-factor authentication is already enabled")
+                    .with_code("MFA_ALREADY_ENABLED")
+            }
+            DomainError::MfaNotEnrolled => {
+                ApiError::bad_request("Two-factor authentication enrollment not started")
+                    .with_code("MFA_NOT_ENROLLED")
+            }
+            DomainError::InvalidMfaCode => {
+                ApiError::unauthorized("Invalid two-factor authentication code")
+                    .with_code("INVALID_MFA_CODE")
+            }
+            DomainError::InvalidMfaToken => {
+                ApiError::unauthorized("Invalid or expired two-factor authentication token")
+                    .with_code("INVALID_MFA_TOKEN")
+            }
+            DomainError::InvalidApiKey => {
+                ApiError::unauthorized("Invalid, expired or revoked API key")
+                    .with_code("INVALID_API_KEY")
+            }
+            DomainError::ApiKeyNotFound => {
+                ApiError::not_found("API key not found").with_code("API_KEY_NOT_FOUND")
+            }
+            DomainError::InvalidApiKeyScope(scope) => ApiError::bad_request(format!(
+                "Cannot grant scope '{scope}': you do not hold this permission"
+            ))
+            .with_code("INVALID_SCOPE"),
+            DomainError::InvalidCursor => ApiError::bad_request(
+                "Invalid pagination cursor; it must come from a listing with the same sort",
+            )
+            .with_code("INVALID_CURSOR"),
+            DomainError::CannotTargetSelf => ApiError::bad_request(
+                "Administrators cannot deactivate or delete their own account",
+            )
+            .with_code("CANNOT_TARGET_SELF"),
+            DomainError::ExportNotFound => {
+                ApiError::not_found("Data export not found").with_code("EXPORT_NOT_FOUND")
+            }
+            DomainError::InvalidDownloadLink => {
+                ApiError::forbidden("Invalid or expired download link")
+                    .with_code("INVALID_DOWNLOAD_LINK")
+            }
+            DomainError::OrganizationNotFound => {
+                ApiError::not_found("Organization not found").with_code("ORGANIZATION_NOT_FOUND")
+            }
+            DomainError::MemberNotFound => {
+                ApiError::not_found("Member not found").with_code("MEMBER_NOT_FOUND")
+            }
+            DomainError::InvitationNotFound => {
+                ApiError::not_found("Invitation not found").with_code("INVITATION_NOT_FOUND")
+            }
+            DomainError::InvalidInvitation => {
+                ApiError::bad_request("Invalid or expired invitation")
+                    .with_code("INVALID_INVITATION")
+            }
+            DomainError::AlreadyMember => {
+                ApiError::conflict("User is already a member of the organization")
+                    .with_code("ALREADY_MEMBER")
+            }
+            DomainError::InsufficientOrgRole => {
+                ApiError::forbidden("Your role in the organization does not allow this action")
+                    .with_code("INSUFFICIENT_ORG_ROLE")
+            }
+            DomainError::LastOrganizationOwner => {
+                ApiError::conflict("The organization must keep at least one owner")
+                    .with_code("LAST_OWNER")
+            }
+            DomainError::EncryptionFailed => {
+                ApiError::internal("An error occurred during two-factor authentication")
+            }
             DomainError::DatabaseError(_) => ApiError::internal("A database error occurred"),
         }
     }
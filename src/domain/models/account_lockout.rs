@@ -0,0 +1,20 @@
+//! Account lockout domain model
+
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+
+/// Failed login state of an account
+#[derive(Debug, Clone, FromRow)]
+pub struct AccountLockout {
+    /// Consecutive failures since the last successful login or lockout
+    pub failed_attempts: i32,
+    pub last_failed_at: DateTime<Utc>,
+    pub locked_until: Option<DateTime<Utc>>,
+}
+
+impl AccountLockout {
+    /// Check if the account is currently locked
+    pub fn is_locked(&self) -> bool {
+        self.locked_until.is_some_and(|until| until > Utc::now())
+    }
+}
@@ -0,0 +1,55 @@
+//! API key domain model
+
+use chrono::{DateTime, Duration, Utc};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Personal API key entity (only the key hash is stored)
+#[derive(Debug, Clone, FromRow)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    /// Public start of the key (e.g. `ak_x7kq2mfa`), shown in listings
+    pub prefix: String,
+    pub key_hash: String,
+    pub scopes: Vec<String>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ApiKey {
+    /// Create a new API key instance (for insertion)
+    pub fn new(
+        user_id: Uuid,
+        name: String,
+        prefix: String,
+        key_hash: String,
+        scopes: Vec<String>,
+        expires_in_days: Option<i64>,
+    ) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            user_id,
+            name,
+            prefix,
+            key_hash,
+            scopes,
+            last_used_at: None,
+            expires_at: expires_in_days.map(|days| now + Duration::days(days)),
+            revoked_at: None,
+            created_at: now,
+        }
+    }
+
+    /// Check if the key can still be used
+    pub fn is_active(&self) -> bool {
+        self.revoked_at.is_none()
+            && self
+                .expires_at
+                .is_none_or(|expires_at| expires_at > Utc::now())
+    }
+}
@@ -0,0 +1,55 @@
+//! Audit log domain model
+
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Record of an administrative action
+#[derive(Debug, Clone, FromRow)]
+pub struct AuditLog {
+    pub id: Uuid,
+    /// User who performed the action, `None` for the system
+    pub actor_id: Option<Uuid>,
+    /// What was done, e.g. `user.deactivated`
+    pub action: String,
+    /// User the action was performed on
+    pub target_user_id: Option<Uuid>,
+    /// Action-specific data, e.g. the changed fields
+    pub details: serde_json::Value,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl AuditLog {
+    /// Create a new audit log entry (for insertion)
+    pub fn new(
+        actor_id: Uuid,
+        action: &str,
+        target_user_id: Option<Uuid>,
+        details: serde_json::Value,
+        ip_address: Option<String>,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            actor_id: Some(actor_id),
+            action: action.to_string(),
+            target_user_id,
+            details,
+            ip_address,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Create an entry for an action the system performed on its own
+    pub fn system(action: &str, target_user_id: Option<Uuid>, details: serde_json::Value) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            actor_id: None,
+            action: action.to_string(),
+            target_user_id,
+            details,
+            ip_address: None,
+            created_at: Utc::now(),
+        }
+    }
+}
@@ -0,0 +1,81 @@
+//! Personal data export domain model
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Archive format of a data export
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema, sqlx::Type,
+)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// One JSON document with a key per section
+    #[default]
+    Json,
+    /// A ZIP archive with a JSON file per section
+    Zip,
+}
+
+impl ExportFormat {
+    /// MIME type of the archive
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Zip => "application/zip",
+        }
+    }
+
+    /// File name extension of the archive
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Zip => "zip",
+        }
+    }
+}
+
+/// Progress of a data export
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum ExportStatus {
+    /// Being assembled
+    Pending,
+    /// Ready to download until it expires
+    Ready,
+    /// Could not be assembled; request a new export
+    Failed,
+}
+
+/// Request for a copy of everything stored about a user (the archive itself
+/// is loaded separately)
+#[derive(Debug, Clone, FromRow)]
+pub struct DataExport {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub format: ExportFormat,
+    pub status: ExportStatus,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    /// When a ready archive is deleted
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl DataExport {
+    /// Create a new pending export (for insertion)
+    pub fn new(user_id: Uuid, format: ExportFormat) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            user_id,
+            format,
+            status: ExportStatus::Pending,
+            created_at: Utc::now(),
+            completed_at: None,
+            expires_at: None,
+        }
+    }
+}
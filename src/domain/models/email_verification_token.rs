@@ -0,0 +1,32 @@
+//! Email verification token domain model
+
+use chrono::{DateTime, Duration, Utc};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Email verification token entity (only the token hash is stored)
+#[derive(Debug, Clone, FromRow)]
+pub struct EmailVerificationToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// Address the token verifies
+    pub email: String,
+    pub token_hash: String,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl EmailVerificationToken {
+    /// Create a new email verification token instance (for insertion)
+    pub fn new(user_id: Uuid, email: String, token_hash: String, expiration_hours: i64) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            user_id,
+            email,
+            token_hash,
+            expires_at: now + Duration::hours(expiration_hours),
+            created_at: now,
+        }
+    }
+}
@@ -1,5 +1,25 @@
 //! Domain models
 
+mod account_lockout;
+mod api_key;
+mod audit_log;
+mod data_export;
+mod email_verification_token;
+mod organization;
+mod password_reset_token;
+mod refresh_token;
 mod user;
+mod user_mfa;
 
-pub use user::User;
+pub use account_lockout::AccountLockout;
+pub use api_key::ApiKey;
+pub use audit_log::AuditLog;
+pub use data_export::{DataExport, ExportFormat, ExportStatus};
+pub use email_verification_token::EmailVerificationToken;
+pub use organization::{
+    Invitation, Member, Membership, OrgRole, Organization, OrganizationMembership, Tenant,
+};
+pub use password_reset_token::PasswordResetToken;
+pub use refresh_token::RefreshToken;
+pub use user::{User, UserFilter, UserSearchResult, UserSortField};
+pub use user_mfa::UserMfa;
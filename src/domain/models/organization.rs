@@ -0,0 +1,173 @@
+//! Organization domain models
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// A customer organization (tenant)
+#[derive(Debug, Clone, FromRow)]
+pub struct Organization {
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Organization {
+    /// Create a new organization instance (for insertion)
+    pub fn new(name: String) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// Role of a member within an organization
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum OrgRole {
+    /// Full control, including granting and revoking ownership
+    Owner,
+    /// Invites, removes and changes the role of members other than owners
+    Admin,
+    /// Sees the organization and its members
+    Member,
+}
+
+impl OrgRole {
+    /// Whether members with this role may invite, remove and change members
+    pub fn can_manage_members(self) -> bool {
+        matches!(self, Self::Owner | Self::Admin)
+    }
+
+    /// Whether members with this role may give `other` to someone or take it
+    /// away from them
+    pub fn can_assign(self, other: OrgRole) -> bool {
+        match other {
+            Self::Owner => self == Self::Owner,
+            Self::Admin | Self::Member => self.can_manage_members(),
+        }
+    }
+}
+
+/// The organization a request acts on.
+///
+/// Tenant-scoped repositories take one when they are created and limit every
+/// query to its rows. It can only be obtained from a [`Membership`], so data
+/// is never scoped to an organization the caller does not belong to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tenant {
+    org_id: Uuid,
+    /// The member acting on the organization
+    user_id: Uuid,
+}
+
+impl Tenant {
+    pub fn org_id(&self) -> Uuid {
+        self.org_id
+    }
+
+    pub fn user_id(&self) -> Uuid {
+        self.user_id
+    }
+}
+
+/// A user's membership in an organization
+#[derive(Debug, Clone, FromRow)]
+pub struct Membership {
+    pub org_id: Uuid,
+    pub user_id: Uuid,
+    pub role: OrgRole,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Membership {
+    /// Create a new membership instance (for insertion)
+    pub fn new(org_id: Uuid, user_id: Uuid, role: OrgRole) -> Self {
+        Self {
+            org_id,
+            user_id,
+            role,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Scope for acting on the membership's organization
+    pub fn tenant(&self) -> Tenant {
+        Tenant {
+            org_id: self.org_id,
+            user_id: self.user_id,
+        }
+    }
+}
+
+/// A membership together with the member's profile
+#[derive(Debug, Clone, FromRow)]
+pub struct Member {
+    #[sqlx(flatten)]
+    pub membership: Membership,
+    pub email: String,
+    pub name: String,
+}
+
+/// An organization as seen by one of its members
+#[derive(Debug, Clone, FromRow)]
+pub struct OrganizationMembership {
+    #[sqlx(flatten)]
+    pub organization: Organization,
+    pub role: OrgRole,
+}
+
+/// Pending invitation to join an organization (only the token hash is stored)
+#[derive(Debug, Clone, FromRow)]
+pub struct Invitation {
+    pub id: Uuid,
+    pub org_id: Uuid,
+    pub email: String,
+    pub role: OrgRole,
+    pub token_hash: String,
+    pub invited_by: Option<Uuid>,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Invitation {
+    /// Create a new invitation instance (for insertion)
+    pub fn new(
+        tenant: Tenant,
+        email: String,
+        role: OrgRole,
+        token_hash: String,
+        invited_by: Uuid,
+        expiration_hours: i64,
+    ) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            org_id: tenant.org_id,
+            email,
+            role,
+            token_hash,
+            invited_by: Some(invited_by),
+            expires_at: now + Duration::hours(expiration_hours),
+            created_at: now,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_owners_assign_ownership() {
+        assert!(OrgRole::Owner.can_assign(OrgRole::Owner));
+        assert!(!OrgRole::Admin.can_assign(OrgRole::Owner));
+        assert!(OrgRole::Admin.can_assign(OrgRole::Member));
+        assert!(!OrgRole::Member.can_assign(OrgRole::Member));
+    }
+}
@@ -0,0 +1,29 @@
+//! Password reset token domain model
+
+use chrono::{DateTime, Duration, Utc};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Password reset token entity (only the token hash is stored)
+#[derive(Debug, Clone, FromRow)]
+pub struct PasswordResetToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub token_hash: String,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl PasswordResetToken {
+    /// Create a new password reset token instance (for insertion)
+    pub fn new(user_id: Uuid, token_hash: String, expiration_minutes: i64) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            user_id,
+            token_hash,
+            expires_at: now + Duration::minutes(expiration_minutes),
+            created_at: now,
+        }
+    }
+}
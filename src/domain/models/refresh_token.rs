@@ -0,0 +1,49 @@
+//! Refresh token domain model
+
+use chrono::{DateTime, Duration, Utc};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Refresh token entity (only the token hash is stored)
+#[derive(Debug, Clone, FromRow)]
+pub struct RefreshToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub family_id: Uuid,
+    /// Organization the session is switched to
+    pub org_id: Option<Uuid>,
+    pub token_hash: String,
+    pub expires_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub replaced_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl RefreshToken {
+    /// Create a new refresh token instance (for insertion)
+    pub fn new(
+        user_id: Uuid,
+        family_id: Uuid,
+        org_id: Option<Uuid>,
+        token_hash: String,
+        expiration_days: i64,
+    ) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            user_id,
+            family_id,
+            org_id,
+            token_hash,
+            expires_at: now + Duration::days(expiration_days),
+            revoked_at: None,
+            replaced_by: None,
+            created_at: now,
+        }
+    }
+
+    /// Check if the token has expired
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now()
+    }
+}
@@ -14,6 +14,11 @@ pub struct User {
     pub password_hash: String,
     pub name: String,
     pub is_active: bool,
+    pub email_verified_at: Option<DateTime<Utc>>,
+    /// Last password change; access tokens issued earlier are rejected
+    pub password_changed_at: Option<DateTime<Utc>>,
+    /// When the owner deleted the account; it is purged after a grace period
+    pub deleted_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -28,8 +33,69 @@ impl User {
             password_hash,
             name,
             is_active: true,
+            email_verified_at: None,
+            password_changed_at: None,
+            deleted_at: None,
             created_at: now,
             updated_at: now,
         }
     }
+
+    /// Check if the user has verified their email address
+    pub fn is_email_verified(&self) -> bool {
+        self.email_verified_at.is_some()
+    }
+}
+
+/// Criteria for listing users; `None` fields don't filter
+#[derive(Debug, Clone, Default)]
+pub struct UserFilter {
+    /// Part of the email or name, ignoring case
+    pub search: Option<String>,
+    pub active: Option<bool>,
+    /// Created at or after this time
+    pub created_after: Option<DateTime<Utc>>,
+    /// Created before this time
+    pub created_before: Option<DateTime<Utc>>,
+    /// Domain of the email address, ignoring case
+    pub email_domain: Option<String>,
+}
+
+/// Fields users can be sorted by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum UserSortField {
+    #[default]
+    CreatedAt,
+    Email,
+    Name,
+}
+
+impl UserSortField {
+    /// Column of the `users` table
+    pub fn column(self) -> &'static str {
+        match self {
+            Self::CreatedAt => "created_at",
+            Self::Email => "email",
+            Self::Name => "name",
+        }
+    }
+
+    /// The user's sort key, as stored in a pagination cursor
+    pub fn value_of(self, user: &User) -> String {
+        match self {
+            Self::CreatedAt => user.created_at.to_rfc3339(),
+            Self::Email => user.email.clone(),
+            Self::Name => user.name.clone(),
+        }
+    }
+}
+
+/// A user found by a search, with how well it matched
+#[derive(Debug, Clone, FromRow)]
+pub struct UserSearchResult {
+    #[sqlx(flatten)]
+    pub user: User,
+    /// Higher is a better match
+    pub rank: f32,
 }
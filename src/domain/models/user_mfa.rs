@@ -0,0 +1,18 @@
+//! Two-factor authentication domain model
+
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+
+/// TOTP enrollment of a user (the secret is stored encrypted)
+#[derive(Debug, Clone, FromRow)]
+pub struct UserMfa {
+    pub secret_encrypted: String,
+    pub enabled_at: Option<DateTime<Utc>>,
+}
+
+impl UserMfa {
+    /// Check if enrollment has been confirmed
+    pub fn is_enabled(&self) -> bool {
+        self.enabled_at.is_some()
+    }
+}
@@ -0,0 +1,596 @@
+//! Admin service - user management by administrators
+//!
+//! Every action is recorded in the audit log with the administrator who
+//! performed it.
+
+use std::net::IpAddr;
+
+use chrono::Utc;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{
+    common::pagination::SortDirection,
+    config::AppState,
+    domain::{
+        errors::DomainError,
+        models::{AuditLog, User, UserFilter, UserSortField},
+        services::{
+            EmailVerificationService, ImportReport, ImportedUser, LockoutService, SessionService,
+            UserService,
+        },
+    },
+    infrastructure::repositories::{AuditLogRepository, RoleRepository, UserRepository},
+};
+
+/// The administrator performing an action
+#[derive(Debug, Clone, Copy)]
+pub struct Actor {
+    pub user_id: Uuid,
+    pub ip: IpAddr,
+}
+
+/// Changes to a user account; `None` leaves a field unchanged
+#[derive(Debug, Default)]
+pub struct AdminUserUpdate {
+    pub name: Option<String>,
+    /// A new address is unverified, and gets a verification email, unless
+    /// `email_verified` is also set
+    pub email: Option<String>,
+    pub email_verified: Option<bool>,
+}
+
+/// A page of users and the number of users matching in total
+#[derive(Debug)]
+pub struct UserPage {
+    pub users: Vec<User>,
+    pub total: i64,
+}
+
+pub struct AdminService<'a> {
+    state: &'a AppState,
+    user_repo: UserRepository<'a>,
+    role_repo: RoleRepository<'a>,
+    audit_log_repo: AuditLogRepository<'a>,
+}
+
+impl<'a> AdminService<'a> {
+    pub fn new(state: &'a AppState) -> Self {
+        Self {
+            state,
+            user_repo: UserRepository::new(&state.db_pool),
+            role_repo: RoleRepository::new(&state.db_pool),
+            audit_log_repo: AuditLogRepository::new(&state.db_pool),
+        }
+    }
+
+    /// List users, including deactivated ones unless `active` is set
+    pub async fn list_users(
+        &self,
+        actor: &Actor,
+        search: Option<&str>,
+        active: Option<bool>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<UserPage, DomainError> {
+        let filter = UserFilter {
+            search: search.map(str::to_string),
+            active,
+            ..UserFilter::default()
+        };
+        let users = self
+            .user_repo
+            .list(
+                &filter,
+                UserSortField::CreatedAt,
+                SortDirection::Desc,
+                None,
+                offset,
+                limit,
+            )
+            .await?;
+        let total = self.user_repo.count(&filter).await?;
+
+        self.audit(
+            actor,
+            "users.listed",
+            None,
+            json!({ "search": search, "active": active, "limit": limit, "offset": offset }),
+        )
+        .await?;
+
+        Ok(UserPage { users, total })
+    }
+
+    /// Get any user, active or not, with their role names
+    pub async fn get_user(
+        &self,
+        actor: &Actor,
+        id: Uuid,
+    ) -> Result<(User, Vec<String>), DomainError> {
+        let user = self.find(id).await?;
+        let roles = self.role_repo.find_roles_for_user(id).await?;
+
+        self.audit(actor, "user.viewed", Some(id), json!({}))
+            .await?;
+
+        Ok((user, roles))
+    }
+
+    /// Change a user's name, email or verification status
+    pub async fn update_user(
+        &self,
+        actor: &Actor,
+        id: Uuid,
+        update: AdminUserUpdate,
+    ) -> Result<User, DomainError> {
+        let mut user = self.find(id).await?;
+        let mut changed = Vec::new();
+
+        if let Some(name) = update.name
+            && name != user.name
+        {
+            user.name = name;
+            changed.push("name");
+        }
+        let mut email_changed = false;
+        if let Some(email) = update
+            .email
+            .map(|email| self.state.config.normalize_email(&email))
+            && email != user.email
+        {
+            user.email = email;
+            user.email_verified_at = None;
+            email_changed = true;
+            changed.push("email");
+        }
+        match update.email_verified {
+            Some(true) if !user.is_email_verified() => {
+                user.email_verified_at = Some(Utc::now());
+                changed.push("email_verified");
+            }
+            Some(false) if user.is_email_verified() => {
+                user.email_verified_at = None;
+                changed.push("email_verified");
+            }
+            _ => {}
+        }
+
+        self.user_repo
+            .update(&user)
+            .await
+            .map_err(|err| match err.as_database_error() {
+                Some(db_err) if db_err.is_unique_violation() => DomainError::UserAlreadyExists,
+                _ => err.into(),
+            })?;
+
+        if email_changed && user.is_active && !user.is_email_verified() {
+            EmailVerificationService::new(self.state)
+                .send_verification(&user)
+                .await?;
+        }
+
+        self.audit(
+            actor,
+            "user.updated",
+            Some(id),
+            json!({ "changed": changed }),
+        )
+        .await?;
+
+        Ok(user)
+    }
+
+    /// Deactivate a user and end all of their sessions.
+    ///
+    /// Deactivated users cannot log in and their API keys stop working until
+    /// they are reactivated.
+    pub async fn deactivate_user(&self, actor: &Actor, id: Uuid) -> Result<(), DomainError> {
+        if id == actor.user_id {
+            return Err(DomainError::CannotTargetSelf);
+        }
+        if !self.user_repo.set_active(id, false).await? {
+            return Err(DomainError::UserNotFound);
+        }
+        SessionService::new(self.state).end_all_sessions(id).await?;
+
+        self.audit(actor, "user.deactivated", Some(id), json!({}))
+            .await
+    }
+
+    /// Reactivate a deactivated user. Deleted users are restored with
+    /// [`Self::restore_user`] instead.
+    pub async fn reactivate_user(&self, actor: &Actor, id: Uuid) -> Result<(), DomainError> {
+        if !self.user_repo.set_active(id, true).await? {
+            return Err(DomainError::UserNotFound);
+        }
+
+        self.audit(actor, "user.reactivated", Some(id), json!({}))
+            .await
+    }
+
+    /// Undo the deletion of an account that has not been purged yet
+    pub async fn restore_user(&self, actor: &Actor, id: Uuid) -> Result<User, DomainError> {
+        if !self.user_repo.restore(id).await? {
+            return Err(DomainError::UserNotFound);
+        }
+        let user = self.find(id).await?;
+
+        self.audit(actor, "user.restored", Some(id), json!({}))
+            .await?;
+
+        Ok(user)
+    }
+
+    /// Create users migrated from another system with their password hashes,
+    /// as [`UserService::import`] does
+    pub async fn import_users(
+        &self,
+        actor: &Actor,
+        users: Vec<ImportedUser>,
+    ) -> Result<ImportReport, DomainError> {
+        let report = UserService::new(self.state).import(users).await?;
+
+        self.audit(
+            actor,
+            "users.imported",
+            None,
+            json!({ "imported": report.imported, "skipped": report.skipped.len() }),
+        )
+        .await?;
+
+        Ok(report)
+    }
+
+    /// Lift a lock placed after repeated failed logins
+    pub async fn unlock_user(&self, actor: &Actor, id: Uuid) -> Result<(), DomainError> {
+        LockoutService::new(self.state).unlock(id).await?;
+
+        self.audit(actor, "user.unlocked", Some(id), json!({}))
+            .await
+    }
+
+    /// Permanently delete a user and all of their data. The only owner of an
+    /// organization cannot be deleted until it has another owner.
+    pub async fn delete_user(&self, actor: &Actor, id: Uuid) -> Result<(), DomainError> {
+        if id == actor.user_id {
+            return Err(DomainError::CannotTargetSelf);
+        }
+        self.find(id).await?;
+        if self.user_repo.is_last_owner(id).await? {
+            return Err(DomainError::LastOrganizationOwner);
+        }
+
+        // Replace any cached revocation state so their tokens stop working right away
+        SessionService::new(self.state).end_all_sessions(id).await?;
+        if !self.user_repo.hard_delete(id).await? {
+            return Err(DomainError::UserNotFound);
+        }
+
+        self.audit(actor, "user.deleted", Some(id), json!({})).await
+    }
+
+    async fn find(&self, id: Uuid) -> Result<User, DomainError> {
+        self.user_repo
+            .find_by_id_including_inactive(id)
+            .await?
+            .ok_or(DomainError::UserNotFound)
+    }
+
+    async fn audit(
+        &self,
+        actor: &Actor,
+        action: &str,
+        target_user_id: Option<Uuid>,
+        details: serde_json::Value,
+    ) -> Result<(), DomainError> {
+        let entry = AuditLog::new(
+            actor.user_id,
+            action,
+            target_user_id,
+            details,
+            Some(actor.ip.to_string()),
+        );
+        self.audit_log_repo.create(&entry).await?;
+        tracing::info!(
+            "Admin {} performed {} on {:?}",
+            actor.user_id,
+            action,
+            target_user_id
+        );
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::{common::jwt::verify_token, domain::services::AuthService, test_utils::test_state};
+
+    async fn audit_actions(state: &AppState, user_id: Uuid) -> Vec<String> {
+        sqlx::query_scalar(
+            "SELECT action FROM audit_logs WHERE target_user_id = $1 ORDER BY created_at",
+        )
+        .bind(user_id)
+        .fetch_all(&state.db_pool)
+        .await
+        .unwrap()
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_deactivate_reactivate_and_delete(pool: PgPool) {
+        let (state, _mailer) = test_state(pool);
+        let auth_service = AuthService::new(&state);
+        let admin_service = AdminService::new(&state);
+        let ip = Ipv4Addr::LOCALHOST.into();
+        auth_service
+            .register("admin@example.com", "password123", "Admin User")
+            .await
+            .unwrap();
+        let tokens = auth_service
+            .register("target@example.com", "password123", "Target User")
+            .await
+            .unwrap()
+            .unwrap();
+        let user_service = UserService::new(&state);
+        let admin = user_service
+            .get_by_email("admin@example.com")
+            .await
+            .unwrap();
+        let target = user_service
+            .get_by_email("target@example.com")
+            .await
+            .unwrap();
+        let actor = Actor {
+            user_id: admin.id,
+            ip,
+        };
+
+        assert!(matches!(
+            admin_service.deactivate_user(&actor, admin.id).await,
+            Err(DomainError::CannotTargetSelf)
+        ));
+
+        admin_service
+            .deactivate_user(&actor, target.id)
+            .await
+            .unwrap();
+        let claims = verify_token(&tokens.access_token, &state.jwt_keys).unwrap();
+        let session_service = SessionService::new(&state);
+        assert!(session_service.is_revoked(&claims).await.unwrap());
+        assert!(
+            auth_service
+                .login("target@example.com", "password123", ip)
+                .await
+                .is_err()
+        );
+
+        // Deactivated users are still visible to administrators
+        let page = admin_service
+            .list_users(&actor, Some("TARGET"), Some(false), 20, 0)
+            .await
+            .unwrap();
+        assert_eq!(page.total, 1);
+        assert_eq!(page.users[0].id, target.id);
+        let (found, roles) = admin_service.get_user(&actor, target.id).await.unwrap();
+        assert!(!found.is_active);
+        assert_eq!(roles, vec!["user".to_string()]);
+
+        admin_service
+            .reactivate_user(&actor, target.id)
+            .await
+            .unwrap();
+        assert!(
+            auth_service
+                .login("target@example.com", "password123", ip)
+                .await
+                .is_ok()
+        );
+
+        admin_service.delete_user(&actor, target.id).await.unwrap();
+        assert!(matches!(
+            admin_service.get_user(&actor, target.id).await,
+            Err(DomainError::UserNotFound)
+        ));
+        // Tokens of deleted users are rejected even with a cold cache
+        state.revocation_cache.user_cutoffs.remove(&target.id);
+        assert!(session_service.is_revoked(&claims).await.unwrap());
+
+        assert_eq!(
+            audit_actions(&state, target.id).await,
+            vec![
+                "user.deactivated",
+                "user.viewed",
+                "user.reactivated",
+                "user.deleted"
+            ]
+        );
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_update_user(pool: PgPool) {
+        let (state, _mailer) = test_state(pool);
+        let auth_service = AuthService::new(&state);
+        let admin_service = AdminService::new(&state);
+        auth_service
+            .register("target@example.com", "password123", "Target User")
+            .await
+            .unwrap();
+        let target = UserService::new(&state)
+            .get_by_email("target@example.com")
+            .await
+            .unwrap();
+        let actor = Actor {
+            user_id: Uuid::new_v4(),
+            ip: Ipv4Addr::LOCALHOST.into(),
+        };
+
+        let updated = admin_service
+            .update_user(
+                &actor,
+                target.id,
+                AdminUserUpdate {
+                    email: Some("renamed@example.com".to_string()),
+                    email_verified: Some(true),
+                    ..AdminUserUpdate::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(updated.email, "renamed@example.com");
+        assert!(updated.is_email_verified());
+
+        let details: serde_json::Value =
+            sqlx::query_scalar("SELECT details FROM audit_logs WHERE action = 'user.updated'")
+                .fetch_one(&state.db_pool)
+                .await
+                .unwrap();
+        assert_eq!(details, json!({ "changed": ["email", "email_verified"] }));
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_restore_deleted_user(pool: PgPool) {
+        let (state, _mailer) = test_state(pool);
+        let auth_service = AuthService::new(&state);
+        let admin_service = AdminService::new(&state);
+        let user_service = UserService::new(&state);
+        let tokens = auth_service
+            .register("restore@example.com", "password123", "Restore User")
+            .await
+            .unwrap()
+            .unwrap();
+        let claims = verify_token(&tokens.access_token, &state.jwt_keys).unwrap();
+        let actor = Actor {
+            user_id: Uuid::new_v4(),
+            ip: Ipv4Addr::LOCALHOST.into(),
+        };
+        user_service
+            .delete_account(&claims, "password123")
+            .await
+            .unwrap();
+
+        // Deleted accounts are restored, not reactivated
+        assert!(matches!(
+            admin_service.reactivate_user(&actor, claims.sub).await,
+            Err(DomainError::UserNotFound)
+        ));
+        let restored = admin_service
+            .restore_user(&actor, claims.sub)
+            .await
+            .unwrap();
+        assert!(restored.is_active);
+        assert_eq!(restored.deleted_at, None);
+        assert!(
+            auth_service
+                .login(
+                    "restore@example.com",
+                    "password123",
+                    Ipv4Addr::LOCALHOST.into()
+                )
+                .await
+                .is_ok()
+        );
+        assert!(matches!(
+            admin_service.restore_user(&actor, claims.sub).await,
+            Err(DomainError::UserNotFound)
+        ));
+        assert_eq!(
+            audit_actions(&state, claims.sub).await,
+            vec!["user.restored"]
+        );
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_unlock_user(pool: PgPool) {
+        let (state, _mailer) = test_state(pool);
+        let auth_service = AuthService::new(&state);
+        let admin_service = AdminService::new(&state);
+        let ip = Ipv4Addr::LOCALHOST.into();
+        auth_service
+            .register("locked@example.com", "password123", "Locked User")
+            .await
+            .unwrap();
+        let user = UserService::new(&state)
+            .get_by_email("locked@example.com")
+            .await
+            .unwrap();
+        let actor = Actor {
+            user_id: Uuid::new_v4(),
+            ip,
+        };
+
+        for _ in 0..state.config.login_max_failed_attempts {
+            assert!(
+                auth_service
+                    .login("locked@example.com", "wrong", ip)
+                    .await
+                    .is_err()
+            );
+        }
+        assert!(matches!(
+            auth_service
+                .login("locked@example.com", "password123", ip)
+                .await,
+            Err(DomainError::AccountLocked { .. })
+        ));
+
+        admin_service.unlock_user(&actor, user.id).await.unwrap();
+        assert!(
+            auth_service
+                .login("locked@example.com", "password123", ip)
+                .await
+                .is_ok()
+        );
+        assert!(matches!(
+            admin_service.unlock_user(&actor, Uuid::new_v4()).await,
+            Err(DomainError::UserNotFound)
+        ));
+        assert_eq!(audit_actions(&state, user.id).await, vec!["user.unlocked"]);
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_import_users_is_audited(pool: PgPool) {
+        let (state, _mailer) = test_state(pool);
+        let actor = Actor {
+            user_id: Uuid::new_v4(),
+            ip: Ipv4Addr::LOCALHOST.into(),
+        };
+        let imported = |email: &str, password_hash: &str| ImportedUser {
+            email: email.to_string(),
+            name: "Imported User".to_string(),
+            password_hash: password_hash.to_string(),
+            email_verified: false,
+        };
+
+        let report = AdminService::new(&state)
+            .import_users(
+                &actor,
+                vec![
+                    imported("one@example.com", &bcrypt::hash("password123", 4).unwrap()),
+                    imported("two@example.com", "not-a-hash"),
+                ],
+            )
+            .await
+            .unwrap();
+        assert_eq!(report.imported, 1);
+
+        let (actor_id, details): (Uuid, serde_json::Value) = sqlx::query_as(
+            "SELECT actor_id, details FROM audit_logs WHERE action = 'users.imported'",
+        )
+        .fetch_one(&state.db_pool)
+        .await
+        .unwrap();
+        assert_eq!(actor_id, actor.user_id);
+        assert_eq!(details, json!({ "imported": 1, "skipped": 1 }));
+    }
+}
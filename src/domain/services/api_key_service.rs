@@ -0,0 +1,179 @@
+//! API key service - personal keys for machine clients
+
+use rand::{Rng, rngs::OsRng};
+use uuid::Uuid;
+
+use crate::{
+    common::token,
+    config::AppState,
+    domain::{errors::DomainError, models::ApiKey},
+    infrastructure::repositories::{ApiKeyRepository, RoleRepository},
+};
+
+/// Marks a string as one of our API keys, e.g. in secret scanners
+const KEY_PREFIX: &str = "ak";
+/// Characters in the public key prefix
+const PREFIX_LENGTH: usize = 8;
+const PREFIX_ALPHABET: &[u8] = b"abcdefghijkmnpqrstuvwxyz23456789";
+
+/// A newly created key together with its secret, which is never shown again
+#[derive(Debug)]
+pub struct CreatedApiKey {
+    pub key: ApiKey,
+    pub secret: String,
+}
+
+/// The owner of a valid API key and what the key may do
+#[derive(Debug)]
+pub struct ApiKeyPrincipal {
+    pub key: ApiKey,
+    pub roles: Vec<String>,
+    /// The key's scopes the owner still holds
+    pub permissions: Vec<String>,
+}
+
+pub struct ApiKeyService<'a> {
+    api_key_repo: ApiKeyRepository<'a>,
+    role_repo: RoleRepository<'a>,
+}
+
+impl<'a> ApiKeyService<'a> {
+    pub fn new(state: &'a AppState) -> Self {
+        Self {
+            api_key_repo: ApiKeyRepository::new(&state.db_pool),
+            role_repo: RoleRepository::new(&state.db_pool),
+        }
+    }
+
+    /// Create a key limited to `scopes`, each of which the user must hold
+    pub async fn create(
+        &self,
+        user_id: Uuid,
+        name: String,
+        scopes: Vec<String>,
+        expires_in_days: Option<i64>,
+    ) -> Result<CreatedApiKey, DomainError> {
+        let permissions = self.role_repo.find_permissions_for_user(user_id).await?;
+        if let Some(scope) = scopes.iter().find(|scope| !permissions.contains(scope)) {
+            return Err(DomainError::InvalidApiKeyScope(scope.clone()));
+        }
+
+        let prefix = format!("{KEY_PREFIX}_{}", generate_prefix());
+        let secret = format!("{prefix}_{}", token::generate());
+        let key = ApiKey::new(
+            user_id,
+            name,
+            prefix,
+            token::hash(&secret),
+            scopes,
+            expires_in_days,
+        );
+        self.api_key_repo.create(&key).await?;
+
+        Ok(CreatedApiKey { key, secret })
+    }
+
+    /// List a user's keys that have not been revoked
+    pub async fn list(&self, user_id: Uuid) -> Result<Vec<ApiKey>, DomainError> {
+        Ok(self.api_key_repo.list_for_user(user_id).await?)
+    }
+
+    /// Revoke one of a user's keys
+    pub async fn revoke(&self, user_id: Uuid, id: Uuid) -> Result<(), DomainError> {
+        if !self.api_key_repo.revoke(user_id, id).await? {
+            return Err(DomainError::ApiKeyNotFound);
+        }
+
+        Ok(())
+    }
+
+    /// Resolve a presented key to its owner.
+    ///
+    /// Permissions are re-read on every request, so a key never grants more
+    /// than its owner currently holds.
+    pub async fn authenticate(&self, secret: &str) -> Result<ApiKeyPrincipal, DomainError> {
+        let key = self
+            .api_key_repo
+            .find_by_hash(&token::hash(secret))
+            .await?
+            .filter(ApiKey::is_active)
+            .ok_or(DomainError::InvalidApiKey)?;
+
+        self.api_key_repo.touch(key.id).await?;
+
+        let roles = self.role_repo.find_roles_for_user(key.user_id).await?;
+        let permissions = self
+            .role_repo
+            .find_permissions_for_user(key.user_id)
+            .await?
+            .into_iter()
+            .filter(|permission| key.scopes.contains(permission))
+            .collect();
+
+        Ok(ApiKeyPrincipal {
+            key,
+            roles,
+            permissions,
+        })
+    }
+}
+
+fn generate_prefix() -> String {
+    (0..PREFIX_LENGTH)
+        .map(|_| PREFIX_ALPHABET[OsRng.gen_range(0..PREFIX_ALPHABET.len())] as char)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::{
+        domain::services::{AuthService, UserService},
+        test_utils::test_state,
+    };
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_api_key_lifecycle(pool: PgPool) {
+        let (state, _mailer) = test_state(pool);
+        AuthService::new(&state)
+            .register("keys@example.com", "password123", "Key User")
+            .await
+            .unwrap();
+        let user = UserService::new(&state)
+            .get_by_email("keys@example.com")
+            .await
+            .unwrap();
+        let api_key_service = ApiKeyService::new(&state);
+
+        // Scopes are limited to the owner's permissions
+        assert!(matches!(
+            api_key_service
+                .create(user.id, "ci".into(), vec!["users:read".into()], None)
+                .await,
+            Err(DomainError::InvalidApiKeyScope(_))
+        ));
+
+        let created = api_key_service
+            .create(user.id, "ci".into(), vec![], Some(30))
+            .await
+            .unwrap();
+        assert!(created.secret.starts_with(&created.key.prefix));
+
+        let principal = api_key_service.authenticate(&created.secret).await.unwrap();
+        assert_eq!(principal.key.user_id, user.id);
+        assert_eq!(principal.roles, vec!["user".to_string()]);
+
+        api_key_service
+            .revoke(user.id, created.key.id)
+            .await
+            .unwrap();
+        assert!(matches!(
+            api_key_service.authenticate(&created.secret).await,
+            Err(DomainError::InvalidApiKey)
+        ));
+        assert!(api_key_service.list(user.id).await.unwrap().is_empty());
+    }
+}
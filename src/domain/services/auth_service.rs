@@ -1,15 +1,65 @@
 //! Authentication service
 
+use std::net::IpAddr;
+
+use chrono::Utc;
+use uuid::Uuid;
+
 use crate::{
-    common::{jwt::create_token, password},
-    config::AppState,
-    domain::{errors::DomainError, models::User},
-    infrastructure::repositories::UserRepository,
+    common::{
+        jwt::{create_mfa_token, create_token},
+        password, token,
+    },
+    config::{AppState, EmailVerificationMode, RegistrationMode},
+    domain::{
+        errors::DomainError,
+        models::{RefreshToken, User},
+        services::{
+            EmailVerificationService, LockoutService, MfaService, PasswordPolicyService,
+            SessionService,
+        },
+    },
+    infrastructure::{
+        mail::{send_in_background, templates},
+        repositories::{
+            OrganizationRepository, RefreshTokenRepository, RoleRepository, UserRepository,
+        },
+    },
 };
 
+/// Access and refresh token pair issued on login, registration and refresh
+#[derive(Debug)]
+pub struct AuthTokens {
+    pub access_token: String,
+    /// Access token lifetime in seconds
+    pub expires_in: i64,
+    pub refresh_token: String,
+    /// Refresh token lifetime in seconds
+    pub refresh_expires_in: i64,
+}
+
+/// Role assigned to newly registered users
+pub(crate) const DEFAULT_ROLE: &str = "user";
+
+/// Lifetime of the token bridging the password and code steps of a two-factor login
+const MFA_TOKEN_EXPIRATION_MINUTES: i64 = 5;
+
+/// Result of the password step of a login
+#[derive(Debug)]
+pub enum LoginOutcome {
+    /// Login complete
+    Authenticated(AuthTokens),
+    /// The account has two-factor authentication enabled; exchange the token and
+    /// a valid code at `/auth/mfa/verify`
+    MfaRequired { mfa_token: String, expires_in: i64 },
+}
+
 pub struct AuthService<'a> {
     state: &'a AppState,
     user_repo: UserRepository<'a>,
+    refresh_token_repo: RefreshTokenRepository<'a>,
+    role_repo: RoleRepository<'a>,
+    organization_repo: OrganizationRepository<'a>,
 }
 
 impl<'a> AuthService<'a> {
@@ -17,66 +67,779 @@ impl<'a> AuthService<'a> {
         Self {
             state,
             user_repo: UserRepository::new(&state.db_pool),
+            refresh_token_repo: RefreshTokenRepository::new(&state.db_pool),
+            role_repo: RoleRepository::new(&state.db_pool),
+            organization_repo: OrganizationRepository::new(&state.db_pool),
         }
     }
 
-    /// Register a new user
+    /// Register a new user and send them a verification email.
+    ///
+    /// Returns `None` instead of tokens when unverified accounts may not log in.
+    /// In [`RegistrationMode::EnumerationResistant`] it always returns `None`, and a
+    /// taken email gets a notice sent to its owner instead of an error.
     pub async fn register(
         &self,
         email: &str,
         password: &str,
         name: &str,
-    ) -> Result<String, DomainError> {
+    ) -> Result<Option<AuthTokens>, DomainError> {
+        let resistant =
+            self.state.config.registration_mode == RegistrationMode::EnumerationResistant;
+        let email = &self.state.config.normalize_email(email);
+
+        PasswordPolicyService::new(self.state).check(password, email, name)?;
+
+        // Hash password before the lookup so both outcomes take the same time
+        let password_hash = password::hash(password, &self.state.config.hash_params())
+            .await
+            .map_err(|_| DomainError::PasswordHashingFailed)?;
+
         // Check if user already exists
-        if self.user_repo.find_by_email(email).await?.is_some() {
-            return Err(DomainError::UserAlreadyExists);
-        }
+        if let Some(existing) = self.user_repo.find_by_email(email).await? {
+            if !resistant {
+                return Err(DomainError::UserAlreadyExists);
+            }
 
-        // Hash password
-        let password_hash =
-            password::hash(password).map_err(|_| DomainError::PasswordHashingFailed)?;
+            let frontend_url = &self.state.config.frontend_url;
+            send_in_background(
+                self.state.mailer.clone(),
+                templates::account_exists(
+                    &existing.email,
+                    &format!("{frontend_url}/login"),
+                    &format!("{frontend_url}/forgot-password"),
+                ),
+            );
+            return Ok(None);
+        }
 
         // Create user
         let user = User::new(email.to_string(), password_hash, name.to_string());
 
-        // Save to database
-        self.user_repo.create(&user).await?;
+        // Save to database. Deactivated and deleted accounts keep their email
+        // until they are purged.
+        if let Err(err) = self.user_repo.create(&user).await {
+            return match err.as_database_error() {
+                Some(db_err) if db_err.is_unique_violation() => {
+                    if resistant {
+                        Ok(None)
+                    } else {
+                        Err(DomainError::UserAlreadyExists)
+                    }
+                }
+                _ => Err(err.into()),
+            };
+        }
+        self.role_repo.assign(user.id, DEFAULT_ROLE).await?;
 
-        // Generate JWT token
-        let token = create_token(
-            user.id,
-            &self.state.config.jwt_secret,
-            self.state.config.jwt_expiration_hours,
-        )
-        .map_err(|_| DomainError::TokenGenerationFailed)?;
+        // Prove ownership of the email
+        EmailVerificationService::new(self.state)
+            .send_verification(&user)
+            .await?;
+
+        if resistant
+            || self.state.config.email_verification_mode == EmailVerificationMode::BlockLogin
+        {
+            return Ok(None);
+        }
 
-        Ok(token)
+        self.create_session(user.id).await.map(Some)
     }
 
     /// Login with email and password
-    pub async fn login(&self, email: &str, password: &str) -> Result<String, DomainError> {
+    pub async fn login(
+        &self,
+        email: &str,
+        password: &str,
+        ip: IpAddr,
+    ) -> Result<LoginOutcome, DomainError> {
+        let lockout = LockoutService::new(self.state);
+        lockout.check_ip(ip)?;
+
+        // Locked addresses are rejected even with the right password, whether
+        // or not they belong to an account
+        let email = self.state.config.normalize_email(email);
+        lockout.check(&email).await?;
+
         // Find user by email
+        let params = self.state.config.hash_params();
+        let Some(user) = self.user_repo.find_by_email(&email).await? else {
+            // Do the work of a password check so timing does not reveal the email is unknown
+            password::verify_dummy(password, &params).await;
+            lockout.record_ip_failure(ip);
+            return Err(lockout.record_failure(&email, None).await?);
+        };
+
+        // Verify password
+        if !password::verify_padded(password, &user.password_hash, &params)
+            .await
+            .map_err(|_| DomainError::InvalidCredentials)?
+        {
+            lockout.record_ip_failure(ip);
+            return Err(lockout.record_failure(&email, Some(&user)).await?);
+        }
+
+        lockout.reset(&email).await?;
+        self.upgrade_hash(&user, password).await;
+
+        if self.state.config.email_verification_mode == EmailVerificationMode::BlockLogin
+            && !user.is_email_verified()
+        {
+            return Err(DomainError::EmailNotVerified);
+        }
+
+        // Second factor required before any session is created
+        if MfaService::new(self.state).is_enabled(user.id).await? {
+            let mfa_token =
+                create_mfa_token(user.id, &self.state.jwt_keys, MFA_TOKEN_EXPIRATION_MINUTES)
+                    .map_err(|_| DomainError::TokenGenerationFailed)?;
+
+            return Ok(LoginOutcome::MfaRequired {
+                mfa_token,
+                expires_in: MFA_TOKEN_EXPIRATION_MINUTES * 60,
+            });
+        }
+
+        self.create_session(user.id)
+            .await
+            .map(LoginOutcome::Authenticated)
+    }
+
+    /// Change the password of a logged-in user.
+    ///
+    /// Ends every existing session, including the caller's, and returns tokens
+    /// for a new one. Wrong current passwords count towards the account lockout.
+    pub async fn change_password(
+        &self,
+        user_id: Uuid,
+        current_password: &str,
+        new_password: &str,
+    ) -> Result<AuthTokens, DomainError> {
         let user = self
             .user_repo
-            .find_by_email(email)
+            .find_by_id(user_id)
             .await?
-            .ok_or(DomainError::InvalidCredentials)?;
+            .ok_or(DomainError::UserNotFound)?;
+
+        self.confirm_password(&user, current_password).await?;
+
+        PasswordPolicyService::new(self.state).check(new_password, &user.email, &user.name)?;
+        let password_hash = password::hash(new_password, &self.state.config.hash_params())
+            .await
+            .map_err(|_| DomainError::PasswordHashingFailed)?;
+
+        let changed_at = Utc::now();
+        self.user_repo
+            .update_password(user.id, &password_hash, changed_at)
+            .await?;
+        LockoutService::new(self.state).reset(&user.email).await?;
+        SessionService::new(self.state)
+            .end_sessions_before(user.id, changed_at)
+            .await?;
+        tracing::info!("User {} changed their password", user.id);
+
+        self.create_session(user.id).await
+    }
+
+    /// Check the password of a logged-in user before a sensitive change.
+    ///
+    /// Wrong passwords count towards the account lockout like failed logins.
+    pub async fn confirm_password(&self, user: &User, password: &str) -> Result<(), DomainError> {
+        let lockout = LockoutService::new(self.state);
+        lockout.check(&user.email).await?;
 
-        // Verify password
         if !password::verify(password, &user.password_hash)
-            .map_err(|_| DomainError::InvalidCredentials)?
+            .await
+            .unwrap_or(false)
         {
-            return Err(DomainError::InvalidCredentials);
+            return Err(
+                match lockout.record_failure(&user.email, Some(user)).await? {
+                    DomainError::InvalidCredentials => DomainError::IncorrectPassword,
+                    locked => locked,
+                },
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Rehash the password if its hash is weaker than the current parameters.
+    ///
+    /// Only possible right after a successful login, while the plaintext is at
+    /// hand. Failures are logged and do not fail the login.
+    async fn upgrade_hash(&self, user: &User, password: &str) {
+        let params = self.state.config.hash_params();
+        if !params.needs_rehash(&user.password_hash) {
+            return;
+        }
+
+        let result = match password::hash(password, &params).await {
+            Ok(password_hash) => self
+                .user_repo
+                .update_password_hash(user.id, &password_hash)
+                .await
+                .map_err(|err| err.to_string()),
+            Err(err) => Err(err.to_string()),
+        };
+
+        match result {
+            Ok(()) => tracing::info!("Upgraded password hash of user {}", user.id),
+            Err(err) => tracing::warn!(
+                "Failed to upgrade password hash of user {}: {}",
+                user.id,
+                err
+            ),
+        }
+    }
+
+    /// Start a new session (token family) for an authenticated user
+    pub async fn create_session(&self, user_id: Uuid) -> Result<AuthTokens, DomainError> {
+        self.issue_tokens(user_id, Uuid::new_v4()).await
+    }
+
+    /// Exchange a refresh token for a new token pair.
+    ///
+    /// The presented token is rotated: it is revoked and replaced by a new one in
+    /// the same family. Presenting a token that was already rotated means it has
+    /// leaked, so the whole family is revoked and the caller must log in again.
+    pub async fn refresh(&self, refresh_token: &str) -> Result<AuthTokens, DomainError> {
+        let stored = self.redeem(refresh_token).await?;
+
+        // Leaving the organization ends the session's access to it
+        let org_id = match stored.org_id {
+            Some(org_id) => self
+                .organization_repo
+                .find_membership(org_id, stored.user_id)
+                .await?
+                .map(|membership| membership.org_id),
+            None => None,
+        };
+
+        self.rotate(&stored, org_id).await
+    }
+
+    /// Switch the session of a refresh token to one of the user's
+    /// organizations, or back to no organization with `None`.
+    ///
+    /// Works like [`Self::refresh`], with the new tokens carrying the
+    /// organization in their `org_id` claim. Organizations the user does not
+    /// belong to are reported as not found.
+    pub async fn switch_organization(
+        &self,
+        user_id: Uuid,
+        refresh_token: &str,
+        org_id: Option<Uuid>,
+    ) -> Result<AuthTokens, DomainError> {
+        if let Some(org_id) = org_id
+            && self
+                .organization_repo
+                .find_membership(org_id, user_id)
+                .await?
+                .is_none()
+        {
+            return Err(DomainError::OrganizationNotFound);
+        }
+
+        let stored = self.redeem(refresh_token).await?;
+        if stored.user_id != user_id {
+            return Err(DomainError::InvalidRefreshToken);
+        }
+
+        self.rotate(&stored, org_id).await
+    }
+
+    /// Look up a refresh token that may be rotated, revoking its family when
+    /// it was reused or its user is gone
+    async fn redeem(&self, refresh_token: &str) -> Result<RefreshToken, DomainError> {
+        let stored = self
+            .refresh_token_repo
+            .find_by_hash(&token::hash(refresh_token))
+            .await?
+            .ok_or(DomainError::InvalidRefreshToken)?;
+
+        if stored.revoked_at.is_some() {
+            // Revoked by logout rather than by rotation: nothing was leaked
+            if stored.replaced_by.is_none() {
+                return Err(DomainError::InvalidRefreshToken);
+            }
+
+            tracing::warn!(
+                user_id = %stored.user_id,
+                family_id = %stored.family_id,
+                "Refresh token reuse detected, revoking token family"
+            );
+            self.refresh_token_repo
+                .revoke_family(stored.family_id)
+                .await?;
+            return Err(DomainError::RefreshTokenReused);
+        }
+
+        if stored.is_expired() {
+            return Err(DomainError::InvalidRefreshToken);
         }
 
+        // Make sure the user still exists and is active
+        if self.user_repo.find_by_id(stored.user_id).await?.is_none() {
+            self.refresh_token_repo
+                .revoke_family(stored.family_id)
+                .await?;
+            return Err(DomainError::InvalidRefreshToken);
+        }
+
+        Ok(stored)
+    }
+
+    /// Replace a redeemed refresh token with a new token pair for `org_id`
+    async fn rotate(
+        &self,
+        stored: &RefreshToken,
+        org_id: Option<Uuid>,
+    ) -> Result<AuthTokens, DomainError> {
+        let (raw_token, new_token) =
+            self.new_refresh_token(stored.user_id, stored.family_id, org_id);
+
+        if !self
+            .refresh_token_repo
+            .rotate(stored.id, &new_token)
+            .await?
+        {
+            // Another request rotated this token first
+            self.refresh_token_repo
+                .revoke_family(stored.family_id)
+                .await?;
+            return Err(DomainError::RefreshTokenReused);
+        }
+
+        self.build_tokens(stored.user_id, stored.family_id, org_id, raw_token)
+            .await
+    }
+
+    /// Issue an access token and a fresh refresh token in the given family
+    async fn issue_tokens(
+        &self,
+        user_id: Uuid,
+        family_id: Uuid,
+    ) -> Result<AuthTokens, DomainError> {
+        let (raw_token, refresh_token) = self.new_refresh_token(user_id, family_id, None);
+        self.refresh_token_repo.create(&refresh_token).await?;
+
+        self.build_tokens(user_id, family_id, None, raw_token).await
+    }
+
+    fn new_refresh_token(
+        &self,
+        user_id: Uuid,
+        family_id: Uuid,
+        org_id: Option<Uuid>,
+    ) -> (String, RefreshToken) {
+        let raw_token = token::generate();
+        let refresh_token = RefreshToken::new(
+            user_id,
+            family_id,
+            org_id,
+            token::hash(&raw_token),
+            self.state.config.refresh_token_expiration_days,
+        );
+
+        (raw_token, refresh_token)
+    }
+
+    async fn build_tokens(
+        &self,
+        user_id: Uuid,
+        session_id: Uuid,
+        org_id: Option<Uuid>,
+        refresh_token: String,
+    ) -> Result<AuthTokens, DomainError> {
+        let config = &self.state.config;
+
+        // Roles and permissions are re-read on every refresh, so changes apply
+        // within one access token lifetime
+        let roles = self.role_repo.find_roles_for_user(user_id).await?;
+        let permissions = self.role_repo.find_permissions_for_user(user_id).await?;
+
         // Generate JWT token
-        let token = create_token(
-            user.id,
-            &self.state.config.jwt_secret,
-            self.state.config.jwt_expiration_hours,
+        let access_token = create_token(
+            user_id,
+            session_id,
+            org_id,
+            roles,
+            permissions,
+            &self.state.jwt_keys,
+            config.jwt_expiration_minutes,
         )
         .map_err(|_| DomainError::TokenGenerationFailed)?;
 
-        Ok(token)
+        Ok(AuthTokens {
+            access_token,
+            expires_in: config.jwt_expiration_minutes * 60,
+            refresh_token,
+            refresh_expires_in: config.refresh_token_expiration_days * 86_400,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::Ipv4Addr,
+        time::{Duration, Instant},
+    };
+
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::{
+        common::{jwt::verify_token, password::HashParams, password_policy::PolicyViolation},
+        config::AppConfig,
+        domain::services::PasswordResetService,
+        test_utils::{test_config, test_state, test_state_with, token_from_email, wait_for_email},
+    };
+
+    /// Time of one failed login
+    async fn time_failed_login(auth_service: &AuthService<'_>, email: &str) -> Duration {
+        let started = Instant::now();
+        assert!(matches!(
+            auth_service
+                .login(email, "wrong_password", Ipv4Addr::LOCALHOST.into())
+                .await,
+            Err(DomainError::InvalidCredentials)
+        ));
+        started.elapsed()
+    }
+
+    fn median(mut times: Vec<Duration>) -> Duration {
+        times.sort();
+        times[times.len() / 2]
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_refresh_rotates_and_reuse_revokes_family(pool: PgPool) {
+        let (state, _mailer) = test_state(pool);
+        let auth_service = AuthService::new(&state);
+        let first = auth_service
+            .register("rotate@example.com", "password123", "Rotate User")
+            .await
+            .unwrap()
+            .unwrap();
+
+        let second = auth_service.refresh(&first.refresh_token).await.unwrap();
+        assert_ne!(second.refresh_token, first.refresh_token);
+        let first_claims = verify_token(&first.access_token, &state.jwt_keys).unwrap();
+        let second_claims = verify_token(&second.access_token, &state.jwt_keys).unwrap();
+        assert_eq!(second_claims.sid, first_claims.sid);
+
+        let third = auth_service.refresh(&second.refresh_token).await.unwrap();
+
+        // Replaying a rotated token revokes every token of the family
+        assert!(matches!(
+            auth_service.refresh(&first.refresh_token).await,
+            Err(DomainError::RefreshTokenReused)
+        ));
+        assert!(matches!(
+            auth_service.refresh(&third.refresh_token).await,
+            Err(DomainError::InvalidRefreshToken)
+        ));
+
+        // Other sessions are unaffected
+        let other = auth_service
+            .login(
+                "rotate@example.com",
+                "password123",
+                Ipv4Addr::LOCALHOST.into(),
+            )
+            .await
+            .unwrap();
+        let LoginOutcome::Authenticated(other) = other else {
+            panic!("unexpected second factor");
+        };
+        assert!(auth_service.refresh(&other.refresh_token).await.is_ok());
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_unknown_email_login_takes_as_long_as_wrong_password(pool: PgPool) {
+        const RUNS: usize = 15;
+
+        // Production costs, so hashing dominates the database round trips.
+        // Lockouts and delays are kept out of the way of repeated failures.
+        let params = HashParams::default();
+        let config = AppConfig {
+            argon2_memory_kib: params.memory_kib,
+            argon2_iterations: params.iterations,
+            login_max_failed_attempts: 100,
+            login_delay_after_attempts: 100,
+            ..test_config()
+        };
+        let (state, _mailer) = test_state_with(pool, config);
+        let auth_service = AuthService::new(&state);
+        auth_service
+            .register("timing@example.com", "password123", "Timing User")
+            .await
+            .unwrap();
+
+        // An imported bcrypt hash verifies much faster than Argon2
+        auth_service
+            .register("bcrypt@example.com", "password123", "Imported User")
+            .await
+            .unwrap();
+        let bcrypt_hash = bcrypt::hash("password123", 4).unwrap();
+        let bcrypt_user = auth_service
+            .user_repo
+            .find_by_email("bcrypt@example.com")
+            .await
+            .unwrap()
+            .unwrap();
+        auth_service
+            .user_repo
+            .update_password_hash(bcrypt_user.id, &bcrypt_hash)
+            .await
+            .unwrap();
+
+        // Warm up the dummy hash and the connection pool
+        time_failed_login(&auth_service, "nobody@example.com").await;
+
+        // Interleaved, so load changes during the test hit every path alike
+        let (mut known, mut unknown, mut legacy) = (Vec::new(), Vec::new(), Vec::new());
+        for _ in 0..RUNS {
+            known.push(time_failed_login(&auth_service, "timing@example.com").await);
+            unknown.push(time_failed_login(&auth_service, "nobody@example.com").await);
+            legacy.push(time_failed_login(&auth_service, "bcrypt@example.com").await);
+        }
+        let (known, unknown, legacy) = (median(known), median(unknown), median(legacy));
+
+        // Every path is dominated by one Argon2 verification at current costs
+        for (path, time) in [("unknown email", unknown), ("bcrypt hash", legacy)] {
+            assert!(
+                time * 4 >= known * 3 && known * 4 >= time * 3,
+                "{path} took {time:?}, wrong password took {known:?}"
+            );
+        }
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_login_upgrades_weaker_hash(pool: PgPool) {
+        let (weak_state, _mailer) = test_state(pool.clone());
+        let tokens = AuthService::new(&weak_state)
+            .register("rehash@example.com", "password123", "Rehash User")
+            .await
+            .unwrap()
+            .unwrap();
+
+        let config = AppConfig {
+            argon2_iterations: weak_state.config.argon2_iterations + 1,
+            ..test_config()
+        };
+        let (state, _mailer) = test_state_with(pool, config);
+        let user_repo = UserRepository::new(&state.db_pool);
+        let stored = |user: Option<User>| user.unwrap().password_hash;
+
+        let before = stored(user_repo.find_by_email("rehash@example.com").await.unwrap());
+        assert!(state.config.hash_params().needs_rehash(&before));
+
+        AuthService::new(&state)
+            .login(
+                "rehash@example.com",
+                "password123",
+                Ipv4Addr::LOCALHOST.into(),
+            )
+            .await
+            .unwrap();
+
+        let after = stored(user_repo.find_by_email("rehash@example.com").await.unwrap());
+        assert!(!state.config.hash_params().needs_rehash(&after));
+        assert!(password::verify("password123", &after).await.unwrap());
+
+        // The password did not change, so existing sessions stay valid
+        AuthService::new(&state)
+            .refresh(&tokens.refresh_token)
+            .await
+            .unwrap();
+        let claims = verify_token(&tokens.access_token, &state.jwt_keys).unwrap();
+        assert!(
+            !SessionService::new(&state)
+                .is_revoked(&claims)
+                .await
+                .unwrap()
+        );
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_register_enforces_password_policy(pool: PgPool) {
+        let config = AppConfig {
+            password_check_breached: true,
+            ..test_config()
+        };
+        let (state, _mailer) = test_state_with(pool, config);
+        let auth_service = AuthService::new(&state);
+
+        let Err(DomainError::WeakPassword(violations)) = auth_service
+            .register("weak@example.com", "password123", "Weak User")
+            .await
+        else {
+            panic!("breached password accepted");
+        };
+        assert_eq!(violations, vec![PolicyViolation::Breached]);
+
+        let Err(DomainError::WeakPassword(violations)) = auth_service
+            .register("weak@example.com", "weak-user", "Weak User")
+            .await
+        else {
+            panic!("password containing the email accepted");
+        };
+        assert_eq!(violations, vec![PolicyViolation::ContainsPersonalInfo]);
+
+        assert!(
+            auth_service
+                .register("weak@example.com", "correct-horse-battery", "Weak User")
+                .await
+                .is_ok()
+        );
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_change_password_revokes_earlier_tokens(pool: PgPool) {
+        let (state, mailer) = test_state(pool);
+        let auth_service = AuthService::new(&state);
+        let ip = Ipv4Addr::LOCALHOST.into();
+        let old_tokens = auth_service
+            .register("change@example.com", "old_password", "Change User")
+            .await
+            .unwrap()
+            .unwrap();
+        let user = UserRepository::new(&state.db_pool)
+            .find_by_email("change@example.com")
+            .await
+            .unwrap()
+            .unwrap();
+        let reset_service = PasswordResetService::new(&state);
+        reset_service
+            .request_reset("change@example.com")
+            .await
+            .unwrap();
+        let reset_token = token_from_email(
+            &wait_for_email(&mailer, "change@example.com", "Reset your password").await,
+        );
+
+        assert!(matches!(
+            auth_service
+                .change_password(user.id, "wrong_password", "new_password")
+                .await,
+            Err(DomainError::IncorrectPassword)
+        ));
+
+        let new_tokens = auth_service
+            .change_password(user.id, "old_password", "new_password")
+            .await
+            .unwrap();
+
+        let session_service = SessionService::new(&state);
+        let old_claims = verify_token(&old_tokens.access_token, &state.jwt_keys).unwrap();
+        let new_claims = verify_token(&new_tokens.access_token, &state.jwt_keys).unwrap();
+        assert!(session_service.is_revoked(&old_claims).await.unwrap());
+        assert!(!session_service.is_revoked(&new_claims).await.unwrap());
+
+        // The check survives a cold cache
+        state.revocation_cache.user_cutoffs.remove(&user.id);
+        assert!(session_service.is_revoked(&old_claims).await.unwrap());
+
+        assert!(matches!(
+            auth_service.refresh(&old_tokens.refresh_token).await,
+            Err(DomainError::InvalidRefreshToken)
+        ));
+        assert!(
+            auth_service
+                .login("change@example.com", "old_password", ip)
+                .await
+                .is_err()
+        );
+        assert!(
+            auth_service
+                .login("change@example.com", "new_password", ip)
+                .await
+                .is_ok()
+        );
+
+        // A reset link sent before the change no longer works
+        assert!(matches!(
+            reset_service
+                .reset_password(&reset_token, "third_password")
+                .await,
+            Err(DomainError::InvalidResetToken)
+        ));
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_enumeration_resistant_registration(pool: PgPool) {
+        let config = AppConfig {
+            registration_mode: RegistrationMode::EnumerationResistant,
+            ..test_config()
+        };
+        let (state, mailer) = test_state_with(pool, config);
+        let auth_service = AuthService::new(&state);
+
+        let first = auth_service
+            .register("taken@example.com", "password123", "Owner")
+            .await
+            .unwrap();
+        let second = auth_service
+            .register("taken@example.com", "other_password", "Someone Else")
+            .await
+            .unwrap();
+
+        // Same response either way; the owner hears about the attempt
+        assert!(first.is_none() && second.is_none());
+        wait_for_email(&mailer, "taken@example.com", "You already have an account").await;
+        assert!(
+            auth_service
+                .login(
+                    "taken@example.com",
+                    "password123",
+                    Ipv4Addr::LOCALHOST.into()
+                )
+                .await
+                .is_ok()
+        );
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_email_identity_ignores_case(pool: PgPool) {
+        let (state, _mailer) = test_state(pool);
+        let auth_service = AuthService::new(&state);
+
+        auth_service
+            .register(" Jane.Doe@Example.COM ", "password123", "Jane")
+            .await
+            .unwrap();
+
+        let user = auth_service
+            .user_repo
+            .find_by_email("jane.doe@example.com")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(user.email, "Jane.Doe@example.com");
+        assert!(matches!(
+            auth_service
+                .register("JANE.DOE@example.com", "password123", "Impostor")
+                .await,
+            Err(DomainError::UserAlreadyExists)
+        ));
+        assert!(
+            auth_service
+                .login(
+                    "jane.doe@EXAMPLE.com",
+                    "password123",
+                    Ipv4Addr::LOCALHOST.into()
+                )
+                .await
+                .is_ok()
+        );
     }
 }
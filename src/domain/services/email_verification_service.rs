@@ -0,0 +1,300 @@
+//! Email verification service
+
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    common::token,
+    config::AppState,
+    domain::{
+        errors::DomainError,
+        models::{EmailVerificationToken, User},
+    },
+    infrastructure::{
+        mail::{send_in_background, templates},
+        repositories::{EmailVerificationRepository, UserRepository},
+    },
+};
+
+pub struct EmailVerificationService<'a> {
+    state: &'a AppState,
+    user_repo: UserRepository<'a>,
+    verification_repo: EmailVerificationRepository<'a>,
+}
+
+impl<'a> EmailVerificationService<'a> {
+    pub fn new(state: &'a AppState) -> Self {
+        Self {
+            state,
+            user_repo: UserRepository::new(&state.db_pool),
+            verification_repo: EmailVerificationRepository::new(&state.db_pool),
+        }
+    }
+
+    /// Email a verification link for the user's current address
+    pub async fn send_verification(&self, user: &User) -> Result<(), DomainError> {
+        let verification_link = self.create_link(user.id, &user.email).await?;
+        send_in_background(
+            self.state.mailer.clone(),
+            templates::email_verification(
+                &user.email,
+                &verification_link,
+                self.state.config.email_verification_expiration_hours,
+            ),
+        );
+
+        Ok(())
+    }
+
+    /// Email a link confirming `new_email` to it, and warn the current address.
+    ///
+    /// The user must already have `new_email` recorded as pending.
+    pub async fn send_email_change(&self, user: &User, new_email: &str) -> Result<(), DomainError> {
+        let config = &self.state.config;
+        let verification_link = self.create_link(user.id, new_email).await?;
+        send_in_background(
+            self.state.mailer.clone(),
+            templates::email_change_verification(
+                new_email,
+                &verification_link,
+                config.email_verification_expiration_hours,
+            ),
+        );
+        send_in_background(
+            self.state.mailer.clone(),
+            templates::email_change_requested(
+                &user.email,
+                new_email,
+                &format!("{}/forgot-password", config.frontend_url),
+            ),
+        );
+
+        Ok(())
+    }
+
+    /// Store a verification token for `email` and return the link carrying it
+    async fn create_link(&self, user_id: Uuid, email: &str) -> Result<String, DomainError> {
+        // Only the most recently sent link stays valid
+        self.verification_repo.invalidate_for_user(user_id).await?;
+
+        let config = &self.state.config;
+        let raw_token = token::generate();
+        let verification_token = EmailVerificationToken::new(
+            user_id,
+            email.to_string(),
+            token::hash(&raw_token),
+            config.email_verification_expiration_hours,
+        );
+        self.verification_repo.create(&verification_token).await?;
+
+        Ok(format!(
+            "{}/verify-email?token={}",
+            config.frontend_url, raw_token
+        ))
+    }
+
+    /// Verify an email address using a token from the verification email.
+    ///
+    /// A token sent for a pending email change switches the account to the
+    /// new address.
+    pub async fn verify(&self, token: &str) -> Result<(), DomainError> {
+        let (user_id, email) = self
+            .verification_repo
+            .consume(&token::hash(token))
+            .await?
+            .ok_or(DomainError::InvalidVerificationToken)?;
+
+        if self.user_repo.mark_email_verified(user_id, &email).await? {
+            return Ok(());
+        }
+
+        // The account may have changed its address since the token was sent
+        let changed = self
+            .user_repo
+            .confirm_pending_email(user_id, &email)
+            .await
+            .map_err(|err| match err.as_database_error() {
+                Some(db_err) if db_err.is_unique_violation() => DomainError::UserAlreadyExists,
+                _ => err.into(),
+            })?;
+        if !changed {
+            return Err(DomainError::InvalidVerificationToken);
+        }
+        tracing::info!("User {} changed their email address", user_id);
+
+        Ok(())
+    }
+
+    /// Resend the verification email for an unverified account.
+    ///
+    /// Throttled per address, including addresses without an account, so it
+    /// responds the same way whether or not the address belongs to an
+    /// unverified account.
+    pub async fn resend(&self, email: &str) -> Result<(), DomainError> {
+        let cooldown =
+            Duration::from_secs(self.state.config.email_verification_resend_cooldown_secs);
+        let cooldowns = &self.state.verification_resend_cooldowns;
+        let email = self.state.config.normalize_email(email);
+        let key = email.to_lowercase();
+
+        if let Some(requested_at) = cooldowns.get(&key) {
+            let remaining = cooldown.saturating_sub(requested_at.elapsed());
+            return Err(DomainError::TooManyRequests {
+                retry_after_secs: remaining.as_secs().max(1),
+            });
+        }
+
+        // Start the cooldown once the lookup succeeded, so a failed request
+        // can be retried right away
+        let user = self.user_repo.find_by_email(&email).await?;
+        cooldowns.insert(key.clone(), Instant::now(), cooldown);
+
+        let Some(user) = user.filter(|user| !user.is_email_verified()) else {
+            return Ok(());
+        };
+
+        // Cooldown is also enforced against the database for multi-instance deployments
+        if let Some(last_sent) = self
+            .verification_repo
+            .find_latest_created_at(user.id)
+            .await?
+        {
+            let elapsed = (Utc::now() - last_sent).to_std().unwrap_or_default();
+            if elapsed < cooldown {
+                return Ok(());
+            }
+        }
+
+        let result = self.send_verification(&user).await;
+        if result.is_err() {
+            cooldowns.remove(&key);
+        }
+        result
+    }
+
+    /// Fail with `EmailNotVerified` unless the user has verified their email
+    pub async fn ensure_verified(&self, user_id: Uuid) -> Result<(), DomainError> {
+        let user = self
+            .user_repo
+            .find_by_id(user_id)
+            .await?
+            .ok_or(DomainError::UserNotFound)?;
+
+        if !user.is_email_verified() {
+            return Err(DomainError::EmailNotVerified);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::{
+        config::AppConfig,
+        domain::services::AuthService,
+        infrastructure::mail::InMemoryMailSender,
+        test_utils::{test_config, test_state, test_state_with, token_from_email, wait_for_email},
+    };
+
+    const SUBJECT: &str = "Verify your email address";
+
+    /// Number of verification emails sent to `to`, once background sends settled
+    async fn sent_to(mailer: &InMemoryMailSender, to: &str) -> usize {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        mailer
+            .messages_to(to)
+            .iter()
+            .filter(|email| email.subject == SUBJECT)
+            .count()
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_resend_is_throttled_for_every_address(pool: PgPool) {
+        let (state, mailer) = test_state(pool);
+        let verification_service = EmailVerificationService::new(&state);
+        AuthService::new(&state)
+            .register("unverified@example.com", "password123", "Unverified")
+            .await
+            .unwrap();
+        AuthService::new(&state)
+            .register("verified@example.com", "password123", "Verified")
+            .await
+            .unwrap();
+        let email = wait_for_email(&mailer, "verified@example.com", SUBJECT).await;
+        verification_service
+            .verify(&token_from_email(&email))
+            .await
+            .unwrap();
+
+        for address in [
+            "unverified@example.com",
+            "verified@example.com",
+            "nobody@example.com",
+        ] {
+            verification_service.resend(address).await.unwrap();
+            assert!(matches!(
+                verification_service.resend(address).await,
+                Err(DomainError::TooManyRequests { .. })
+            ));
+        }
+
+        // The registration email is recent enough that no second one is sent
+        assert_eq!(sent_to(&mailer, "unverified@example.com").await, 1);
+        assert_eq!(sent_to(&mailer, "verified@example.com").await, 1);
+        assert_eq!(sent_to(&mailer, "nobody@example.com").await, 0);
+
+        // The cooldown is per address, ignoring case
+        assert!(matches!(
+            verification_service.resend("Unverified@Example.com").await,
+            Err(DomainError::TooManyRequests { .. })
+        ));
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_resend_replaces_earlier_link(pool: PgPool) {
+        let config = AppConfig {
+            email_verification_resend_cooldown_secs: 0,
+            ..test_config()
+        };
+        let (state, mailer) = test_state_with(pool, config);
+        let verification_service = EmailVerificationService::new(&state);
+        AuthService::new(&state)
+            .register("resend@example.com", "password123", "Resend")
+            .await
+            .unwrap();
+        let first = wait_for_email(&mailer, "resend@example.com", SUBJECT).await;
+
+        verification_service
+            .resend("resend@example.com")
+            .await
+            .unwrap();
+        assert_eq!(sent_to(&mailer, "resend@example.com").await, 2);
+        let second = wait_for_email(&mailer, "resend@example.com", SUBJECT).await;
+
+        assert!(matches!(
+            verification_service.verify(&token_from_email(&first)).await,
+            Err(DomainError::InvalidVerificationToken)
+        ));
+        verification_service
+            .verify(&token_from_email(&second))
+            .await
+            .unwrap();
+
+        // Verified accounts get no further links
+        verification_service
+            .resend("resend@example.com")
+            .await
+            .unwrap();
+        assert_eq!(sent_to(&mailer, "resend@example.com").await, 2);
+    }
+}
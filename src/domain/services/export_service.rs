@@ -0,0 +1,402 @@
+//! Export service - personal data exports
+//!
+//! Exports are assembled in the background from every registered
+//! [`DataExporter`](crate::infrastructure::export::DataExporter). Finished
+//! archives are downloaded through signed links that expire with the archive,
+//! so they can be opened in a browser without a session.
+
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+use crate::{
+    common::signing,
+    config::AppState,
+    domain::{
+        errors::DomainError,
+        models::{DataExport, ExportFormat, ExportStatus},
+    },
+    infrastructure::{export::archive, repositories::DataExportRepository},
+};
+
+/// Pending exports older than this were interrupted, e.g. by a restart
+const STALE_AFTER_MINUTES: i64 = 30;
+
+/// A ready archive and what to call it
+#[derive(Debug)]
+pub struct ExportDownload {
+    pub export: DataExport,
+    pub archive: Vec<u8>,
+}
+
+impl ExportDownload {
+    /// File name offered to the browser
+    pub fn file_name(&self) -> String {
+        format!(
+            "personal-data-{}.{}",
+            self.export.created_at.format("%Y-%m-%d"),
+            self.export.format.extension()
+        )
+    }
+}
+
+pub struct ExportService<'a> {
+    state: &'a AppState,
+    export_repo: DataExportRepository<'a>,
+}
+
+impl<'a> ExportService<'a> {
+    pub fn new(state: &'a AppState) -> Self {
+        Self {
+            state,
+            export_repo: DataExportRepository::new(&state.db_pool),
+        }
+    }
+
+    /// Start assembling an export of everything stored about the user.
+    ///
+    /// Only one export is assembled per user at a time; while one is pending
+    /// it is returned instead of starting another.
+    pub async fn request(
+        &self,
+        user_id: Uuid,
+        format: ExportFormat,
+    ) -> Result<DataExport, DomainError> {
+        if let Some(pending) = self.export_repo.find_pending_for_user(user_id).await? {
+            return Ok(pending);
+        }
+
+        let export = DataExport::new(user_id, format);
+        self.export_repo.create(&export).await?;
+
+        let state = self.state.clone();
+        let pending = export.clone();
+        tokio::spawn(async move {
+            ExportService::new(&state).assemble(&pending).await;
+        });
+        tracing::info!("User {} requested a data export", user_id);
+
+        Ok(export)
+    }
+
+    /// Get one of the user's exports
+    pub async fn get(&self, user_id: Uuid, id: Uuid) -> Result<DataExport, DomainError> {
+        self.export_repo
+            .find_for_user(user_id, id)
+            .await?
+            .ok_or(DomainError::ExportNotFound)
+    }
+
+    /// Signed link to a ready export, valid until the archive expires
+    pub fn download_url(&self, export: &DataExport) -> Option<String> {
+        if export.status != ExportStatus::Ready {
+            return None;
+        }
+        let expires = export.expires_at?.timestamp();
+        let signature = signing::sign(
+            &self.state.config.data_export_signing_key,
+            &signed_resource(export.id),
+            expires,
+        );
+
+        Some(format!(
+            "/api/v1/exports/{}/download?expires={expires}&signature={signature}",
+            export.id
+        ))
+    }
+
+    /// Fetch the archive a signed link points to
+    pub async fn download(
+        &self,
+        id: Uuid,
+        expires: i64,
+        signature: &str,
+    ) -> Result<ExportDownload, DomainError> {
+        if !signing::verify(
+            &self.state.config.data_export_signing_key,
+            &signed_resource(id),
+            expires,
+            signature,
+            Utc::now().timestamp(),
+        ) {
+            return Err(DomainError::InvalidDownloadLink);
+        }
+
+        let (export, archive) = self
+            .export_repo
+            .find_archive(id)
+            .await?
+            .ok_or(DomainError::ExportNotFound)?;
+
+        Ok(ExportDownload { export, archive })
+    }
+
+    /// Delete expired exports and fail interrupted ones. Returns the number
+    /// of exports deleted.
+    pub async fn purge_expired(&self) -> Result<u64, DomainError> {
+        self.export_repo
+            .fail_stale(
+                Utc::now() - Duration::minutes(STALE_AFTER_MINUTES),
+                self.expires_at(),
+            )
+            .await?;
+
+        Ok(self.export_repo.delete_expired().await?)
+    }
+
+    /// Run every exporter and store the archive, or mark the export failed
+    async fn assemble(&self, export: &DataExport) {
+        let result = match self.build_archive(export).await {
+            Ok(archive) => {
+                self.export_repo
+                    .complete(export.id, &archive, self.expires_at())
+                    .await
+            }
+            Err(err) => {
+                tracing::error!("Failed to assemble data export {}: {}", export.id, err);
+                self.export_repo.fail(export.id, self.expires_at()).await
+            }
+        };
+
+        if let Err(err) = result {
+            tracing::error!("Failed to store data export {}: {}", export.id, err);
+        }
+    }
+
+    async fn build_archive(&self, export: &DataExport) -> Result<Vec<u8>, String> {
+        let mut sections = Vec::with_capacity(self.state.exporters.len());
+        for exporter in self.state.exporters.iter() {
+            let value = exporter
+                .export(&self.state.db_pool, export.user_id)
+                .await
+                .map_err(|err| format!("{} exporter: {err}", exporter.section()))?;
+            sections.push((exporter.section(), value));
+        }
+
+        let generated_at = Utc::now();
+        match export.format {
+            ExportFormat::Json => Ok(archive::json(generated_at, sections)),
+            ExportFormat::Zip => {
+                archive::zip(generated_at, sections).map_err(|err| err.to_string())
+            }
+        }
+    }
+
+    fn expires_at(&self) -> DateTime<Utc> {
+        Utc::now() + Duration::hours(self.state.config.data_export_expiration_hours)
+    }
+}
+
+/// What a download link signature covers
+fn signed_resource(id: Uuid) -> String {
+    format!("data_exports/{id}")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Cursor, sync::Arc};
+
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::{
+        common::jwt::verify_token,
+        domain::services::{AuthService, UserService},
+        infrastructure::export::DataExporter,
+        test_utils::test_state,
+    };
+
+    /// Section contributed by a table outside this crate
+    struct WishlistExporter;
+
+    #[async_trait::async_trait]
+    impl DataExporter for WishlistExporter {
+        fn section(&self) -> &'static str {
+            "wishlist"
+        }
+
+        async fn export(
+            &self,
+            _pool: &PgPool,
+            user_id: Uuid,
+        ) -> Result<serde_json::Value, sqlx::Error> {
+            Ok(serde_json::json!({ "user_id": user_id, "items": ["teapot"] }))
+        }
+    }
+
+    /// Poll until the background task has finished the export
+    async fn wait_until_done(
+        export_service: &ExportService<'_>,
+        export: &DataExport,
+    ) -> DataExport {
+        for _ in 0..100 {
+            let current = export_service.get(export.user_id, export.id).await.unwrap();
+            if current.status != ExportStatus::Pending {
+                return current;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        panic!("export {} was not assembled", export.id);
+    }
+
+    /// Split a download URL into the export id, expiry and signature
+    fn link_parts(url: &str) -> (Uuid, i64, String) {
+        let (path, query) = url.split_once('?').unwrap();
+        let id = path.split('/').nth(4).unwrap().parse().unwrap();
+        let mut expires = 0;
+        let mut signature = String::new();
+        for pair in query.split('&') {
+            match pair.split_once('=').unwrap() {
+                ("expires", value) => expires = value.parse().unwrap(),
+                ("signature", value) => signature = value.to_string(),
+                _ => {}
+            }
+        }
+        (id, expires, signature)
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_export_is_assembled_and_downloaded_with_signed_link(pool: PgPool) {
+        let (state, _mailer) = test_state(pool);
+        AuthService::new(&state)
+            .register("export@example.com", "password123", "Export User")
+            .await
+            .unwrap();
+        let user_id: Uuid = sqlx::query_scalar("SELECT id FROM users")
+            .fetch_one(&state.db_pool)
+            .await
+            .unwrap();
+        let export_service = ExportService::new(&state);
+
+        let export = export_service
+            .request(user_id, ExportFormat::Json)
+            .await
+            .unwrap();
+        assert_eq!(export.status, ExportStatus::Pending);
+        assert_eq!(export_service.download_url(&export), None);
+
+        let ready = wait_until_done(&export_service, &export).await;
+        assert_eq!(ready.status, ExportStatus::Ready);
+        let (id, expires, signature) = link_parts(&export_service.download_url(&ready).unwrap());
+        assert_eq!(id, export.id);
+
+        let download = export_service
+            .download(id, expires, &signature)
+            .await
+            .unwrap();
+        let document: serde_json::Value = serde_json::from_slice(&download.archive).unwrap();
+        let sections = &document["sections"];
+        assert_eq!(sections["profile"]["email"], "export@example.com");
+        assert!(sections["profile"].get("password_hash").is_none());
+        assert_eq!(sections["roles"], serde_json::json!(["user"]));
+        assert_eq!(sections["sessions"].as_array().unwrap().len(), 1);
+        for section in ["organizations", "api_keys", "mfa", "audit_log"] {
+            assert!(sections.get(section).is_some(), "missing {section}");
+        }
+
+        // The link only works as signed
+        assert!(matches!(
+            export_service.download(id, expires + 1, &signature).await,
+            Err(DomainError::InvalidDownloadLink)
+        ));
+        assert!(matches!(
+            export_service
+                .download(Uuid::new_v4(), expires, &signature)
+                .await,
+            Err(DomainError::InvalidDownloadLink)
+        ));
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_zip_export(pool: PgPool) {
+        let (state, _mailer) = test_state(pool);
+        AuthService::new(&state)
+            .register("zip@example.com", "password123", "Zip User")
+            .await
+            .unwrap();
+        let user_id: Uuid = sqlx::query_scalar("SELECT id FROM users")
+            .fetch_one(&state.db_pool)
+            .await
+            .unwrap();
+        let export_service = ExportService::new(&state);
+
+        let export = export_service
+            .request(user_id, ExportFormat::Zip)
+            .await
+            .unwrap();
+        let ready = wait_until_done(&export_service, &export).await;
+        let (id, expires, signature) = link_parts(&export_service.download_url(&ready).unwrap());
+        let download = export_service
+            .download(id, expires, &signature)
+            .await
+            .unwrap();
+
+        assert!(download.file_name().ends_with(".zip"));
+        let archive = zip::ZipArchive::new(Cursor::new(download.archive)).unwrap();
+        assert!(archive.file_names().any(|name| name == "profile.json"));
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_registered_exporter_adds_a_section(pool: PgPool) {
+        let (state, _mailer) = test_state(pool);
+        let state = state.with_exporter(Arc::new(WishlistExporter));
+        AuthService::new(&state)
+            .register("wishlist@example.com", "password123", "Wishlist User")
+            .await
+            .unwrap();
+        let user_id: Uuid = sqlx::query_scalar("SELECT id FROM users")
+            .fetch_one(&state.db_pool)
+            .await
+            .unwrap();
+        let export_service = ExportService::new(&state);
+
+        let export = export_service
+            .request(user_id, ExportFormat::Json)
+            .await
+            .unwrap();
+        let ready = wait_until_done(&export_service, &export).await;
+        let (id, expires, signature) = link_parts(&export_service.download_url(&ready).unwrap());
+        let download = export_service
+            .download(id, expires, &signature)
+            .await
+            .unwrap();
+
+        let document: serde_json::Value = serde_json::from_slice(&download.archive).unwrap();
+        let sections = &document["sections"];
+        assert_eq!(sections["wishlist"]["user_id"], user_id.to_string());
+        assert_eq!(sections["wishlist"]["items"], serde_json::json!(["teapot"]));
+        assert_eq!(sections["profile"]["email"], "wishlist@example.com");
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_deleting_the_account_disables_download_links(pool: PgPool) {
+        let (state, _mailer) = test_state(pool);
+        let tokens = AuthService::new(&state)
+            .register("deleted@example.com", "password123", "Deleted User")
+            .await
+            .unwrap()
+            .unwrap();
+        let claims = verify_token(&tokens.access_token, &state.jwt_keys).unwrap();
+        let export_service = ExportService::new(&state);
+
+        let export = export_service
+            .request(claims.sub, ExportFormat::Json)
+            .await
+            .unwrap();
+        let ready = wait_until_done(&export_service, &export).await;
+        let (id, expires, signature) = link_parts(&export_service.download_url(&ready).unwrap());
+
+        UserService::new(&state)
+            .delete_account(&claims, "password123")
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            export_service.download(id, expires, &signature).await,
+            Err(DomainError::ExportNotFound)
+        ));
+    }
+}
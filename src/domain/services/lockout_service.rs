@@ -0,0 +1,329 @@
+//! Login brute-force protection: per-address lockout and per-IP throttling
+//!
+//! Lockouts and delays are tracked by email address, whether or not it belongs
+//! to an account, so failed logins get the same responses either way and do
+//! not reveal which addresses are registered.
+
+use std::{net::IpAddr, time::Duration};
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    config::AppState,
+    domain::{errors::DomainError, models::User},
+    infrastructure::{
+        mail::{send_in_background, templates},
+        repositories::{LockoutRepository, UserRepository},
+    },
+};
+
+/// Seconds after the last failure at which an unlocked address's failure
+/// count is forgotten
+const STALE_AFTER_SECS: i64 = 86_400;
+
+pub struct LockoutService<'a> {
+    state: &'a AppState,
+    user_repo: UserRepository<'a>,
+    lockout_repo: LockoutRepository<'a>,
+}
+
+impl<'a> LockoutService<'a> {
+    pub fn new(state: &'a AppState) -> Self {
+        Self {
+            state,
+            user_repo: UserRepository::new(&state.db_pool),
+            lockout_repo: LockoutRepository::new(&state.db_pool),
+        }
+    }
+
+    /// Reject login attempts from an address with too many recent failures
+    pub fn check_ip(&self, ip: IpAddr) -> Result<(), DomainError> {
+        let config = &self.state.config;
+        let failures = self.state.login_ip_failures.get(&ip).unwrap_or(0);
+        if failures >= config.login_ip_max_failures {
+            return Err(DomainError::TooManyRequests {
+                retry_after_secs: config.login_ip_window_secs,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Count a failed login from an address.
+    ///
+    /// Not reset by successful logins, so an attacker cannot clear the counter
+    /// by interleaving logins to an account they control.
+    pub fn record_ip_failure(&self, ip: IpAddr) {
+        let failures = self.state.login_ip_failures.get(&ip).unwrap_or(0) + 1;
+        self.state.login_ip_failures.insert(
+            ip,
+            failures,
+            Duration::from_secs(self.state.config.login_ip_window_secs),
+        );
+    }
+
+    /// Reject login attempts while the address is locked or inside its
+    /// progressive delay. Runs before the password is checked.
+    pub async fn check(&self, email: &str) -> Result<(), DomainError> {
+        let Some(lockout) = self.lockout_repo.find(email).await? else {
+            return Ok(());
+        };
+
+        if let Some(locked_until) = lockout.locked_until.filter(|_| lockout.is_locked()) {
+            return Err(DomainError::AccountLocked {
+                retry_after_secs: secs_until(locked_until),
+            });
+        }
+
+        if let Some(delay) = self.delay_after(lockout.failed_attempts) {
+            let ready_at = lockout.last_failed_at + delay;
+            if ready_at > Utc::now() {
+                return Err(DomainError::TooManyRequests {
+                    retry_after_secs: secs_until(ready_at),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Count a failed password for `email` and return the error for the caller.
+    ///
+    /// The attempt that reaches the limit locks the address and, if it
+    /// belongs to `user`, emails the owner.
+    pub async fn record_failure(
+        &self,
+        email: &str,
+        user: Option<&User>,
+    ) -> Result<DomainError, DomainError> {
+        let config = &self.state.config;
+        let lockout = self
+            .lockout_repo
+            .record_failure(
+                email,
+                config.login_max_failed_attempts,
+                config.login_lockout_minutes * 60,
+            )
+            .await?;
+
+        // Just locked: the counter restarts together with the lock
+        if lockout.failed_attempts == 0
+            && let Some(locked_until) = lockout.locked_until.filter(|_| lockout.is_locked())
+        {
+            if let Some(user) = user {
+                tracing::warn!("Locked account {} after repeated failed logins", user.id);
+                send_in_background(
+                    self.state.mailer.clone(),
+                    templates::account_locked(
+                        &user.email,
+                        &format!("{}/forgot-password", config.frontend_url),
+                        config.login_lockout_minutes,
+                    ),
+                );
+            }
+
+            return Ok(DomainError::AccountLocked {
+                retry_after_secs: secs_until(locked_until),
+            });
+        }
+
+        Ok(DomainError::InvalidCredentials)
+    }
+
+    /// Forget failed logins after a successful login or password reset
+    pub async fn reset(&self, email: &str) -> Result<(), DomainError> {
+        self.lockout_repo.clear(email).await?;
+        Ok(())
+    }
+
+    /// Lift a lock on behalf of an administrator
+    pub async fn unlock(&self, user_id: Uuid) -> Result<(), DomainError> {
+        let user = self
+            .user_repo
+            .find_by_id(user_id)
+            .await?
+            .ok_or(DomainError::UserNotFound)?;
+
+        if self.lockout_repo.clear(&user.email).await? {
+            tracing::info!("Unlocked account {}", user_id);
+        }
+
+        Ok(())
+    }
+
+    /// Forget failure counts of unlocked addresses a day after their last failure
+    pub async fn purge_stale(&self) -> Result<u64, DomainError> {
+        Ok(self.lockout_repo.delete_stale(STALE_AFTER_SECS).await?)
+    }
+
+    /// Wait required before the next attempt after `failed_attempts` consecutive
+    /// failures: doubling from one second once the threshold is reached
+    fn delay_after(&self, failed_attempts: i32) -> Option<chrono::Duration> {
+        let config = &self.state.config;
+        if failed_attempts < config.login_delay_after_attempts {
+            return None;
+        }
+
+        let over = (failed_attempts - config.login_delay_after_attempts) as u32;
+        let secs = 1u64
+            .checked_shl(over)
+            .unwrap_or(u64::MAX)
+            .min(config.login_max_delay_secs);
+
+        Some(chrono::Duration::seconds(secs as i64))
+    }
+}
+
+/// Whole seconds until `time`, at least one
+fn secs_until(time: DateTime<Utc>) -> u64 {
+    (time - Utc::now()).num_seconds().max(1) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::{
+        config::AppConfig,
+        domain::services::{AuthService, UserService},
+        test_utils::{test_config, test_state, test_state_with, wait_for_email},
+    };
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_lockout_after_failed_logins(pool: PgPool) {
+        let (state, mailer) = test_state(pool);
+        let auth_service = AuthService::new(&state);
+        let ip = Ipv4Addr::LOCALHOST.into();
+        auth_service
+            .register("locked@example.com", "password123", "Locked User")
+            .await
+            .unwrap();
+
+        let max_attempts = state.config.login_max_failed_attempts;
+        for _ in 1..max_attempts {
+            assert!(matches!(
+                auth_service.login("locked@example.com", "wrong", ip).await,
+                Err(DomainError::InvalidCredentials)
+            ));
+        }
+        assert!(matches!(
+            auth_service.login("locked@example.com", "wrong", ip).await,
+            Err(DomainError::AccountLocked { .. })
+        ));
+        wait_for_email(
+            &mailer,
+            "locked@example.com",
+            "Your account has been locked",
+        )
+        .await;
+
+        // The right password does not get through while locked
+        assert!(matches!(
+            auth_service
+                .login("locked@example.com", "password123", ip)
+                .await,
+            Err(DomainError::AccountLocked { .. })
+        ));
+
+        let user = UserService::new(&state)
+            .get_by_email("locked@example.com")
+            .await
+            .unwrap();
+        LockoutService::new(&state).unlock(user.id).await.unwrap();
+        assert!(
+            auth_service
+                .login("locked@example.com", "password123", ip)
+                .await
+                .is_ok()
+        );
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_unknown_emails_are_locked_out_like_accounts(pool: PgPool) {
+        let config = AppConfig {
+            login_delay_after_attempts: 2,
+            ..test_config()
+        };
+        let (state, mailer) = test_state_with(pool, config);
+        let auth_service = AuthService::new(&state);
+        let ip = Ipv4Addr::LOCALHOST.into();
+        auth_service
+            .register("known@example.com", "password123", "Known User")
+            .await
+            .unwrap();
+
+        // Both get the same progressive delay...
+        for email in ["known@example.com", "Unknown@Example.com"] {
+            for _ in 0..2 {
+                assert!(matches!(
+                    auth_service.login(email, "wrong", ip).await,
+                    Err(DomainError::InvalidCredentials)
+                ));
+            }
+            assert!(matches!(
+                auth_service.login(email, "wrong", ip).await,
+                Err(DomainError::TooManyRequests { .. })
+            ));
+        }
+
+        // ...and the same lock once the limit is reached
+        for email in ["known@example.com", "unknown@example.com"] {
+            sqlx::query(
+                "UPDATE account_lockouts SET failed_attempts = $2, last_failed_at = NOW() - INTERVAL '1 hour' WHERE email = $1",
+            )
+            .bind(email)
+            .bind(state.config.login_max_failed_attempts - 1)
+            .execute(&state.db_pool)
+            .await
+            .unwrap();
+
+            assert!(matches!(
+                auth_service.login(email, "wrong", ip).await,
+                Err(DomainError::AccountLocked { .. })
+            ));
+            assert!(matches!(
+                auth_service.login(email, "password123", ip).await,
+                Err(DomainError::AccountLocked { .. })
+            ));
+        }
+
+        // Only the account owner is told about the lock
+        wait_for_email(&mailer, "known@example.com", "Your account has been locked").await;
+        assert!(mailer.messages_to("unknown@example.com").is_empty());
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_ip_throttling(pool: PgPool) {
+        let (state, _mailer) = test_state(pool);
+        let auth_service = AuthService::new(&state);
+        let ip = Ipv4Addr::new(203, 0, 113, 7).into();
+
+        // A different address each time, so per-address lockouts stay out of the way
+        for i in 0..state.config.login_ip_max_failures {
+            let email = format!("nobody{i}@example.com");
+            assert!(matches!(
+                auth_service.login(&email, "wrong", ip).await,
+                Err(DomainError::InvalidCredentials)
+            ));
+        }
+        assert!(matches!(
+            auth_service.login("nobody@example.com", "wrong", ip).await,
+            Err(DomainError::TooManyRequests { .. })
+        ));
+
+        // Other addresses are unaffected
+        assert!(matches!(
+            auth_service
+                .login("nobody@example.com", "wrong", Ipv4Addr::LOCALHOST.into())
+                .await,
+            Err(DomainError::InvalidCredentials)
+        ));
+    }
+}
@@ -0,0 +1,453 @@
+//! Two-factor authentication service (TOTP with recovery codes)
+
+use std::time::Duration;
+
+use chrono::Utc;
+use rand::{Rng, rngs::OsRng};
+use uuid::Uuid;
+
+use crate::{
+    common::{crypto, jwt::verify_mfa_token, token, totp},
+    config::AppState,
+    domain::{
+        errors::DomainError,
+        models::UserMfa,
+        services::{AuthService, AuthTokens},
+    },
+    infrastructure::repositories::{MfaRepository, UserRepository},
+};
+
+/// Number of recovery codes issued on enrollment
+const RECOVERY_CODE_COUNT: usize = 10;
+/// Characters in a recovery code (excluding the separator)
+const RECOVERY_CODE_LENGTH: usize = 10;
+/// Recovery code alphabet, without easily confused characters (0/o, 1/l)
+const RECOVERY_CODE_ALPHABET: &[u8] = b"abcdefghijkmnpqrstuvwxyz23456789";
+/// Accepted clock drift, in 30 second steps
+const TOTP_SKEW: i64 = 1;
+/// Failed code attempts allowed per user before throttling
+const MAX_FAILED_ATTEMPTS: u32 = 5;
+/// Window during which failed attempts are counted
+const FAILED_ATTEMPT_WINDOW: Duration = Duration::from_secs(300);
+
+/// Secret and provisioning URI returned when enrollment starts
+#[derive(Debug)]
+pub struct MfaEnrollment {
+    /// Base32-encoded secret, for manual entry
+    pub secret: String,
+    /// `otpauth://` URI, usually rendered as a QR code
+    pub otpauth_uri: String,
+}
+
+pub struct MfaService<'a> {
+    state: &'a AppState,
+    user_repo: UserRepository<'a>,
+    mfa_repo: MfaRepository<'a>,
+}
+
+impl<'a> MfaService<'a> {
+    pub fn new(state: &'a AppState) -> Self {
+        Self {
+            state,
+            user_repo: UserRepository::new(&state.db_pool),
+            mfa_repo: MfaRepository::new(&state.db_pool),
+        }
+    }
+
+    /// Check if a user has confirmed two-factor authentication
+    pub async fn is_enabled(&self, user_id: Uuid) -> Result<bool, DomainError> {
+        Ok(self
+            .mfa_repo
+            .find_by_user_id(user_id)
+            .await?
+            .is_some_and(|mfa| mfa.is_enabled()))
+    }
+
+    /// Start enrollment by generating a new secret.
+    ///
+    /// The secret is not used for login until confirmed with [`Self::confirm`].
+    pub async fn enroll(&self, user_id: Uuid) -> Result<MfaEnrollment, DomainError> {
+        let user = self
+            .user_repo
+            .find_by_id(user_id)
+            .await?
+            .ok_or(DomainError::UserNotFound)?;
+
+        let secret = totp::generate_secret();
+        let secret_encrypted = crypto::encrypt(&self.state.config.mfa_encryption_key, &secret)
+            .map_err(|_| DomainError::EncryptionFailed)?;
+
+        if !self
+            .mfa_repo
+            .upsert_pending(user_id, &secret_encrypted)
+            .await?
+        {
+            return Err(DomainError::MfaAlreadyEnabled);
+        }
+
+        Ok(MfaEnrollment {
+            secret: totp::encode_secret(&secret),
+            otpauth_uri: totp::provisioning_uri(
+                &secret,
+                &self.state.config.mfa_issuer,
+                &user.email,
+            ),
+        })
+    }
+
+    /// Confirm enrollment with a code from the authenticator app.
+    ///
+    /// Returns the recovery codes; they are only stored hashed and cannot be shown again.
+    pub async fn confirm(&self, user_id: Uuid, code: &str) -> Result<Vec<String>, DomainError> {
+        let mfa = self
+            .mfa_repo
+            .find_by_user_id(user_id)
+            .await?
+            .ok_or(DomainError::MfaNotEnrolled)?;
+        if mfa.is_enabled() {
+            return Err(DomainError::MfaAlreadyEnabled);
+        }
+
+        self.check_attempts(user_id)?;
+        let secret = self.decrypt_secret(&mfa)?;
+        let Some(step) = totp::verify(&secret, code, Utc::now().timestamp(), TOTP_SKEW) else {
+            return Err(self.record_failure(user_id));
+        };
+        self.state.mfa_failed_attempts.remove(&user_id);
+
+        let recovery_codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+            .map(|_| generate_recovery_code())
+            .collect();
+        let hashes: Vec<String> = recovery_codes
+            .iter()
+            .map(|code| token::hash(&normalize_recovery_code(code)))
+            .collect();
+
+        self.mfa_repo.enable(user_id, step, &hashes).await?;
+
+        Ok(recovery_codes)
+    }
+
+    /// Complete a two-step login with the MFA pending token from `/auth/login`
+    /// and a TOTP or recovery code
+    pub async fn verify_login(
+        &self,
+        mfa_token: &str,
+        code: &str,
+    ) -> Result<AuthTokens, DomainError> {
+        let claims = verify_mfa_token(mfa_token, &self.state.jwt_keys)
+            .map_err(|_| DomainError::InvalidMfaToken)?;
+
+        // The account may have been deactivated since the password step
+        if self.user_repo.find_by_id(claims.sub).await?.is_none() {
+            return Err(DomainError::InvalidMfaToken);
+        }
+
+        self.verify_code(claims.sub, code).await?;
+
+        AuthService::new(self.state)
+            .create_session(claims.sub)
+            .await
+    }
+
+    /// Turn off two-factor authentication, given a valid TOTP or recovery code
+    pub async fn disable(&self, user_id: Uuid, code: &str) -> Result<(), DomainError> {
+        self.verify_code(user_id, code).await?;
+        self.mfa_repo.delete(user_id).await?;
+
+        Ok(())
+    }
+
+    /// Verify a TOTP code or unused recovery code for a user with MFA enabled
+    async fn verify_code(&self, user_id: Uuid, code: &str) -> Result<(), DomainError> {
+        let mfa = self
+            .mfa_repo
+            .find_by_user_id(user_id)
+            .await?
+            .filter(|mfa| mfa.is_enabled())
+            .ok_or(DomainError::MfaNotEnrolled)?;
+
+        self.check_attempts(user_id)?;
+        let secret = self.decrypt_secret(&mfa)?;
+
+        let accepted = match totp::verify(&secret, code, Utc::now().timestamp(), TOTP_SKEW) {
+            // A code may only be used once
+            Some(step) => self.mfa_repo.use_step(user_id, step).await?,
+            None => {
+                let code_hash = token::hash(&normalize_recovery_code(code));
+                self.mfa_repo.use_recovery_code(user_id, &code_hash).await?
+            }
+        };
+
+        if !accepted {
+            return Err(self.record_failure(user_id));
+        }
+        self.state.mfa_failed_attempts.remove(&user_id);
+
+        Ok(())
+    }
+
+    fn decrypt_secret(&self, mfa: &UserMfa) -> Result<Vec<u8>, DomainError> {
+        crypto::decrypt(&self.state.config.mfa_encryption_key, &mfa.secret_encrypted)
+            .map_err(|_| DomainError::EncryptionFailed)
+    }
+
+    fn check_attempts(&self, user_id: Uuid) -> Result<(), DomainError> {
+        let attempts = self.state.mfa_failed_attempts.get(&user_id).unwrap_or(0);
+        if attempts >= MAX_FAILED_ATTEMPTS {
+            return Err(DomainError::TooManyRequests {
+                retry_after_secs: FAILED_ATTEMPT_WINDOW.as_secs(),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn record_failure(&self, user_id: Uuid) -> DomainError {
+        let attempts = self.state.mfa_failed_attempts.get(&user_id).unwrap_or(0) + 1;
+        self.state
+            .mfa_failed_attempts
+            .insert(user_id, attempts, FAILED_ATTEMPT_WINDOW);
+
+        DomainError::InvalidMfaCode
+    }
+}
+
+/// Generate a recovery code formatted as `xxxxx-xxxxx`
+fn generate_recovery_code() -> String {
+    let chars: String = (0..RECOVERY_CODE_LENGTH)
+        .map(|_| RECOVERY_CODE_ALPHABET[OsRng.gen_range(0..RECOVERY_CODE_ALPHABET.len())] as char)
+        .collect();
+
+    format!(
+        "{}-{}",
+        &chars[..RECOVERY_CODE_LENGTH / 2],
+        &chars[RECOVERY_CODE_LENGTH / 2..]
+    )
+}
+
+/// Normalize user input so recovery codes match regardless of case and separators
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::{
+        common::jwt::verify_token,
+        domain::services::{LoginOutcome, UserService},
+        test_utils::test_state,
+    };
+
+    /// A user with two-factor authentication confirmed using the code of the
+    /// previous time step, so codes of the current and next step are unused.
+    /// Returns the user ID, the secret, the current step and the recovery codes.
+    async fn enabled_user(state: &AppState) -> (Uuid, Vec<u8>, i64, Vec<String>) {
+        AuthService::new(state)
+            .register("mfa@example.com", "password123", "Mfa User")
+            .await
+            .unwrap();
+        let user = UserService::new(state)
+            .get_by_email("mfa@example.com")
+            .await
+            .unwrap();
+        let mfa_service = MfaService::new(state);
+        mfa_service.enroll(user.id).await.unwrap();
+        let secret = stored_secret(&mfa_service, user.id).await;
+
+        let step = totp::time_step(Utc::now().timestamp());
+        let recovery_codes = mfa_service
+            .confirm(user.id, &totp::generate_code(&secret, step - 1))
+            .await
+            .unwrap();
+
+        (user.id, secret, step, recovery_codes)
+    }
+
+    async fn stored_secret(mfa_service: &MfaService<'_>, user_id: Uuid) -> Vec<u8> {
+        let mfa = mfa_service
+            .mfa_repo
+            .find_by_user_id(user_id)
+            .await
+            .unwrap()
+            .unwrap();
+        mfa_service.decrypt_secret(&mfa).unwrap()
+    }
+
+    async fn mfa_token(state: &AppState) -> String {
+        match AuthService::new(state)
+            .login("mfa@example.com", "password123", Ipv4Addr::LOCALHOST.into())
+            .await
+            .unwrap()
+        {
+            LoginOutcome::MfaRequired { mfa_token, .. } => mfa_token,
+            LoginOutcome::Authenticated(_) => panic!("second factor not required"),
+        }
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_enroll_and_confirm(pool: PgPool) {
+        let (state, _mailer) = test_state(pool);
+        AuthService::new(&state)
+            .register("mfa@example.com", "password123", "Mfa User")
+            .await
+            .unwrap();
+        let user = UserService::new(&state)
+            .get_by_email("mfa@example.com")
+            .await
+            .unwrap();
+        let mfa_service = MfaService::new(&state);
+
+        assert!(matches!(
+            mfa_service.confirm(user.id, "123456").await,
+            Err(DomainError::MfaNotEnrolled)
+        ));
+        let enrollment = mfa_service.enroll(user.id).await.unwrap();
+        let secret = stored_secret(&mfa_service, user.id).await;
+        assert_eq!(enrollment.secret, totp::encode_secret(&secret));
+        assert!(enrollment.otpauth_uri.starts_with("otpauth://totp/"));
+
+        // Not enabled until a code from the authenticator app confirms it
+        assert!(!mfa_service.is_enabled(user.id).await.unwrap());
+        assert!(matches!(
+            mfa_service.confirm(user.id, "not-a-code").await,
+            Err(DomainError::InvalidMfaCode)
+        ));
+        let step = totp::time_step(Utc::now().timestamp());
+        let recovery_codes = mfa_service
+            .confirm(user.id, &totp::generate_code(&secret, step))
+            .await
+            .unwrap();
+        assert_eq!(recovery_codes.len(), RECOVERY_CODE_COUNT);
+        assert!(mfa_service.is_enabled(user.id).await.unwrap());
+
+        assert!(matches!(
+            mfa_service.enroll(user.id).await,
+            Err(DomainError::MfaAlreadyEnabled)
+        ));
+        assert!(matches!(
+            mfa_service
+                .confirm(user.id, &totp::generate_code(&secret, step + 1))
+                .await,
+            Err(DomainError::MfaAlreadyEnabled)
+        ));
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_login_requires_a_code_that_cannot_be_replayed(pool: PgPool) {
+        let (state, _mailer) = test_state(pool);
+        let (user_id, secret, step, _recovery_codes) = enabled_user(&state).await;
+        let mfa_service = MfaService::new(&state);
+
+        let code = totp::generate_code(&secret, step);
+        let tokens = mfa_service
+            .verify_login(&mfa_token(&state).await, &code)
+            .await
+            .unwrap();
+        let claims = verify_token(&tokens.access_token, &state.jwt_keys).unwrap();
+        assert_eq!(claims.sub, user_id);
+
+        // The same code, or one from an earlier step, is not accepted again
+        let mfa_token = mfa_token(&state).await;
+        assert!(matches!(
+            mfa_service.verify_login(&mfa_token, &code).await,
+            Err(DomainError::InvalidMfaCode)
+        ));
+        assert!(matches!(
+            mfa_service
+                .verify_login(&mfa_token, &totp::generate_code(&secret, step - 1))
+                .await,
+            Err(DomainError::InvalidMfaCode)
+        ));
+        mfa_service
+            .verify_login(&mfa_token, &totp::generate_code(&secret, step + 1))
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            mfa_service.verify_login("not-a-token", &code).await,
+            Err(DomainError::InvalidMfaToken)
+        ));
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_recovery_code_works_once(pool: PgPool) {
+        let (state, _mailer) = test_state(pool);
+        let (_user_id, _secret, _step, recovery_codes) = enabled_user(&state).await;
+        let mfa_service = MfaService::new(&state);
+        let mfa_token = mfa_token(&state).await;
+
+        // Case and separators do not matter
+        let code = recovery_codes[0].to_uppercase().replace('-', " ");
+        mfa_service.verify_login(&mfa_token, &code).await.unwrap();
+        assert!(matches!(
+            mfa_service
+                .verify_login(&mfa_token, &recovery_codes[0])
+                .await,
+            Err(DomainError::InvalidMfaCode)
+        ));
+        mfa_service
+            .verify_login(&mfa_token, &recovery_codes[1])
+            .await
+            .unwrap();
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_failed_attempts_are_throttled(pool: PgPool) {
+        let (state, _mailer) = test_state(pool);
+        let (user_id, secret, step, recovery_codes) = enabled_user(&state).await;
+        let mfa_service = MfaService::new(&state);
+        let mfa_token = mfa_token(&state).await;
+
+        for _ in 0..MAX_FAILED_ATTEMPTS {
+            assert!(matches!(
+                mfa_service.verify_login(&mfa_token, "wrong-code").await,
+                Err(DomainError::InvalidMfaCode)
+            ));
+        }
+
+        // Further attempts are refused for the rest of the window, even with
+        // a valid code, and disabling is throttled the same way
+        assert!(matches!(
+            mfa_service
+                .verify_login(&mfa_token, &totp::generate_code(&secret, step))
+                .await,
+            Err(DomainError::TooManyRequests { retry_after_secs }) if retry_after_secs == FAILED_ATTEMPT_WINDOW.as_secs()
+        ));
+        assert!(matches!(
+            mfa_service.disable(user_id, &recovery_codes[0]).await,
+            Err(DomainError::TooManyRequests { .. })
+        ));
+
+        // The window passing lifts the throttle
+        state.mfa_failed_attempts.remove(&user_id);
+        mfa_service
+            .verify_login(&mfa_token, &totp::generate_code(&secret, step))
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn test_recovery_code_format() {
+        let code = generate_recovery_code();
+
+        assert_eq!(code.len(), RECOVERY_CODE_LENGTH + 1);
+        assert_eq!(code.chars().nth(RECOVERY_CODE_LENGTH / 2), Some('-'));
+        assert_eq!(
+            normalize_recovery_code(&code.to_uppercase()),
+            code.replace('-', "")
+        );
+    }
+}
@@ -1,7 +1,27 @@
 //! Business logic services
 
+mod admin_service;
+mod api_key_service;
 mod auth_service;
+mod email_verification_service;
+mod export_service;
+mod lockout_service;
+mod mfa_service;
+mod organization_service;
+mod password_policy_service;
+mod password_reset_service;
+mod session_service;
 mod user_service;
 
-pub use auth_service::AuthService;
-pub use user_service::UserService;
+pub use admin_service::{Actor, AdminService, AdminUserUpdate};
+pub use api_key_service::ApiKeyService;
+pub use auth_service::{AuthService, AuthTokens, LoginOutcome};
+pub use email_verification_service::EmailVerificationService;
+pub use export_service::ExportService;
+pub use lockout_service::LockoutService;
+pub use mfa_service::MfaService;
+pub use organization_service::OrganizationService;
+pub use password_policy_service::PasswordPolicyService;
+pub use password_reset_service::PasswordResetService;
+pub use session_service::SessionService;
+pub use user_service::{ImportReport, ImportSkipReason, ImportedUser, UserSearchHit, UserService};
@@ -0,0 +1,498 @@
+//! Organization service - organizations, their members and invitations
+//!
+//! Everything inside an organization is reached through the caller's
+//! [`Membership`], whose [`Tenant`] scopes the repositories. Rows of other
+//! organizations are never found, so acting on them reports "not found"
+//! rather than "forbidden" and does not reveal that they exist.
+//!
+//! [`Tenant`]: crate::domain::models::Tenant
+
+use uuid::Uuid;
+
+use crate::{
+    common::token,
+    config::AppState,
+    domain::{
+        errors::DomainError,
+        models::{Invitation, Member, Membership, OrgRole, Organization, OrganizationMembership},
+    },
+    infrastructure::{
+        mail::{send_in_background, templates},
+        repositories::{
+            InvitationRepository, MemberChange, MemberRepository, OrganizationRepository,
+            UserRepository,
+        },
+    },
+};
+
+pub struct OrganizationService<'a> {
+    state: &'a AppState,
+    organization_repo: OrganizationRepository<'a>,
+    user_repo: UserRepository<'a>,
+}
+
+impl<'a> OrganizationService<'a> {
+    pub fn new(state: &'a AppState) -> Self {
+        Self {
+            state,
+            organization_repo: OrganizationRepository::new(&state.db_pool),
+            user_repo: UserRepository::new(&state.db_pool),
+        }
+    }
+
+    /// Create an organization owned by `user_id`
+    pub async fn create(
+        &self,
+        user_id: Uuid,
+        name: String,
+    ) -> Result<OrganizationMembership, DomainError> {
+        let organization = Organization::new(name);
+        let owner = Membership::new(organization.id, user_id, OrgRole::Owner);
+        self.organization_repo.create(&organization, &owner).await?;
+        tracing::info!("User {} created organization {}", user_id, organization.id);
+
+        Ok(OrganizationMembership {
+            organization,
+            role: OrgRole::Owner,
+        })
+    }
+
+    /// List the organizations a user belongs to
+    pub async fn list_for_user(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<OrganizationMembership>, DomainError> {
+        Ok(self.organization_repo.list_for_user(user_id).await?)
+    }
+
+    /// A user's membership in an organization, reporting organizations they
+    /// do not belong to as not found
+    pub async fn membership(&self, org_id: Uuid, user_id: Uuid) -> Result<Membership, DomainError> {
+        self.organization_repo
+            .find_membership(org_id, user_id)
+            .await?
+            .ok_or(DomainError::OrganizationNotFound)
+    }
+
+    /// The organization of a membership
+    pub async fn get(&self, membership: &Membership) -> Result<Organization, DomainError> {
+        self.organization_repo
+            .find(membership.tenant())
+            .await?
+            .ok_or(DomainError::OrganizationNotFound)
+    }
+
+    /// List the members of the caller's organization
+    pub async fn list_members(&self, membership: &Membership) -> Result<Vec<Member>, DomainError> {
+        Ok(self.members(membership).list().await?)
+    }
+
+    /// Get a member of the caller's organization
+    pub async fn get_member(
+        &self,
+        membership: &Membership,
+        user_id: Uuid,
+    ) -> Result<Member, DomainError> {
+        self.members(membership)
+            .find(user_id)
+            .await?
+            .ok_or(DomainError::MemberNotFound)
+    }
+
+    /// Change the role of a member of the caller's organization
+    pub async fn update_member_role(
+        &self,
+        membership: &Membership,
+        user_id: Uuid,
+        role: OrgRole,
+    ) -> Result<Member, DomainError> {
+        let member = self.get_member(membership, user_id).await?;
+        if !membership.role.can_assign(member.membership.role) || !membership.role.can_assign(role)
+        {
+            return Err(DomainError::InsufficientOrgRole);
+        }
+
+        applied(self.members(membership).update_role(user_id, role).await?)?;
+        tracing::info!(
+            "User {} changed the role of {} in organization {} to {:?}",
+            membership.user_id,
+            user_id,
+            membership.org_id,
+            role
+        );
+
+        self.get_member(membership, user_id).await
+    }
+
+    /// Remove a member from the caller's organization. Members may always
+    /// remove themselves, i.e. leave.
+    pub async fn remove_member(
+        &self,
+        membership: &Membership,
+        user_id: Uuid,
+    ) -> Result<(), DomainError> {
+        let member = self.get_member(membership, user_id).await?;
+        if user_id != membership.user_id && !membership.role.can_assign(member.membership.role) {
+            return Err(DomainError::InsufficientOrgRole);
+        }
+
+        applied(self.members(membership).remove(user_id).await?)?;
+        tracing::info!(
+            "User {} removed {} from organization {}",
+            membership.user_id,
+            user_id,
+            membership.org_id
+        );
+
+        Ok(())
+    }
+
+    /// Invite someone to the caller's organization by email
+    pub async fn invite(
+        &self,
+        membership: &Membership,
+        email: &str,
+        role: OrgRole,
+    ) -> Result<Invitation, DomainError> {
+        if !membership.role.can_assign(role) {
+            return Err(DomainError::InsufficientOrgRole);
+        }
+
+        let email = self.state.config.normalize_email(email);
+        if self.members(membership).exists_with_email(&email).await? {
+            return Err(DomainError::AlreadyMember);
+        }
+        let organization = self.get(membership).await?;
+
+        let config = &self.state.config;
+        let raw_token = token::generate();
+        let invitation = Invitation::new(
+            membership.tenant(),
+            email,
+            role,
+            token::hash(&raw_token),
+            membership.user_id,
+            config.org_invitation_expiration_hours,
+        );
+        self.invitations(membership).create(&invitation).await?;
+
+        let invitation_link = format!(
+            "{}/accept-invitation?token={}",
+            config.frontend_url, raw_token
+        );
+        send_in_background(
+            self.state.mailer.clone(),
+            templates::organization_invitation(
+                &invitation.email,
+                &organization.name,
+                &invitation_link,
+                config.org_invitation_expiration_hours,
+            ),
+        );
+
+        Ok(invitation)
+    }
+
+    /// List the pending invitations of the caller's organization
+    pub async fn list_invitations(
+        &self,
+        membership: &Membership,
+    ) -> Result<Vec<Invitation>, DomainError> {
+        if !membership.role.can_manage_members() {
+            return Err(DomainError::InsufficientOrgRole);
+        }
+
+        Ok(self.invitations(membership).list_pending().await?)
+    }
+
+    /// Withdraw a pending invitation of the caller's organization
+    pub async fn revoke_invitation(
+        &self,
+        membership: &Membership,
+        id: Uuid,
+    ) -> Result<(), DomainError> {
+        let invitations = self.invitations(membership);
+        let invitation = invitations
+            .find(id)
+            .await?
+            .ok_or(DomainError::InvitationNotFound)?;
+        if !membership.role.can_assign(invitation.role) {
+            return Err(DomainError::InsufficientOrgRole);
+        }
+
+        if !invitations.revoke(id).await? {
+            return Err(DomainError::InvitationNotFound);
+        }
+
+        Ok(())
+    }
+
+    /// Accept an invitation sent to the user's email address
+    pub async fn accept_invitation(
+        &self,
+        user_id: Uuid,
+        token: &str,
+    ) -> Result<OrganizationMembership, DomainError> {
+        let user = self
+            .user_repo
+            .find_by_id(user_id)
+            .await?
+            .ok_or(DomainError::UserNotFound)?;
+
+        let membership = self
+            .organization_repo
+            .accept_invitation(&token::hash(token), &user.email, user.id)
+            .await?
+            .ok_or(DomainError::InvalidInvitation)?;
+        tracing::info!("User {} joined organization {}", user_id, membership.org_id);
+
+        Ok(OrganizationMembership {
+            organization: self.get(&membership).await?,
+            role: membership.role,
+        })
+    }
+
+    fn members(&self, membership: &Membership) -> MemberRepository<'a> {
+        MemberRepository::new(
+            &self.state.db_pool,
+            membership.tenant(),
+            self.state.config.tenant_isolation,
+        )
+    }
+
+    fn invitations(&self, membership: &Membership) -> InvitationRepository<'a> {
+        InvitationRepository::new(
+            &self.state.db_pool,
+            membership.tenant(),
+            self.state.config.tenant_isolation,
+        )
+    }
+}
+
+fn applied(change: MemberChange) -> Result<(), DomainError> {
+    match change {
+        MemberChange::Applied => Ok(()),
+        MemberChange::NotFound => Err(DomainError::MemberNotFound),
+        MemberChange::LastOwner => Err(DomainError::LastOrganizationOwner),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::{
+        common::jwt::verify_token,
+        config::AppConfig,
+        domain::{
+            models::User,
+            services::{AuthService, UserService},
+        },
+        infrastructure::repositories::TenantIsolation,
+        test_utils::{test_config, test_state, test_state_with, token_from_email, wait_for_email},
+    };
+
+    async fn register(state: &AppState, email: &str) -> User {
+        AuthService::new(state)
+            .register(email, "password123", "Org User")
+            .await
+            .unwrap();
+        UserService::new(state).get_by_email(email).await.unwrap()
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_members_and_invitations_are_tenant_scoped(pool: PgPool) {
+        members_and_invitations_are_tenant_scoped(pool, TenantIsolation::Filter).await;
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_members_and_invitations_are_tenant_scoped_under_row_level_security(pool: PgPool) {
+        // The repositories' queries must also hold up under the database policies
+        members_and_invitations_are_tenant_scoped(pool, TenantIsolation::RowLevelSecurity).await;
+    }
+
+    async fn members_and_invitations_are_tenant_scoped(pool: PgPool, isolation: TenantIsolation) {
+        let config = AppConfig {
+            tenant_isolation: isolation,
+            ..test_config()
+        };
+        let (state, mailer) = test_state_with(pool, config);
+        let owner = register(&state, "owner@example.com").await;
+        let invitee = register(&state, "invitee@example.com").await;
+        let outsider = register(&state, "outsider@example.com").await;
+        let organization_service = OrganizationService::new(&state);
+
+        let acme = organization_service
+            .create(owner.id, "Acme".into())
+            .await
+            .unwrap();
+        let other = organization_service
+            .create(outsider.id, "Other".into())
+            .await
+            .unwrap();
+        let owner_membership = organization_service
+            .membership(acme.organization.id, owner.id)
+            .await
+            .unwrap();
+        let outsider_membership = organization_service
+            .membership(other.organization.id, outsider.id)
+            .await
+            .unwrap();
+
+        // Invitations only work for the address they were sent to
+        organization_service
+            .invite(&owner_membership, "Invitee@EXAMPLE.com", OrgRole::Member)
+            .await
+            .unwrap();
+        let email = wait_for_email(
+            &mailer,
+            "Invitee@example.com",
+            "You have been invited to join Acme",
+        )
+        .await;
+        let token = token_from_email(&email);
+        assert!(matches!(
+            organization_service
+                .accept_invitation(outsider.id, &token)
+                .await,
+            Err(DomainError::InvalidInvitation)
+        ));
+        let joined = organization_service
+            .accept_invitation(invitee.id, &token)
+            .await
+            .unwrap();
+        assert_eq!(joined.organization.id, acme.organization.id);
+        assert_eq!(joined.role, OrgRole::Member);
+        assert_eq!(
+            organization_service
+                .list_members(&owner_membership)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+
+        // Rows of other organizations are not found rather than forbidden
+        assert!(matches!(
+            organization_service
+                .get_member(&owner_membership, outsider.id)
+                .await,
+            Err(DomainError::MemberNotFound)
+        ));
+        assert!(matches!(
+            organization_service
+                .remove_member(&outsider_membership, owner.id)
+                .await,
+            Err(DomainError::MemberNotFound)
+        ));
+        assert!(matches!(
+            organization_service
+                .membership(acme.organization.id, outsider.id)
+                .await,
+            Err(DomainError::OrganizationNotFound)
+        ));
+        let pending = organization_service
+            .invite(&outsider_membership, "someone@example.com", OrgRole::Admin)
+            .await
+            .unwrap();
+        assert!(matches!(
+            organization_service
+                .revoke_invitation(&owner_membership, pending.id)
+                .await,
+            Err(DomainError::InvitationNotFound)
+        ));
+
+        // Roles limit what members can do, and the last owner stays
+        let invitee_membership = organization_service
+            .membership(acme.organization.id, invitee.id)
+            .await
+            .unwrap();
+        assert!(matches!(
+            organization_service
+                .remove_member(&invitee_membership, owner.id)
+                .await,
+            Err(DomainError::InsufficientOrgRole)
+        ));
+        assert!(matches!(
+            organization_service
+                .update_member_role(&owner_membership, owner.id, OrgRole::Admin)
+                .await,
+            Err(DomainError::LastOrganizationOwner)
+        ));
+        let promoted = organization_service
+            .update_member_role(&owner_membership, invitee.id, OrgRole::Admin)
+            .await
+            .unwrap();
+        assert_eq!(promoted.membership.role, OrgRole::Admin);
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_switch_organization(pool: PgPool) {
+        let (state, _mailer) = test_state(pool);
+        let user = register(&state, "switch@example.com").await;
+        let outsider = register(&state, "elsewhere@example.com").await;
+        let organization_service = OrganizationService::new(&state);
+        let auth_service = AuthService::new(&state);
+
+        let acme = organization_service
+            .create(user.id, "Acme".into())
+            .await
+            .unwrap();
+        let other = organization_service
+            .create(outsider.id, "Other".into())
+            .await
+            .unwrap();
+        let tokens = auth_service.create_session(user.id).await.unwrap();
+        let claims = verify_token(&tokens.access_token, &state.jwt_keys).unwrap();
+        assert_eq!(claims.org_id, None);
+
+        assert!(matches!(
+            auth_service
+                .switch_organization(user.id, &tokens.refresh_token, Some(other.organization.id))
+                .await,
+            Err(DomainError::OrganizationNotFound)
+        ));
+
+        let switched = auth_service
+            .switch_organization(user.id, &tokens.refresh_token, Some(acme.organization.id))
+            .await
+            .unwrap();
+        let switched_claims = verify_token(&switched.access_token, &state.jwt_keys).unwrap();
+        assert_eq!(switched_claims.org_id, Some(acme.organization.id));
+        assert_eq!(switched_claims.sid, claims.sid);
+
+        // Refreshing keeps the organization
+        let refreshed = auth_service.refresh(&switched.refresh_token).await.unwrap();
+        let refreshed_claims = verify_token(&refreshed.access_token, &state.jwt_keys).unwrap();
+        assert_eq!(refreshed_claims.org_id, Some(acme.organization.id));
+
+        // ...until the user no longer belongs to it
+        let admin = register(&state, "second-owner@example.com").await;
+        let membership = organization_service
+            .membership(acme.organization.id, user.id)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO organization_memberships (org_id, user_id, role) VALUES ($1, $2, 'owner')",
+        )
+        .bind(acme.organization.id)
+        .bind(admin.id)
+        .execute(&state.db_pool)
+        .await
+        .unwrap();
+        organization_service
+            .remove_member(&membership, user.id)
+            .await
+            .unwrap();
+        let left = auth_service
+            .refresh(&refreshed.refresh_token)
+            .await
+            .unwrap();
+        let left_claims = verify_token(&left.access_token, &state.jwt_keys).unwrap();
+        assert_eq!(left_claims.org_id, None);
+    }
+}
@@ -0,0 +1,36 @@
+//! Password policy enforcement for new passwords
+
+use crate::{
+    common::password_policy::PolicyViolation, config::AppState, domain::errors::DomainError,
+};
+
+pub struct PasswordPolicyService<'a> {
+    state: &'a AppState,
+}
+
+impl<'a> PasswordPolicyService<'a> {
+    pub fn new(state: &'a AppState) -> Self {
+        Self { state }
+    }
+
+    /// Check a new password for the user with `email` and `name` against the
+    /// configured policy and the breached-password list.
+    ///
+    /// Fails with every rule the password breaks.
+    pub fn check(&self, password: &str, email: &str, name: &str) -> Result<(), DomainError> {
+        let mut violations = self
+            .state
+            .config
+            .password_policy
+            .check(password, &[email, name]);
+        if self.state.breached_passwords.contains(password) {
+            violations.push(PolicyViolation::Breached);
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(DomainError::WeakPassword(violations))
+        }
+    }
+}
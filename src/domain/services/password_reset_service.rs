@@ -0,0 +1,187 @@
+//! Password reset service
+
+use chrono::Utc;
+
+use crate::{
+    common::{password, token},
+    config::AppState,
+    domain::{
+        errors::DomainError,
+        models::PasswordResetToken,
+        services::{LockoutService, PasswordPolicyService, SessionService},
+    },
+    infrastructure::{
+        mail::{send_in_background, templates},
+        repositories::{PasswordResetRepository, UserRepository},
+    },
+};
+
+pub struct PasswordResetService<'a> {
+    state: &'a AppState,
+    user_repo: UserRepository<'a>,
+    reset_repo: PasswordResetRepository<'a>,
+}
+
+impl<'a> PasswordResetService<'a> {
+    pub fn new(state: &'a AppState) -> Self {
+        Self {
+            state,
+            user_repo: UserRepository::new(&state.db_pool),
+            reset_repo: PasswordResetRepository::new(&state.db_pool),
+        }
+    }
+
+    /// Email a password reset link to the owner of `email`.
+    ///
+    /// Succeeds whether or not the email belongs to an account so callers
+    /// cannot use this to discover registered emails.
+    pub async fn request_reset(&self, email: &str) -> Result<(), DomainError> {
+        let email = self.state.config.normalize_email(email);
+        let Some(user) = self.user_repo.find_by_email(&email).await? else {
+            return Ok(());
+        };
+
+        // Only the most recently requested link stays valid
+        self.reset_repo.invalidate_for_user(user.id).await?;
+
+        let config = &self.state.config;
+        let raw_token = token::generate();
+        let reset_token = PasswordResetToken::new(
+            user.id,
+            token::hash(&raw_token),
+            config.password_reset_expiration_minutes,
+        );
+        self.reset_repo.create(&reset_token).await?;
+
+        let reset_link = format!("{}/reset-password?token={}", config.frontend_url, raw_token);
+        send_in_background(
+            self.state.mailer.clone(),
+            templates::password_reset(
+                &user.email,
+                &reset_link,
+                config.password_reset_expiration_minutes,
+            ),
+        );
+
+        Ok(())
+    }
+
+    /// Set a new password using a reset token.
+    ///
+    /// Changing the password revokes all of the user's existing sessions and
+    /// lifts any lock placed on the account after failed logins.
+    pub async fn reset_password(&self, token: &str, new_password: &str) -> Result<(), DomainError> {
+        let token_hash = token::hash(token);
+
+        // Check the new password before using up the token, so it can be retried
+        let user = match self.reset_repo.find_valid(&token_hash).await? {
+            Some(user_id) => self.user_repo.find_by_id(user_id).await?,
+            None => None,
+        }
+        .ok_or(DomainError::InvalidResetToken)?;
+        PasswordPolicyService::new(self.state).check(new_password, &user.email, &user.name)?;
+
+        let password_hash = password::hash(new_password, &self.state.config.hash_params())
+            .await
+            .map_err(|_| DomainError::PasswordHashingFailed)?;
+
+        let user_id = self
+            .reset_repo
+            .consume(&token_hash)
+            .await?
+            .ok_or(DomainError::InvalidResetToken)?;
+
+        let changed_at = Utc::now();
+        self.user_repo
+            .update_password(user_id, &password_hash, changed_at)
+            .await?;
+        LockoutService::new(self.state).reset(&user.email).await?;
+        SessionService::new(self.state)
+            .end_sessions_before(user_id, changed_at)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::{
+        domain::services::AuthService,
+        test_utils::{test_state, token_from_email, wait_for_email},
+    };
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_reset_password_flow(pool: PgPool) {
+        let (state, mailer) = test_state(pool);
+        let auth_service = AuthService::new(&state);
+        let reset_service = PasswordResetService::new(&state);
+
+        auth_service
+            .register("reset@example.com", "old_password", "Reset User")
+            .await
+            .unwrap();
+
+        reset_service
+            .request_reset("reset@example.com")
+            .await
+            .unwrap();
+        let email = wait_for_email(&mailer, "reset@example.com", "Reset your password").await;
+        let token = token_from_email(&email);
+
+        reset_service
+            .reset_password(&token, "new_password")
+            .await
+            .unwrap();
+
+        assert!(
+            auth_service
+                .login(
+                    "reset@example.com",
+                    "old_password",
+                    Ipv4Addr::LOCALHOST.into()
+                )
+                .await
+                .is_err()
+        );
+        assert!(
+            auth_service
+                .login(
+                    "reset@example.com",
+                    "new_password",
+                    Ipv4Addr::LOCALHOST.into()
+                )
+                .await
+                .is_ok()
+        );
+
+        // Tokens are single-use
+        assert!(matches!(
+            reset_service
+                .reset_password(&token, "another_password")
+                .await,
+            Err(DomainError::InvalidResetToken)
+        ));
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_request_reset_for_unknown_email(pool: PgPool) {
+        let (state, mailer) = test_state(pool);
+        let reset_service = PasswordResetService::new(&state);
+
+        reset_service
+            .request_reset("nobody@example.com")
+            .await
+            .unwrap();
+
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert!(mailer.messages_to("nobody@example.com").is_empty());
+    }
+}
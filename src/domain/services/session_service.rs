@@ -0,0 +1,291 @@
+//! Session service - logout and access token revocation
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    common::jwt::Claims,
+    config::AppState,
+    domain::errors::DomainError,
+    infrastructure::repositories::{RefreshTokenRepository, RevocationRepository},
+};
+
+pub struct SessionService<'a> {
+    state: &'a AppState,
+    revocation_repo: RevocationRepository<'a>,
+    refresh_token_repo: RefreshTokenRepository<'a>,
+}
+
+impl<'a> SessionService<'a> {
+    pub fn new(state: &'a AppState) -> Self {
+        Self {
+            state,
+            revocation_repo: RevocationRepository::new(&state.db_pool),
+            refresh_token_repo: RefreshTokenRepository::new(&state.db_pool),
+        }
+    }
+
+    /// End the session the given access token belongs to
+    pub async fn logout(&self, claims: &Claims) -> Result<(), DomainError> {
+        self.revoke_access_token(claims).await?;
+        self.refresh_token_repo.revoke_family(claims.sid).await?;
+
+        Ok(())
+    }
+
+    /// End every session of the user the given access token belongs to
+    pub async fn logout_all(&self, claims: &Claims) -> Result<(), DomainError> {
+        self.end_all_sessions(claims.sub).await
+    }
+
+    /// End every session of a user, e.g. when an administrator deactivates them
+    pub async fn end_all_sessions(&self, user_id: Uuid) -> Result<(), DomainError> {
+        // Tokens are stamped with the application's clock, so cutoffs are too
+        let cutoff = Utc::now();
+        self.revocation_repo
+            .revoke_all_for_user(user_id, cutoff)
+            .await?;
+        self.end_sessions_before(user_id, cutoff).await
+    }
+
+    /// End every session of a user whose access tokens issued before `cutoff`
+    /// are now rejected, e.g. after a password change
+    pub async fn end_sessions_before(
+        &self,
+        user_id: Uuid,
+        cutoff: DateTime<Utc>,
+    ) -> Result<(), DomainError> {
+        self.state.revocation_cache.user_cutoffs.insert(
+            user_id,
+            Some(cutoff.timestamp_millis()),
+            self.cache_ttl(),
+        );
+        self.refresh_token_repo.revoke_all_for_user(user_id).await?;
+
+        Ok(())
+    }
+
+    /// Check whether an access token has been revoked, either individually or
+    /// by a user-wide cutoff (logout everywhere, password change, deactivation)
+    pub async fn is_revoked(&self, claims: &Claims) -> Result<bool, DomainError> {
+        let cache = &self.state.revocation_cache;
+
+        let token_revoked = match cache.tokens.get(&claims.jti) {
+            Some(revoked) => revoked,
+            None => {
+                let revoked = self.revocation_repo.is_token_revoked(claims.jti).await?;
+                let ttl = if revoked {
+                    time_until(claims.exp)
+                } else {
+                    self.cache_ttl()
+                };
+                cache.tokens.insert(claims.jti, revoked, ttl);
+                revoked
+            }
+        };
+
+        if token_revoked {
+            return Ok(true);
+        }
+
+        let cutoff = match cache.user_cutoffs.get(&claims.sub) {
+            Some(cutoff) => cutoff,
+            None => {
+                let cutoff = self
+                    .revocation_repo
+                    .find_user_cutoff(claims.sub, Utc::now())
+                    .await?
+                    .map(|cutoff| cutoff.timestamp_millis());
+                cache
+                    .user_cutoffs
+                    .insert(claims.sub, cutoff, self.cache_ttl());
+                cutoff
+            }
+        };
+
+        // Cutoffs are finer than milliseconds, so a token issued in the same
+        // millisecond as the cutoff may predate it
+        Ok(cutoff.is_some_and(|cutoff| claims.issued_at_millis() <= cutoff))
+    }
+
+    /// Remove revocation entries for tokens that have expired anyway
+    pub async fn purge_expired(&self) -> Result<u64, DomainError> {
+        Ok(self.revocation_repo.delete_expired().await?)
+    }
+
+    async fn revoke_access_token(&self, claims: &Claims) -> Result<(), DomainError> {
+        let expires_at = DateTime::<Utc>::from_timestamp(claims.exp, 0).unwrap_or_else(Utc::now);
+
+        self.revocation_repo
+            .revoke_token(claims.jti, claims.sub, expires_at)
+            .await?;
+        self.state
+            .revocation_cache
+            .tokens
+            .insert(claims.jti, true, time_until(claims.exp));
+
+        Ok(())
+    }
+
+    fn cache_ttl(&self) -> Duration {
+        Duration::from_secs(self.state.config.revocation_cache_ttl_secs)
+    }
+}
+
+/// Time remaining until a unix timestamp (zero if already passed)
+fn time_until(timestamp: i64) -> Duration {
+    Duration::from_secs(u64::try_from(timestamp - Utc::now().timestamp()).unwrap_or(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::{
+        common::{jwt::verify_token, password},
+        domain::services::{AuthService, AuthTokens, LoginOutcome},
+        infrastructure::repositories::UserRepository,
+        test_utils::test_state,
+    };
+
+    async fn login(auth_service: &AuthService<'_>) -> AuthTokens {
+        match auth_service
+            .login(
+                "session@example.com",
+                "password123",
+                Ipv4Addr::LOCALHOST.into(),
+            )
+            .await
+            .unwrap()
+        {
+            LoginOutcome::Authenticated(tokens) => tokens,
+            LoginOutcome::MfaRequired { .. } => panic!("unexpected second factor"),
+        }
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_logout_revokes_tokens_immediately(pool: PgPool) {
+        let (state, _mailer) = test_state(pool);
+        let auth_service = AuthService::new(&state);
+        let session_service = SessionService::new(&state);
+        auth_service
+            .register("session@example.com", "password123", "Session User")
+            .await
+            .unwrap();
+
+        let first = login(&auth_service).await;
+        let second = login(&auth_service).await;
+        let first_claims = verify_token(&first.access_token, &state.jwt_keys).unwrap();
+        let second_claims = verify_token(&second.access_token, &state.jwt_keys).unwrap();
+
+        // Logout ends only its own session
+        session_service.logout(&first_claims).await.unwrap();
+        assert!(session_service.is_revoked(&first_claims).await.unwrap());
+        assert!(!session_service.is_revoked(&second_claims).await.unwrap());
+        assert!(auth_service.refresh(&first.refresh_token).await.is_err());
+
+        // Logout everywhere applies to tokens issued in the same second
+        session_service.logout_all(&second_claims).await.unwrap();
+        assert!(session_service.is_revoked(&second_claims).await.unwrap());
+        assert!(auth_service.refresh(&second.refresh_token).await.is_err());
+
+        let third = login(&auth_service).await;
+        let third_claims = verify_token(&third.access_token, &state.jwt_keys).unwrap();
+        assert!(!session_service.is_revoked(&third_claims).await.unwrap());
+
+        // The database agrees once the cache is cold
+        state
+            .revocation_cache
+            .user_cutoffs
+            .remove(&third_claims.sub);
+        assert!(session_service.is_revoked(&second_claims).await.unwrap());
+        assert!(!session_service.is_revoked(&third_claims).await.unwrap());
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_database_changes_revoke_tokens(pool: PgPool) {
+        let (state, _mailer) = test_state(pool);
+        let auth_service = AuthService::new(&state);
+        let session_service = SessionService::new(&state);
+        let user_repo = UserRepository::new(&state.db_pool);
+        auth_service
+            .register("session@example.com", "password123", "Session User")
+            .await
+            .unwrap();
+
+        // A password change made outside the application
+        let tokens = login(&auth_service).await;
+        let claims = verify_token(&tokens.access_token, &state.jwt_keys).unwrap();
+        let hash = password::hash("password123", &state.config.hash_params())
+            .await
+            .unwrap();
+        user_repo
+            .update_password(claims.sub, &hash, Utc::now())
+            .await
+            .unwrap();
+        assert!(session_service.is_revoked(&claims).await.unwrap());
+        assert!(matches!(
+            auth_service.refresh(&tokens.refresh_token).await,
+            Err(DomainError::InvalidRefreshToken)
+        ));
+
+        // Deactivation
+        let tokens = login(&auth_service).await;
+        let claims = verify_token(&tokens.access_token, &state.jwt_keys).unwrap();
+        assert!(!session_service.is_revoked(&claims).await.unwrap());
+        user_repo.set_active(claims.sub, false).await.unwrap();
+        state.revocation_cache.user_cutoffs.remove(&claims.sub);
+        assert!(session_service.is_revoked(&claims).await.unwrap());
+        user_repo.set_active(claims.sub, true).await.unwrap();
+        assert!(matches!(
+            auth_service.refresh(&tokens.refresh_token).await,
+            Err(DomainError::InvalidRefreshToken)
+        ));
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_cutoff_in_the_millisecond_a_token_was_issued(pool: PgPool) {
+        let (state, _mailer) = test_state(pool);
+        let auth_service = AuthService::new(&state);
+        let session_service = SessionService::new(&state);
+        auth_service
+            .register("session@example.com", "password123", "Session User")
+            .await
+            .unwrap();
+        let tokens = login(&auth_service).await;
+        let claims = verify_token(&tokens.access_token, &state.jwt_keys).unwrap();
+        let issued_at = claims.issued_at_millis();
+        let later = Claims {
+            jti: Uuid::new_v4(),
+            iat_ms: Some(issued_at + 1),
+            ..claims.clone()
+        };
+
+        // The token may predate a cutoff within its millisecond, so it is
+        // rejected; a token from the next millisecond is not
+        let cutoff = DateTime::from_timestamp_millis(issued_at).unwrap()
+            + chrono::Duration::microseconds(500);
+        RevocationRepository::new(&state.db_pool)
+            .revoke_all_for_user(claims.sub, cutoff)
+            .await
+            .unwrap();
+        assert!(session_service.is_revoked(&claims).await.unwrap());
+        assert!(!session_service.is_revoked(&later).await.unwrap());
+
+        // The cached cutoff gives the same answers
+        session_service
+            .end_sessions_before(claims.sub, cutoff)
+            .await
+            .unwrap();
+        assert!(session_service.is_revoked(&claims).await.unwrap());
+        assert!(!session_service.is_revoked(&later).await.unwrap());
+    }
+}
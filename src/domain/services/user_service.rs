@@ -1,20 +1,87 @@
 //! User service
 
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+use serde_json::json;
 use uuid::Uuid;
 
+use super::auth_service::DEFAULT_ROLE;
 use crate::{
-    config::AppState,
-    domain::{errors::DomainError, models::User},
-    infrastructure::repositories::UserRepository,
+    common::{
+        email,
+        jwt::Claims,
+        pagination::{Cursor, Page, PageRequest, SortDirection},
+        password,
+        search::{self, Fragment},
+    },
+    config::{AppState, UserPurgeMode},
+    domain::{
+        errors::DomainError,
+        models::{AuditLog, User, UserFilter, UserSortField},
+        services::{AuthService, EmailVerificationService, SessionService},
+    },
+    infrastructure::repositories::{
+        ApiKeyRepository, AuditLogRepository, DataExportRepository, UserRepository,
+    },
 };
 
+/// A user brought over from another system, with its existing password hash
+#[derive(Debug)]
+pub struct ImportedUser {
+    pub email: String,
+    pub name: String,
+    /// Argon2, bcrypt or PBKDF2-SHA256 hash; replaced by Argon2 on first login
+    pub password_hash: String,
+    pub email_verified: bool,
+}
+
+/// Why an imported user was not created
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportSkipReason {
+    /// An account with the email already exists
+    AlreadyExists,
+    /// The password hash is in a format we cannot verify, or its cost is
+    /// above the limits of [`password`], e.g. [`password::MAX_BCRYPT_COST`]
+    UnsupportedHash,
+}
+
+/// Outcome of a bulk import
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub imported: usize,
+    /// Users that were not created, by email
+    pub skipped: Vec<(String, ImportSkipReason)>,
+}
+
+/// Accounts whose emails are the same address once normalized
+#[derive(Debug)]
+pub struct DuplicateEmail {
+    /// The address they share, lowercased
+    pub email: String,
+    /// Id, stored email and creation time of each account, oldest first
+    pub accounts: Vec<(Uuid, String, DateTime<Utc>)>,
+}
+
+/// A user found by [`UserService::search`]
+#[derive(Debug)]
+pub struct UserSearchHit {
+    pub user: User,
+    /// Higher is a better match
+    pub rank: f32,
+    /// Fields containing a search term, split into matched and unmatched parts
+    pub highlights: Vec<(&'static str, Vec<Fragment>)>,
+}
+
 pub struct UserService<'a> {
+    state: &'a AppState,
     user_repo: UserRepository<'a>,
 }
 
 impl<'a> UserService<'a> {
     pub fn new(state: &'a AppState) -> Self {
         Self {
+            state,
             user_repo: UserRepository::new(&state.db_pool),
         }
     }
@@ -31,8 +98,1040 @@ impl<'a> UserService<'a> {
     #[allow(dead_code)]
     pub async fn get_by_email(&self, email: &str) -> Result<User, DomainError> {
         self.user_repo
-            .find_by_email(email)
+            .find_by_email(&self.state.config.normalize_email(email))
             .await?
             .ok_or(DomainError::UserNotFound)
     }
+
+    /// List users page by page.
+    ///
+    /// A cursor from a previous page must come from a listing with the same sort.
+    pub async fn list(
+        &self,
+        filter: &UserFilter,
+        sort: UserSortField,
+        direction: SortDirection,
+        page: PageRequest,
+        include_total: bool,
+    ) -> Result<Page<User>, DomainError> {
+        let limit = page.limit();
+        let (after, offset) = match page {
+            PageRequest::Cursor { after: None, .. } => (None, 0),
+            PageRequest::Cursor {
+                after: Some(encoded),
+                ..
+            } => {
+                let cursor = Cursor::decode(&encoded, sort.column(), direction)
+                    .map_err(|_| DomainError::InvalidCursor)?;
+                if sort == UserSortField::CreatedAt
+                    && DateTime::parse_from_rfc3339(&cursor.value).is_err()
+                {
+                    return Err(DomainError::InvalidCursor);
+                }
+                (Some(cursor), 0)
+            }
+            PageRequest::Offset { offset, .. } => (None, offset),
+        };
+
+        let users = self
+            .user_repo
+            .list(filter, sort, direction, after.as_ref(), offset, limit + 1)
+            .await?;
+        let total = if include_total {
+            Some(self.user_repo.count(filter).await?)
+        } else {
+            None
+        };
+
+        Ok(Page::from_overfetched(
+            users,
+            limit,
+            |user| Cursor::new(sort.column(), direction, sort.value_of(user), user.id),
+            total,
+        ))
+    }
+
+    /// Search users matching `filter` by part of their name or email, best
+    /// matches first
+    pub async fn search(
+        &self,
+        query: &str,
+        filter: &UserFilter,
+        limit: i64,
+    ) -> Result<Vec<UserSearchHit>, DomainError> {
+        let query = search::normalize(query);
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let terms = search::terms(&query);
+        let results = self.user_repo.search(&query, filter, limit).await?;
+
+        Ok(results
+            .into_iter()
+            .map(|result| {
+                let highlights = [("name", &result.user.name), ("email", &result.user.email)]
+                    .into_iter()
+                    .filter_map(|(field, text)| {
+                        search::highlight(text, &terms).map(|fragments| (field, fragments))
+                    })
+                    .collect();
+
+                UserSearchHit {
+                    user: result.user,
+                    rank: result.rank,
+                    highlights,
+                }
+            })
+            .collect())
+    }
+
+    /// Update the user's own name and email.
+    ///
+    /// Changing the email requires the current password. The new address only
+    /// replaces the current one once the user follows the link sent to it, and
+    /// the current address is told about the change.
+    pub async fn update_profile(
+        &self,
+        id: Uuid,
+        name: Option<String>,
+        email: Option<String>,
+        current_password: Option<&str>,
+    ) -> Result<User, DomainError> {
+        let mut user = self.get_by_id(id).await?;
+
+        let new_email = email
+            .map(|email| self.state.config.normalize_email(&email))
+            .filter(|email| *email != user.email);
+        if let Some(email) = &new_email {
+            let password = current_password.ok_or(DomainError::IncorrectPassword)?;
+            AuthService::new(self.state)
+                .confirm_password(&user, password)
+                .await?;
+
+            if self.user_repo.email_taken(email).await? {
+                return Err(DomainError::UserAlreadyExists);
+            }
+        }
+
+        if let Some(name) = name {
+            user.name = name;
+            self.user_repo.update(&user).await?;
+        }
+
+        if let Some(email) = new_email {
+            self.user_repo.set_pending_email(user.id, &email).await?;
+            EmailVerificationService::new(self.state)
+                .send_email_change(&user, &email)
+                .await?;
+            tracing::info!("User {} requested an email address change", user.id);
+        }
+
+        Ok(user)
+    }
+
+    /// Delete the account of the user the access token belongs to.
+    ///
+    /// Requires the account password. The account is deactivated rather than
+    /// removed, and can be restored by an administrator until it is purged
+    /// after the grace period. Its sessions and API keys stop working
+    /// immediately and are not brought back by a restore; its data exports
+    /// are deleted. The only owner of an organization must hand it over first.
+    pub async fn delete_account(&self, claims: &Claims, password: &str) -> Result<(), DomainError> {
+        let user = self.get_by_id(claims.sub).await?;
+        AuthService::new(self.state)
+            .confirm_password(&user, password)
+            .await?;
+
+        if !self.user_repo.delete(user.id).await? {
+            return Err(DomainError::LastOrganizationOwner);
+        }
+        ApiKeyRepository::new(&self.state.db_pool)
+            .revoke_all_for_user(user.id)
+            .await?;
+        DataExportRepository::new(&self.state.db_pool)
+            .delete_for_user(user.id)
+            .await?;
+        SessionService::new(self.state).logout_all(claims).await?;
+        tracing::info!("User {} deleted their account", user.id);
+
+        Ok(())
+    }
+
+    /// Purge accounts deleted longer ago than the grace period, removing or
+    /// anonymizing them as configured. Their email addresses can then be
+    /// registered again. Returns the number of accounts purged.
+    ///
+    /// Accounts left as the only owner of an organization, e.g. because the
+    /// other owners left during the grace period, wait until it has another.
+    pub async fn purge_deleted(&self) -> Result<usize, DomainError> {
+        const BATCH_SIZE: i64 = 100;
+
+        let config = &self.state.config;
+        let cutoff = Utc::now() - Duration::days(config.user_deletion_grace_days);
+        let audit_log_repo = AuditLogRepository::new(&self.state.db_pool);
+        let mut purged = 0;
+
+        loop {
+            let ids = self
+                .user_repo
+                .find_pending_purge(cutoff, BATCH_SIZE)
+                .await?;
+            if ids.is_empty() {
+                return Ok(purged);
+            }
+
+            for id in ids {
+                let (done, mode) = match config.user_purge_mode {
+                    UserPurgeMode::Delete => (self.user_repo.remove_deleted(id).await?, "delete"),
+                    UserPurgeMode::Anonymize => (self.user_repo.anonymize(id).await?, "anonymize"),
+                };
+                if !done {
+                    continue;
+                }
+                audit_log_repo
+                    .create(&AuditLog::system(
+                        "user.purged",
+                        Some(id),
+                        json!({ "mode": mode }),
+                    ))
+                    .await?;
+                purged += 1;
+            }
+        }
+    }
+
+    /// Rewrite stored provider aliases (`j.doe+news@gmail.com`) to the
+    /// canonical address logins and registrations look up (`jdoe@gmail.com`).
+    ///
+    /// Run on startup while provider canonicalization is on. Accounts whose
+    /// canonical address another account already has are left alone and
+    /// counted in the result; [`Self::duplicate_emails`] lists them.
+    pub async fn canonicalize_stored_emails(&self) -> Result<(usize, usize), DomainError> {
+        let (mut rewritten, mut conflicting) = (0, 0);
+
+        for (id, stored) in self
+            .user_repo
+            .list_emails_at_domains(&email::provider_domains())
+            .await?
+        {
+            let canonical = self.state.config.normalize_email(&stored);
+            if canonical == stored {
+                continue;
+            }
+
+            if self.user_repo.rewrite_email(id, &canonical).await? {
+                rewritten += 1;
+            } else {
+                conflicting += 1;
+            }
+        }
+
+        Ok((rewritten, conflicting))
+    }
+
+    /// Find accounts that share an email address under the current
+    /// normalization rules, including deactivated accounts.
+    ///
+    /// Used to clean up data stored before emails were normalized, or before
+    /// provider canonicalization was turned on.
+    pub async fn duplicate_emails(&self) -> Result<Vec<DuplicateEmail>, DomainError> {
+        let mut groups: Vec<DuplicateEmail> = Vec::new();
+        let mut group_of: HashMap<String, usize> = HashMap::new();
+
+        for (id, email, created_at) in self.user_repo.list_emails().await? {
+            let key = self.state.config.normalize_email(&email).to_lowercase();
+            let index = *group_of.entry(key.clone()).or_insert_with(|| {
+                groups.push(DuplicateEmail {
+                    email: key,
+                    accounts: Vec::new(),
+                });
+                groups.len() - 1
+            });
+            groups[index].accounts.push((id, email, created_at));
+        }
+
+        groups.retain(|group| group.accounts.len() > 1);
+        Ok(groups)
+    }
+
+    /// Create users with pre-hashed passwords.
+    ///
+    /// Users whose email is taken or whose hash format is not supported are
+    /// skipped and reported; the rest are created with the default role.
+    pub async fn import(&self, users: Vec<ImportedUser>) -> Result<ImportReport, DomainError> {
+        let mut report = ImportReport::default();
+
+        for imported in users {
+            if !password::is_supported(&imported.password_hash) {
+                report
+                    .skipped
+                    .push((imported.email, ImportSkipReason::UnsupportedHash));
+                continue;
+            }
+
+            let email = self.state.config.normalize_email(&imported.email);
+            let mut user = User::new(email, imported.password_hash, imported.name);
+            if imported.email_verified {
+                user.email_verified_at = Some(Utc::now());
+            }
+
+            if !self
+                .user_repo
+                .create_with_role_if_absent(&user, DEFAULT_ROLE)
+                .await?
+            {
+                report
+                    .skipped
+                    .push((user.email, ImportSkipReason::AlreadyExists));
+                continue;
+            }
+            report.imported += 1;
+        }
+
+        tracing::info!(
+            "Imported {} users, skipped {}",
+            report.imported,
+            report.skipped.len()
+        );
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::{
+        common::jwt::verify_token,
+        config::AppConfig,
+        domain::{
+            models::{DataExport, ExportFormat, OrgRole},
+            services::{Actor, AdminService, ApiKeyService, AuthService, OrganizationService},
+        },
+        infrastructure::repositories::RoleRepository,
+        test_utils::{test_config, test_state, test_state_with, token_from_email, wait_for_email},
+    };
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_list_pages_are_stable_under_inserts(pool: PgPool) {
+        let (state, _mailer) = test_state(pool);
+        let auth_service = AuthService::new(&state);
+        let user_service = UserService::new(&state);
+        for (email, name) in [
+            ("a@one.example", "Delta"),
+            ("b@two.example", "Alpha"),
+            ("c@one.example", "Charlie"),
+            ("d@ONE.example", "Bravo"),
+        ] {
+            auth_service
+                .register(email, "password123", name)
+                .await
+                .unwrap();
+        }
+        let emails = |page: &Page<User>| -> Vec<String> {
+            page.items.iter().map(|user| user.email.clone()).collect()
+        };
+        let all = UserFilter::default();
+        let newest_first = |after| PageRequest::Cursor { after, limit: 2 };
+
+        let first = user_service
+            .list(
+                &all,
+                UserSortField::CreatedAt,
+                SortDirection::Desc,
+                newest_first(None),
+                true,
+            )
+            .await
+            .unwrap();
+        assert_eq!(emails(&first), vec!["d@one.example", "c@one.example"]);
+        assert!(first.has_more);
+        assert_eq!(first.total, Some(4));
+
+        // A user registered in between does not shift the next page
+        auth_service
+            .register("e@one.example", "password123", "Echo")
+            .await
+            .unwrap();
+        let second = user_service
+            .list(
+                &all,
+                UserSortField::CreatedAt,
+                SortDirection::Desc,
+                newest_first(first.next_cursor.clone()),
+                false,
+            )
+            .await
+            .unwrap();
+        assert_eq!(emails(&second), vec!["b@two.example", "a@one.example"]);
+        assert!(!second.has_more);
+        assert_eq!(second.next_cursor, None);
+        assert_eq!(second.total, None);
+
+        // Cursors only continue the sort they came from
+        assert!(matches!(
+            user_service
+                .list(
+                    &all,
+                    UserSortField::Name,
+                    SortDirection::Desc,
+                    newest_first(first.next_cursor),
+                    false,
+                )
+                .await,
+            Err(DomainError::InvalidCursor)
+        ));
+
+        let one_example = UserFilter {
+            email_domain: Some("one.example".to_string()),
+            ..UserFilter::default()
+        };
+        let by_name = user_service
+            .list(
+                &one_example,
+                UserSortField::Name,
+                SortDirection::Asc,
+                PageRequest::Offset {
+                    offset: 1,
+                    limit: 2,
+                },
+                true,
+            )
+            .await
+            .unwrap();
+        assert_eq!(emails(&by_name), vec!["c@one.example", "a@one.example"]);
+        assert!(by_name.has_more);
+        assert_eq!(by_name.total, Some(4));
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_search_ranks_and_highlights(pool: PgPool) {
+        let (state, _mailer) = test_state(pool);
+        let auth_service = AuthService::new(&state);
+        let user_service = UserService::new(&state);
+        for (email, name) in [
+            ("jane.doe@example.com", "Jane Doe"),
+            ("jo@example.com", "Janet Smith"),
+            ("somchai@example.co.th", "สมชายใจดี"),
+            ("nam@example.co.th", "น้ำใส"),
+        ] {
+            auth_service
+                .register(email, "password123", name)
+                .await
+                .unwrap();
+        }
+        let emails = |hits: &[UserSearchHit]| -> Vec<String> {
+            hits.iter().map(|hit| hit.user.email.clone()).collect()
+        };
+        let all = UserFilter::default();
+
+        // Word prefixes; the exact substring ranks higher
+        let hits = user_service.search("JANE", &all, 10).await.unwrap();
+        assert_eq!(
+            emails(&hits),
+            vec!["jane.doe@example.com", "jo@example.com"]
+        );
+        assert!(hits[0].rank > hits[1].rank);
+        assert_eq!(
+            hits[0].highlights[0],
+            (
+                "name",
+                vec![
+                    Fragment {
+                        text: "Jane".to_string(),
+                        matched: true
+                    },
+                    Fragment {
+                        text: " Doe".to_string(),
+                        matched: false
+                    },
+                ]
+            )
+        );
+
+        // Words can match different fields; users matching all words rank first
+        let hits = user_service.search("doe example", &all, 10).await.unwrap();
+        assert_eq!(emails(&hits)[0], "jane.doe@example.com");
+
+        // Inside a Thai name written without spaces
+        let hits = user_service.search("ใจดี", &all, 10).await.unwrap();
+        assert_eq!(emails(&hits), vec!["somchai@example.co.th"]);
+        assert_eq!(hits[0].highlights[0].0, "name");
+
+        // SARA AM typed as NIKHAHIT + SARA AA still matches
+        let hits = user_service
+            .search("น\u{0E4D}\u{0E49}\u{0E32}", &all, 10)
+            .await
+            .unwrap();
+        assert_eq!(emails(&hits), vec!["nam@example.co.th"]);
+
+        // Listing filters narrow the matches
+        let janet = user_service.get_by_email("jo@example.com").await.unwrap();
+        UserRepository::new(&state.db_pool)
+            .set_active(janet.id, false)
+            .await
+            .unwrap();
+        let active = UserFilter {
+            active: Some(true),
+            ..UserFilter::default()
+        };
+        let hits = user_service.search("jane", &active, 10).await.unwrap();
+        assert_eq!(emails(&hits), vec!["jane.doe@example.com"]);
+
+        assert!(
+            user_service
+                .search("   ", &all, 10)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            user_service.search("example", &all, 2).await.unwrap().len(),
+            2
+        );
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_update_profile_changes_email_once_verified(pool: PgPool) {
+        let (state, mailer) = test_state(pool);
+        let auth_service = AuthService::new(&state);
+        let user_service = UserService::new(&state);
+        let ip = Ipv4Addr::LOCALHOST.into();
+        auth_service
+            .register("taken@example.com", "password123", "Other User")
+            .await
+            .unwrap();
+        auth_service
+            .register("old@example.com", "password123", "Old Name")
+            .await
+            .unwrap();
+        let user = user_service.get_by_email("old@example.com").await.unwrap();
+        let verification =
+            wait_for_email(&mailer, "old@example.com", "Verify your email address").await;
+        EmailVerificationService::new(&state)
+            .verify(&token_from_email(&verification))
+            .await
+            .unwrap();
+
+        // Renaming needs no password and keeps the address verified
+        let renamed = user_service
+            .update_profile(user.id, Some("New Name".to_string()), None, None)
+            .await
+            .unwrap();
+        assert_eq!(renamed.name, "New Name");
+        assert!(renamed.is_email_verified());
+
+        let new_email = || Some("new@example.com".to_string());
+        for password in [None, Some("wrong_password")] {
+            assert!(matches!(
+                user_service
+                    .update_profile(user.id, None, new_email(), password)
+                    .await,
+                Err(DomainError::IncorrectPassword)
+            ));
+        }
+        assert!(matches!(
+            user_service
+                .update_profile(
+                    user.id,
+                    None,
+                    Some("taken@example.com".to_string()),
+                    Some("password123")
+                )
+                .await,
+            Err(DomainError::UserAlreadyExists)
+        ));
+
+        // The current address stays in use until the new one is confirmed
+        let updated = user_service
+            .update_profile(user.id, None, new_email(), Some("password123"))
+            .await
+            .unwrap();
+        assert_eq!(updated.email, "old@example.com");
+        assert!(updated.is_email_verified());
+        wait_for_email(
+            &mailer,
+            "old@example.com",
+            "Your email address is being changed",
+        )
+        .await;
+        assert!(
+            auth_service
+                .login("new@example.com", "password123", ip)
+                .await
+                .is_err()
+        );
+        assert!(
+            auth_service
+                .login("old@example.com", "password123", ip)
+                .await
+                .is_ok()
+        );
+
+        let confirmation =
+            wait_for_email(&mailer, "new@example.com", "Confirm your new email address").await;
+        EmailVerificationService::new(&state)
+            .verify(&token_from_email(&confirmation))
+            .await
+            .unwrap();
+        let user = user_service.get_by_id(user.id).await.unwrap();
+        assert_eq!(user.email, "new@example.com");
+        assert_eq!(user.name, "New Name");
+        assert!(user.is_email_verified());
+        assert!(
+            auth_service
+                .login("new@example.com", "password123", ip)
+                .await
+                .is_ok()
+        );
+
+        // The link works once
+        assert!(matches!(
+            EmailVerificationService::new(&state)
+                .verify(&token_from_email(&confirmation))
+                .await,
+            Err(DomainError::InvalidVerificationToken)
+        ));
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_delete_account_requires_password(pool: PgPool) {
+        let (state, _mailer) = test_state(pool);
+        let auth_service = AuthService::new(&state);
+        let user_service = UserService::new(&state);
+        let tokens = auth_service
+            .register("delete@example.com", "password123", "Delete User")
+            .await
+            .unwrap()
+            .unwrap();
+        let claims = verify_token(&tokens.access_token, &state.jwt_keys).unwrap();
+        let api_key = ApiKeyService::new(&state)
+            .create(claims.sub, "ci".to_string(), Vec::new(), None)
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            user_service.delete_account(&claims, "wrong_password").await,
+            Err(DomainError::IncorrectPassword)
+        ));
+        user_service
+            .delete_account(&claims, "password123")
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            user_service.get_by_id(claims.sub).await,
+            Err(DomainError::UserNotFound)
+        ));
+        assert!(
+            SessionService::new(&state)
+                .is_revoked(&claims)
+                .await
+                .unwrap()
+        );
+        assert!(matches!(
+            ApiKeyService::new(&state)
+                .authenticate(&api_key.secret)
+                .await,
+            Err(DomainError::InvalidApiKey)
+        ));
+        assert!(
+            auth_service
+                .login(
+                    "delete@example.com",
+                    "password123",
+                    Ipv4Addr::LOCALHOST.into(),
+                )
+                .await
+                .is_err()
+        );
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_purge_deleted_after_grace_period(pool: PgPool) {
+        let config = AppConfig {
+            user_purge_mode: UserPurgeMode::Anonymize,
+            ..test_config()
+        };
+        let (state, _mailer) = test_state_with(pool, config);
+        let auth_service = AuthService::new(&state);
+        let user_service = UserService::new(&state);
+        let tokens = auth_service
+            .register("purge@example.com", "password123", "Purge User")
+            .await
+            .unwrap()
+            .unwrap();
+        let claims = verify_token(&tokens.access_token, &state.jwt_keys).unwrap();
+        user_service
+            .delete_account(&claims, "password123")
+            .await
+            .unwrap();
+
+        // The email stays taken during the grace period
+        assert_eq!(user_service.purge_deleted().await.unwrap(), 0);
+        assert!(matches!(
+            auth_service
+                .register("purge@example.com", "password123", "Someone Else")
+                .await,
+            Err(DomainError::UserAlreadyExists)
+        ));
+
+        // An export that finished assembling after the deletion goes too
+        DataExportRepository::new(&state.db_pool)
+            .create(&DataExport::new(claims.sub, ExportFormat::Json))
+            .await
+            .unwrap();
+
+        sqlx::query("UPDATE users SET deleted_at = NOW() - INTERVAL '31 days' WHERE id = $1")
+            .bind(claims.sub)
+            .execute(&state.db_pool)
+            .await
+            .unwrap();
+        assert_eq!(user_service.purge_deleted().await.unwrap(), 1);
+        assert_eq!(user_service.purge_deleted().await.unwrap(), 0);
+
+        let anonymized = UserRepository::new(&state.db_pool)
+            .find_by_id_including_inactive(claims.sub)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(anonymized.email, format!("deleted-{}@invalid", claims.sub));
+        assert_eq!(anonymized.name, "Deleted user");
+        assert!(anonymized.deleted_at.is_some());
+        assert!(
+            RoleRepository::new(&state.db_pool)
+                .find_roles_for_user(claims.sub)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        // Nothing references the user any more, whatever the table
+        let references = sqlx::query_as::<_, (String, String)>(
+            r#"
+            SELECT c.conrelid::regclass::text, a.attname
+            FROM pg_constraint c
+            JOIN pg_attribute a ON a.attrelid = c.conrelid AND a.attnum = c.conkey[1]
+            WHERE c.contype = 'f' AND c.confrelid = 'users'::regclass
+            "#,
+        )
+        .fetch_all(&state.db_pool)
+        .await
+        .unwrap();
+        assert!(!references.is_empty());
+        for (table, column) in references {
+            let count: i64 =
+                sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {table} WHERE {column} = $1"))
+                    .bind(claims.sub)
+                    .fetch_one(&state.db_pool)
+                    .await
+                    .unwrap();
+            assert_eq!(count, 0, "{table}.{column} still references the user");
+        }
+
+        // The address can be registered again
+        auth_service
+            .register("purge@example.com", "password123", "New Owner")
+            .await
+            .unwrap();
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_last_organization_owner_is_kept(pool: PgPool) {
+        let (state, _mailer) = test_state(pool);
+        let auth_service = AuthService::new(&state);
+        let user_service = UserService::new(&state);
+        let organization_service = OrganizationService::new(&state);
+        let mut claims = Vec::new();
+        for email in ["first@example.com", "second@example.com"] {
+            let tokens = auth_service
+                .register(email, "password123", "Owner")
+                .await
+                .unwrap()
+                .unwrap();
+            claims.push(verify_token(&tokens.access_token, &state.jwt_keys).unwrap());
+        }
+        let (first, second) = (&claims[0], &claims[1]);
+        let acme = organization_service
+            .create(first.sub, "Acme".into())
+            .await
+            .unwrap();
+        let actor = Actor {
+            user_id: Uuid::new_v4(),
+            ip: Ipv4Addr::LOCALHOST.into(),
+        };
+
+        // Neither the user nor an administrator can delete the only owner
+        assert!(matches!(
+            user_service.delete_account(first, "password123").await,
+            Err(DomainError::LastOrganizationOwner)
+        ));
+        assert!(matches!(
+            AdminService::new(&state)
+                .delete_user(&actor, first.sub)
+                .await,
+            Err(DomainError::LastOrganizationOwner)
+        ));
+
+        // With a second owner the first can go
+        sqlx::query(
+            "INSERT INTO organization_memberships (org_id, user_id, role) VALUES ($1, $2, 'owner')",
+        )
+        .bind(acme.organization.id)
+        .bind(second.sub)
+        .execute(&state.db_pool)
+        .await
+        .unwrap();
+        user_service
+            .delete_account(first, "password123")
+            .await
+            .unwrap();
+
+        // The deleted owner is not purged while the organization depends on them
+        let second_membership = organization_service
+            .membership(acme.organization.id, second.sub)
+            .await
+            .unwrap();
+        organization_service
+            .update_member_role(&second_membership, second.sub, OrgRole::Member)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE users SET deleted_at = NOW() - INTERVAL '31 days' WHERE id = $1")
+            .bind(first.sub)
+            .execute(&state.db_pool)
+            .await
+            .unwrap();
+        assert_eq!(user_service.purge_deleted().await.unwrap(), 0);
+
+        sqlx::query("UPDATE organization_memberships SET role = 'owner' WHERE user_id = $1")
+            .bind(second.sub)
+            .execute(&state.db_pool)
+            .await
+            .unwrap();
+        assert_eq!(user_service.purge_deleted().await.unwrap(), 1);
+        assert_eq!(
+            organization_service
+                .list_members(&second_membership)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_duplicate_emails_under_provider_rules(pool: PgPool) {
+        let (state, _mailer) = test_state(pool.clone());
+        let auth_service = AuthService::new(&state);
+        for email in [
+            "j.doe@gmail.com",
+            "jdoe+news@googlemail.com",
+            "jdoe@example.com",
+        ] {
+            auth_service
+                .register(email, "password123", "Jane")
+                .await
+                .unwrap();
+        }
+
+        // Distinct addresses until provider aliases are folded together
+        assert!(
+            UserService::new(&state)
+                .duplicate_emails()
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let config = AppConfig {
+            email_canonicalize_providers: true,
+            ..test_config()
+        };
+        let (state, _mailer) = test_state_with(pool, config);
+        let duplicates = UserService::new(&state).duplicate_emails().await.unwrap();
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].email, "jdoe@gmail.com");
+        let emails: Vec<&str> = duplicates[0]
+            .accounts
+            .iter()
+            .map(|(_, email, _)| email.as_str())
+            .collect();
+        assert_eq!(emails, vec!["j.doe@gmail.com", "jdoe+news@googlemail.com"]);
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_canonicalize_stored_emails(pool: PgPool) {
+        let (state, _mailer) = test_state(pool.clone());
+        let auth_service = AuthService::new(&state);
+        for email in [
+            "J.Doe@gmail.com",
+            "x+tag@googlemail.com",
+            "jane.doe@gmail.com",
+            "janedoe+news@gmail.com",
+            "a.b+c@example.com",
+        ] {
+            auth_service
+                .register(email, "password123", "Jane")
+                .await
+                .unwrap();
+        }
+
+        let config = AppConfig {
+            email_canonicalize_providers: true,
+            ..test_config()
+        };
+        let (state, _mailer) = test_state_with(pool, config);
+        let auth_service = AuthService::new(&state);
+        let user_service = UserService::new(&state);
+        let ip = Ipv4Addr::LOCALHOST.into();
+
+        // Stored aliases are not found under their canonical address...
+        assert!(
+            auth_service
+                .login("jdoe@gmail.com", "password123", ip)
+                .await
+                .is_err()
+        );
+
+        // ...until they are rewritten; one address can only go to one account
+        assert_eq!(
+            user_service.canonicalize_stored_emails().await.unwrap(),
+            (3, 1)
+        );
+        for email in ["j.doe@gmail.com", "X+other@gmail.com", "janedoe@gmail.com"] {
+            assert!(auth_service.login(email, "password123", ip).await.is_ok());
+        }
+        assert_eq!(
+            user_service
+                .get_by_email("x@googlemail.com")
+                .await
+                .unwrap()
+                .email,
+            "x@gmail.com"
+        );
+        let mut stored: Vec<String> = UserRepository::new(&state.db_pool)
+            .list_emails()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(_, email, _)| email)
+            .collect();
+        stored.sort();
+        assert_eq!(
+            stored,
+            vec![
+                "a.b+c@example.com",
+                "janedoe+news@gmail.com",
+                "janedoe@gmail.com",
+                "jdoe@gmail.com",
+                "x@gmail.com",
+            ]
+        );
+
+        // Nothing left to do on the next startup
+        assert_eq!(
+            user_service.canonicalize_stored_emails().await.unwrap(),
+            (0, 1)
+        );
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_import_legacy_hash_and_upgrade_on_login(pool: PgPool) {
+        let (state, _mailer) = test_state(pool);
+        let user_service = UserService::new(&state);
+        let imported = |email: &str, password_hash: &str| ImportedUser {
+            email: email.to_string(),
+            name: "Imported User".to_string(),
+            password_hash: password_hash.to_string(),
+            email_verified: true,
+        };
+
+        let bcrypt_hash = bcrypt::hash("password123", 4).unwrap();
+        let report = user_service
+            .import(vec![
+                imported("legacy@example.com", &bcrypt_hash),
+                imported("legacy@example.com", &bcrypt_hash),
+                imported("md5@example.com", "482c811da5d5b4bc6d497ffa98491e38"),
+                imported(
+                    "slow@example.com",
+                    "pbkdf2_sha256$4000000000$saltsalt$nmRtMaaws8W3ThsEvjnelfmKw8lOAXbrZLA52KMfwy4=",
+                ),
+                imported("slow-bcrypt@example.com", &bcrypt_hash.replacen("$04$", "$31$", 1)),
+                imported(
+                    "slow-argon2@example.com",
+                    "$argon2id$v=19$m=4194304,t=2,p=1$c2FsdHNhbHQ$bm1SdE1hYXdzOFczVGhzRXZqbmVsZm1LdzhsT0FYYnI",
+                ),
+            ])
+            .await
+            .unwrap();
+
+        assert_eq!(report.imported, 1);
+        assert_eq!(
+            report.skipped,
+            vec![
+                (
+                    "legacy@example.com".to_string(),
+                    ImportSkipReason::AlreadyExists
+                ),
+                (
+                    "md5@example.com".to_string(),
+                    ImportSkipReason::UnsupportedHash
+                ),
+                (
+                    "slow@example.com".to_string(),
+                    ImportSkipReason::UnsupportedHash
+                ),
+                (
+                    "slow-bcrypt@example.com".to_string(),
+                    ImportSkipReason::UnsupportedHash
+                ),
+                (
+                    "slow-argon2@example.com".to_string(),
+                    ImportSkipReason::UnsupportedHash
+                ),
+            ]
+        );
+
+        AuthService::new(&state)
+            .login(
+                "legacy@example.com",
+                "password123",
+                Ipv4Addr::LOCALHOST.into(),
+            )
+            .await
+            .unwrap();
+
+        let user = user_service
+            .get_by_email("legacy@example.com")
+            .await
+            .unwrap();
+        assert!(user.is_email_verified());
+        assert_eq!(
+            RoleRepository::new(&state.db_pool)
+                .find_roles_for_user(user.id)
+                .await
+                .unwrap(),
+            vec![DEFAULT_ROLE]
+        );
+        assert!(user.password_hash.starts_with("$argon2id$"));
+        assert!(
+            password::verify("password123", &user.password_hash)
+                .await
+                .unwrap()
+        );
+    }
 }
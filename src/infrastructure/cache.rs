@@ -0,0 +1,93 @@
+//! In-process caches
+//!
+//! These caches are per-instance and never authoritative: the database remains
+//! the source of truth and entries simply expire after their TTL.
+
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use uuid::Uuid;
+
+/// Number of entries after which expired entries are swept on insert
+const SWEEP_THRESHOLD: usize = 10_000;
+
+/// Minimal thread-safe cache with per-entry expiration
+pub struct TtlCache<K, V> {
+    entries: Mutex<HashMap<K, (V, Instant)>>,
+}
+
+impl<K: Eq + Hash, V: Clone> TtlCache<K, V> {
+    pub fn new() -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Get a value if present and not expired
+    pub fn get(&self, key: &K) -> Option<V> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        match entries.get(key) {
+            Some((value, expires_at)) if *expires_at > Instant::now() => Some(value.clone()),
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Insert a value that expires after `ttl`
+    pub fn insert(&self, key: K, value: V, ttl: Duration) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+
+        if entries.len() >= SWEEP_THRESHOLD {
+            entries.retain(|_, (_, expires_at)| *expires_at > now);
+        }
+
+        entries.insert(key, (value, now + ttl));
+    }
+
+    /// Remove a value
+    pub fn remove(&self, key: &K) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.remove(key);
+    }
+}
+
+impl<K: Eq + Hash, V: Clone> Default for TtlCache<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Cache of access token revocation state
+#[derive(Default)]
+pub struct RevocationCache {
+    /// Whether a token (by `jti`) has been revoked
+    pub tokens: TtlCache<Uuid, bool>,
+    /// Per-user cutoff (unix timestamp in milliseconds) before which tokens are rejected
+    pub user_cutoffs: TtlCache<Uuid, Option<i64>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ttl_cache_expires_entries() {
+        let cache = TtlCache::new();
+        cache.insert("live", 1, Duration::from_secs(60));
+        cache.insert("expired", 2, Duration::ZERO);
+
+        assert_eq!(cache.get(&"live"), Some(1));
+        assert_eq!(cache.get(&"expired"), None);
+
+        cache.remove(&"live");
+        assert_eq!(cache.get(&"live"), None);
+    }
+}
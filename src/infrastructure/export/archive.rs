@@ -0,0 +1,75 @@
+//! Archive formats of a data export
+
+use std::io::{Cursor, Write};
+
+use chrono::{DateTime, Utc};
+use serde_json::{Map, Value, json};
+use zip::{ZipWriter, write::SimpleFileOptions};
+
+/// One JSON document: when it was generated and a key per section
+pub fn json(generated_at: DateTime<Utc>, sections: Vec<(&str, Value)>) -> Vec<u8> {
+    let sections: Map<String, Value> = sections
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value))
+        .collect();
+    let document = json!({ "generated_at": generated_at, "sections": sections });
+
+    serde_json::to_vec_pretty(&document).expect("JSON values serialize")
+}
+
+/// A ZIP archive with `export.json` describing the export and a
+/// `<section>.json` file per section
+pub fn zip(
+    generated_at: DateTime<Utc>,
+    sections: Vec<(&str, Value)>,
+) -> Result<Vec<u8>, zip::result::ZipError> {
+    let mut archive = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default();
+
+    let names: Vec<&str> = sections.iter().map(|(name, _)| *name).collect();
+    let manifest = json!({ "generated_at": generated_at, "sections": names });
+    archive.start_file("export.json", options)?;
+    archive.write_all(&serde_json::to_vec_pretty(&manifest).expect("JSON values serialize"))?;
+
+    for (name, value) in sections {
+        archive.start_file(format!("{name}.json"), options)?;
+        archive.write_all(&serde_json::to_vec_pretty(&value).expect("JSON values serialize"))?;
+    }
+
+    Ok(archive.finish()?.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+
+    #[test]
+    fn test_zip_has_a_file_per_section() {
+        let bytes = zip(
+            Utc::now(),
+            vec![
+                ("profile", json!({ "name": "Jane" })),
+                ("roles", json!(["user"])),
+            ],
+        )
+        .unwrap();
+
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+        let mut names: Vec<&str> = archive.file_names().collect();
+        names.sort_unstable();
+        assert_eq!(names, vec!["export.json", "profile.json", "roles.json"]);
+
+        let mut profile = String::new();
+        archive
+            .by_name("profile.json")
+            .unwrap()
+            .read_to_string(&mut profile)
+            .unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&profile).unwrap(),
+            json!({ "name": "Jane" })
+        );
+    }
+}
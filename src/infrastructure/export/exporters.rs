@@ -0,0 +1,196 @@
+//! Exporters for the tables of this crate
+
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::DataExporter;
+use crate::infrastructure::repositories::{
+    ApiKeyRepository, AuditLogRepository, MfaRepository, OrganizationRepository,
+    RefreshTokenRepository, RoleRepository, UserRepository,
+};
+
+/// The account itself
+pub struct ProfileExporter;
+
+#[async_trait::async_trait]
+impl DataExporter for ProfileExporter {
+    fn section(&self) -> &'static str {
+        "profile"
+    }
+
+    async fn export(&self, pool: &PgPool, user_id: Uuid) -> Result<Value, sqlx::Error> {
+        let Some(user) = UserRepository::new(pool)
+            .find_by_id_including_inactive(user_id)
+            .await?
+        else {
+            return Ok(Value::Null);
+        };
+
+        Ok(json!({
+            "id": user.id,
+            "email": user.email,
+            "name": user.name,
+            "is_active": user.is_active,
+            "email_verified_at": user.email_verified_at,
+            "password_changed_at": user.password_changed_at,
+            "deleted_at": user.deleted_at,
+            "created_at": user.created_at,
+            "updated_at": user.updated_at,
+        }))
+    }
+}
+
+/// Roles granted to the user
+pub struct RolesExporter;
+
+#[async_trait::async_trait]
+impl DataExporter for RolesExporter {
+    fn section(&self) -> &'static str {
+        "roles"
+    }
+
+    async fn export(&self, pool: &PgPool, user_id: Uuid) -> Result<Value, sqlx::Error> {
+        let roles = RoleRepository::new(pool)
+            .find_roles_for_user(user_id)
+            .await?;
+
+        Ok(json!(roles))
+    }
+}
+
+/// Organizations the user belongs to
+pub struct OrganizationsExporter;
+
+#[async_trait::async_trait]
+impl DataExporter for OrganizationsExporter {
+    fn section(&self) -> &'static str {
+        "organizations"
+    }
+
+    async fn export(&self, pool: &PgPool, user_id: Uuid) -> Result<Value, sqlx::Error> {
+        let organizations = OrganizationRepository::new(pool)
+            .list_for_user(user_id)
+            .await?;
+
+        Ok(organizations
+            .into_iter()
+            .map(|membership| {
+                json!({
+                    "id": membership.organization.id,
+                    "name": membership.organization.name,
+                    "role": membership.role,
+                })
+            })
+            .collect())
+    }
+}
+
+/// Logins, as recorded by their refresh tokens
+pub struct SessionsExporter;
+
+#[async_trait::async_trait]
+impl DataExporter for SessionsExporter {
+    fn section(&self) -> &'static str {
+        "sessions"
+    }
+
+    async fn export(&self, pool: &PgPool, user_id: Uuid) -> Result<Value, sqlx::Error> {
+        let tokens = RefreshTokenRepository::new(pool)
+            .list_for_user(user_id)
+            .await?;
+
+        Ok(tokens
+            .into_iter()
+            .map(|token| {
+                json!({
+                    "id": token.id,
+                    "session_id": token.family_id,
+                    "organization_id": token.org_id,
+                    "created_at": token.created_at,
+                    "expires_at": token.expires_at,
+                    "revoked_at": token.revoked_at,
+                })
+            })
+            .collect())
+    }
+}
+
+/// API keys, without their hashes
+pub struct ApiKeysExporter;
+
+#[async_trait::async_trait]
+impl DataExporter for ApiKeysExporter {
+    fn section(&self) -> &'static str {
+        "api_keys"
+    }
+
+    async fn export(&self, pool: &PgPool, user_id: Uuid) -> Result<Value, sqlx::Error> {
+        let keys = ApiKeyRepository::new(pool)
+            .list_all_for_user(user_id)
+            .await?;
+
+        Ok(keys
+            .into_iter()
+            .map(|key| {
+                json!({
+                    "id": key.id,
+                    "name": key.name,
+                    "prefix": key.prefix,
+                    "scopes": key.scopes,
+                    "last_used_at": key.last_used_at,
+                    "expires_at": key.expires_at,
+                    "revoked_at": key.revoked_at,
+                    "created_at": key.created_at,
+                })
+            })
+            .collect())
+    }
+}
+
+/// Two-factor authentication status, without the secret
+pub struct MfaExporter;
+
+#[async_trait::async_trait]
+impl DataExporter for MfaExporter {
+    fn section(&self) -> &'static str {
+        "mfa"
+    }
+
+    async fn export(&self, pool: &PgPool, user_id: Uuid) -> Result<Value, sqlx::Error> {
+        let mfa = MfaRepository::new(pool).find_by_user_id(user_id).await?;
+
+        Ok(json!({
+            "enrolled": mfa.is_some(),
+            "enabled_at": mfa.and_then(|mfa| mfa.enabled_at),
+        }))
+    }
+}
+
+/// Audit log entries where the user acted or was acted on
+pub struct AuditLogExporter;
+
+#[async_trait::async_trait]
+impl DataExporter for AuditLogExporter {
+    fn section(&self) -> &'static str {
+        "audit_log"
+    }
+
+    async fn export(&self, pool: &PgPool, user_id: Uuid) -> Result<Value, sqlx::Error> {
+        let entries = AuditLogRepository::new(pool).list_for_user(user_id).await?;
+
+        Ok(entries
+            .into_iter()
+            .map(|entry| {
+                json!({
+                    "action": entry.action,
+                    "actor_id": entry.actor_id,
+                    "target_user_id": entry.target_user_id,
+                    "details": entry.details,
+                    "ip_address": entry.ip_address,
+                    "created_at": entry.created_at,
+                })
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,43 @@
+//! Personal data export
+//!
+//! Each part of the system that stores data about users contributes a section
+//! to a user's export through the [`DataExporter`] trait. The built-in
+//! exporters cover the tables of this crate; new tables register their own
+//! exporter via [`AppState::with_exporter`](crate::config::AppState::with_exporter).
+
+pub mod archive;
+mod exporters;
+
+use std::sync::Arc;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+pub use exporters::{
+    ApiKeysExporter, AuditLogExporter, MfaExporter, OrganizationsExporter, ProfileExporter,
+    RolesExporter, SessionsExporter,
+};
+
+/// Contribution of one part of the system to a user's data export
+#[async_trait::async_trait]
+pub trait DataExporter: Send + Sync {
+    /// Name of the section, e.g. `profile`; also the file name in ZIP archives
+    fn section(&self) -> &'static str;
+
+    /// Everything this part of the system stores about the user. Secrets such
+    /// as password and token hashes are left out.
+    async fn export(&self, pool: &PgPool, user_id: Uuid) -> Result<serde_json::Value, sqlx::Error>;
+}
+
+/// Exporters for the tables of this crate
+pub fn built_in_exporters() -> Vec<Arc<dyn DataExporter>> {
+    vec![
+        Arc::new(ProfileExporter),
+        Arc::new(RolesExporter),
+        Arc::new(OrganizationsExporter),
+        Arc::new(SessionsExporter),
+        Arc::new(ApiKeysExporter),
+        Arc::new(MfaExporter),
+        Arc::new(AuditLogExporter),
+    ]
+}
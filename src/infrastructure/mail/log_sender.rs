@@ -0,0 +1,37 @@
+//! Mail sender that writes messages to the log (development default)
+
+use super::{Email, MailError, MailSender};
+
+pub struct LogMailSender {
+    /// Log message bodies. They carry bearer tokens (reset and verification
+    /// links), so this is for development only.
+    include_body: bool,
+}
+
+impl LogMailSender {
+    pub fn new(include_body: bool) -> Self {
+        Self { include_body }
+    }
+}
+
+#[async_trait::async_trait]
+impl MailSender for LogMailSender {
+    async fn send(&self, email: Email) -> Result<(), MailError> {
+        if self.include_body {
+            tracing::info!(
+                to = %email.to,
+                subject = %email.subject,
+                "Email not delivered (log mail sender):\n{}",
+                email.body
+            );
+        } else {
+            tracing::warn!(
+                to = %email.to,
+                subject = %email.subject,
+                "Email not delivered (log mail sender)"
+            );
+        }
+
+        Ok(())
+    }
+}
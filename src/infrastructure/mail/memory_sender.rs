@@ -0,0 +1,44 @@
+//! Mail sender that keeps messages in memory, for tests
+
+use std::sync::Mutex;
+
+use super::{Email, MailError, MailSender};
+
+#[derive(Default)]
+pub struct InMemoryMailSender {
+    messages: Mutex<Vec<Email>>,
+}
+
+impl InMemoryMailSender {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// All messages sent so far
+    pub fn messages(&self) -> Vec<Email> {
+        self.messages
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Messages sent to a given address
+    pub fn messages_to(&self, to: &str) -> Vec<Email> {
+        self.messages()
+            .into_iter()
+            .filter(|email| email.to == to)
+            .collect()
+    }
+}
+
+#[async_trait::async_trait]
+impl MailSender for InMemoryMailSender {
+    async fn send(&self, email: Email) -> Result<(), MailError> {
+        self.messages
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(email);
+
+        Ok(())
+    }
+}
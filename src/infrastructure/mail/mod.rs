@@ -0,0 +1,62 @@
+//! Outgoing mail
+//!
+//! Services send mail through the [`MailSender`] trait so the transport can be
+//! swapped without touching business logic. `MAIL_TRANSPORT` selects an SMTP
+//! relay, or for development a sender that only logs messages.
+
+mod log_sender;
+#[cfg(test)]
+mod memory_sender;
+mod smtp_sender;
+pub mod templates;
+
+use std::sync::Arc;
+
+pub use log_sender::LogMailSender;
+#[cfg(test)]
+pub use memory_sender::InMemoryMailSender;
+pub use smtp_sender::{SmtpMailSender, SmtpSettings};
+
+/// Mail transport selected by configuration
+#[derive(Debug, Clone)]
+pub enum MailTransport {
+    /// Log messages instead of delivering them
+    Log,
+    Smtp(SmtpSettings),
+}
+
+/// An outgoing email message
+#[derive(Debug, Clone)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Mail transport
+#[async_trait::async_trait]
+pub trait MailSender: Send + Sync {
+    /// Deliver a single message
+    async fn send(&self, email: Email) -> Result<(), MailError>;
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MailError {
+    #[error("Failed to deliver email: {0}")]
+    DeliveryFailed(String),
+    #[error("Invalid mail configuration: {0}")]
+    InvalidConfiguration(String),
+}
+
+/// Deliver a message in the background.
+///
+/// Callers do not wait for delivery, so response times do not reveal whether a
+/// message was sent. Failures are logged.
+pub fn send_in_background(mailer: Arc<dyn MailSender>, email: Email) {
+    tokio::spawn(async move {
+        let to = email.to.clone();
+        if let Err(err) = mailer.send(email).await {
+            tracing::error!(to = %to, "Failed to send email: {}", err);
+        }
+    });
+}
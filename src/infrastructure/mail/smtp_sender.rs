@@ -0,0 +1,177 @@
+//! Mail sender delivering messages through an SMTP relay
+
+use lettre::{
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+    message::{Mailbox, header::ContentType},
+    transport::smtp::authentication::Credentials,
+};
+
+use super::{Email, MailError, MailSender};
+
+/// How the connection to the relay is secured
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmtpSecurity {
+    /// Upgrade a plain connection with STARTTLS (usually port 587)
+    StartTls,
+    /// TLS from the start (usually port 465)
+    Tls,
+    /// No encryption, for local relays only
+    None,
+}
+
+impl std::str::FromStr for SmtpSecurity {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "starttls" => Ok(Self::StartTls),
+            "tls" => Ok(Self::Tls),
+            "none" => Ok(Self::None),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Connection settings of an SMTP relay
+#[derive(Clone)]
+pub struct SmtpSettings {
+    pub host: String,
+    pub port: u16,
+    pub security: SmtpSecurity,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Sender address, e.g. `Axum API <no-reply@example.com>`
+    pub from: String,
+}
+
+impl std::fmt::Debug for SmtpSettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SmtpSettings")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("security", &self.security)
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "<redacted>"))
+            .field("from", &self.from)
+            .finish()
+    }
+}
+
+pub struct SmtpMailSender {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailSender {
+    pub fn new(settings: &SmtpSettings) -> Result<Self, MailError> {
+        let from = settings
+            .from
+            .parse()
+            .map_err(|err| MailError::InvalidConfiguration(format!("sender address: {err}")))?;
+
+        let builder = match settings.security {
+            SmtpSecurity::StartTls => {
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&settings.host)
+            }
+            SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&settings.host),
+            SmtpSecurity::None => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(
+                &settings.host,
+            )),
+        }
+        .map_err(|err| MailError::InvalidConfiguration(err.to_string()))?
+        .port(settings.port);
+
+        let builder = match (&settings.username, &settings.password) {
+            (Some(username), Some(password)) => {
+                builder.credentials(Credentials::new(username.clone(), password.clone()))
+            }
+            _ => builder,
+        };
+
+        Ok(Self {
+            transport: builder.build(),
+            from,
+        })
+    }
+
+    fn message(&self, email: Email) -> Result<Message, MailError> {
+        let to: Mailbox = email
+            .to
+            .parse()
+            .map_err(|err| MailError::DeliveryFailed(format!("recipient address: {err}")))?;
+
+        Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(email.subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(email.body)
+            .map_err(|err| MailError::DeliveryFailed(err.to_string()))
+    }
+}
+
+#[async_trait::async_trait]
+impl MailSender for SmtpMailSender {
+    async fn send(&self, email: Email) -> Result<(), MailError> {
+        let message = self.message(email)?;
+
+        self.transport
+            .send(message)
+            .await
+            .map_err(|err| MailError::DeliveryFailed(err.to_string()))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(from: &str) -> SmtpSettings {
+        SmtpSettings {
+            host: "smtp.example.com".to_string(),
+            port: 587,
+            security: SmtpSecurity::StartTls,
+            username: Some("mailer".to_string()),
+            password: Some("secret".to_string()),
+            from: from.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_builds_plain_text_messages() {
+        let sender = SmtpMailSender::new(&settings("Axum API <no-reply@example.com>")).unwrap();
+        let message = sender
+            .message(Email {
+                to: "jane@example.com".to_string(),
+                subject: "Reset your password".to_string(),
+                body: "https://example.com/reset-password?token=abc".to_string(),
+            })
+            .unwrap();
+
+        let raw = String::from_utf8(message.formatted()).unwrap();
+        assert!(raw.contains("From: \"Axum API\" <no-reply@example.com>"));
+        assert!(raw.contains("To: jane@example.com"));
+        assert!(raw.contains("Subject: Reset your password"));
+        assert!(raw.contains("Content-Type: text/plain"));
+
+        assert!(matches!(
+            sender.message(Email {
+                to: "not an address".to_string(),
+                subject: String::new(),
+                body: String::new(),
+            }),
+            Err(MailError::DeliveryFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_rejects_invalid_sender_and_hides_password() {
+        assert!(matches!(
+            SmtpMailSender::new(&settings("no-reply")),
+            Err(MailError::InvalidConfiguration(_))
+        ));
+        assert!(!format!("{:?}", settings("no-reply@example.com")).contains("secret"));
+    }
+}
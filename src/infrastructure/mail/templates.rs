@@ -0,0 +1,119 @@
+//! Email message templates
+
+use super::Email;
+
+/// Password reset instructions
+pub fn password_reset(to: &str, reset_link: &str, expiration_minutes: i64) -> Email {
+    Email {
+        to: to.to_string(),
+        subject: "Reset your password".to_string(),
+        body: format!(
+            "We received a request to reset the password for your account.\n\n\
+             Use the link below to choose a new password. The link expires in \
+             {expiration_minutes} minutes and can only be used once.\n\n\
+             {reset_link}\n\n\
+             If you did not request a password reset, you can ignore this email."
+        ),
+    }
+}
+
+/// Email address verification link
+pub fn email_verification(to: &str, verification_link: &str, expiration_hours: i64) -> Email {
+    Email {
+        to: to.to_string(),
+        subject: "Verify your email address".to_string(),
+        body: format!(
+            "Please confirm that this is your email address by opening the link below. \
+             The link expires in {expiration_hours} hours.\n\n\
+             {verification_link}\n\n\
+             If you did not create an account, you can ignore this email."
+        ),
+    }
+}
+
+/// Link confirming a new email address for an existing account
+pub fn email_change_verification(
+    to: &str,
+    verification_link: &str,
+    expiration_hours: i64,
+) -> Email {
+    Email {
+        to: to.to_string(),
+        subject: "Confirm your new email address".to_string(),
+        body: format!(
+            "Please confirm that you want to use this email address for your account \
+             by opening the link below. The link expires in {expiration_hours} hours.\n\n\
+             {verification_link}\n\n\
+             Until then, your account keeps its current address. If you did not \
+             request this change, you can ignore this email."
+        ),
+    }
+}
+
+/// Notice to the current address that a change to another one was requested
+pub fn email_change_requested(to: &str, new_email: &str, reset_link: &str) -> Email {
+    Email {
+        to: to.to_string(),
+        subject: "Your email address is being changed".to_string(),
+        body: format!(
+            "Someone asked to change the email address of your account to \
+             {new_email}. The change takes effect once the new address is confirmed.\n\n\
+             If this was not you, reset your password right away:\n\n\
+             {reset_link}"
+        ),
+    }
+}
+
+/// Notice that an account was locked after repeated failed logins
+pub fn account_locked(to: &str, reset_link: &str, lockout_minutes: i64) -> Email {
+    Email {
+        to: to.to_string(),
+        subject: "Your account has been locked".to_string(),
+        body: format!(
+            "We locked your account for {lockout_minutes} minutes after several failed \
+             login attempts.\n\n\
+             If this was you, wait for the lock to expire or reset your password to \
+             unlock it right away:\n\n\
+             {reset_link}\n\n\
+             If this was not you, someone may be trying to guess your password. \
+             Resetting it is recommended."
+        ),
+    }
+}
+
+/// Notice that someone tried to register with an email that already has an account
+pub fn account_exists(to: &str, login_link: &str, reset_link: &str) -> Email {
+    Email {
+        to: to.to_string(),
+        subject: "You already have an account".to_string(),
+        body: format!(
+            "Someone tried to create an account with this email address, but you \
+             already have one.\n\n\
+             Log in here:\n\n\
+             {login_link}\n\n\
+             Forgot your password? Reset it here:\n\n\
+             {reset_link}\n\n\
+             If this was not you, you can ignore this email."
+        ),
+    }
+}
+
+/// Invitation to join an organization
+pub fn organization_invitation(
+    to: &str,
+    organization: &str,
+    invitation_link: &str,
+    expiration_hours: i64,
+) -> Email {
+    Email {
+        to: to.to_string(),
+        subject: format!("You have been invited to join {organization}"),
+        body: format!(
+            "You have been invited to join {organization}.\n\n\
+             Log in or create an account with this email address, then open the link \
+             below to accept. The link expires in {expiration_hours} hours.\n\n\
+             {invitation_link}\n\n\
+             If you were not expecting this invitation, you can ignore this email."
+        ),
+    }
+}
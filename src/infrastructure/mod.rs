@@ -3,6 +3,12 @@
 //! This module contains:
 //! - Database connection management
 //! - Repository implementations
+//! - In-process caches
+//! - Outgoing mail
+//! - Personal data export
 //! - External API clients
 
+pub mod cache;
+pub mod export;
+pub mod mail;
 pub mod repositories;
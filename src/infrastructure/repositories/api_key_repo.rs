@@ -0,0 +1,139 @@
+//! API key repository - Data access for personal API keys
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::domain::models::ApiKey;
+
+pub struct ApiKeyRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> ApiKeyRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Create a new API key
+    pub async fn create(&self, key: &ApiKey) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO api_keys (id, user_id, name, prefix, key_hash, scopes, expires_at, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            "#,
+        )
+        .bind(key.id)
+        .bind(key.user_id)
+        .bind(&key.name)
+        .bind(&key.prefix)
+        .bind(&key.key_hash)
+        .bind(&key.scopes)
+        .bind(key.expires_at)
+        .bind(key.created_at)
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Find a key by its hash, only if it belongs to an active user
+    pub async fn find_by_hash(&self, key_hash: &str) -> Result<Option<ApiKey>, sqlx::Error> {
+        sqlx::query_as::<_, ApiKey>(
+            r#"
+            SELECT k.id, k.user_id, k.name, k.prefix, k.key_hash, k.scopes, k.last_used_at,
+                   k.expires_at, k.revoked_at, k.created_at
+            FROM api_keys k
+            JOIN users u ON u.id = k.user_id
+            WHERE k.key_hash = $1 AND u.is_active = true
+            "#,
+        )
+        .bind(key_hash)
+        .fetch_optional(self.pool)
+        .await
+    }
+
+    /// List a user's keys that have not been revoked, newest first
+    pub async fn list_for_user(&self, user_id: Uuid) -> Result<Vec<ApiKey>, sqlx::Error> {
+        sqlx::query_as::<_, ApiKey>(
+            r#"
+            SELECT id, user_id, name, prefix, key_hash, scopes, last_used_at,
+                   expires_at, revoked_at, created_at
+            FROM api_keys
+            WHERE user_id = $1 AND revoked_at IS NULL
+            ORDER BY created_at DESC
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(self.pool)
+        .await
+    }
+
+    /// List all of a user's keys, revoked ones included, oldest first
+    pub async fn list_all_for_user(&self, user_id: Uuid) -> Result<Vec<ApiKey>, sqlx::Error> {
+        sqlx::query_as::<_, ApiKey>(
+            r#"
+            SELECT id, user_id, name, prefix, key_hash, scopes, last_used_at,
+                   expires_at, revoked_at, created_at
+            FROM api_keys
+            WHERE user_id = $1
+            ORDER BY created_at
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(self.pool)
+        .await
+    }
+
+    /// Record that a key was used.
+    ///
+    /// Writes at most once a minute per key, so busy clients don't turn every
+    /// request into an UPDATE.
+    pub async fn touch(&self, id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE api_keys
+            SET last_used_at = NOW()
+            WHERE id = $1
+              AND (last_used_at IS NULL OR last_used_at < NOW() - INTERVAL '1 minute')
+            "#,
+        )
+        .bind(id)
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Revoke one of a user's keys. Returns `false` if no such active key exists.
+    pub async fn revoke(&self, user_id: Uuid, id: Uuid) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE api_keys
+            SET revoked_at = NOW()
+            WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL
+            "#,
+        )
+        .bind(id)
+        .bind(user_id)
+        .execute(self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Revoke every active key of a user
+    pub async fn revoke_all_for_user(&self, user_id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE api_keys
+            SET revoked_at = NOW()
+            WHERE user_id = $1 AND revoked_at IS NULL
+            "#,
+        )
+        .bind(user_id)
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+//! Audit log repository - Data access for the audit trail
+
+use crate::domain::models::AuditLog;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+pub struct AuditLogRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> AuditLogRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Append an entry to the audit log
+    pub async fn create(&self, entry: &AuditLog) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO audit_logs (id, actor_id, action, target_user_id, details, ip_address, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+        )
+        .bind(entry.id)
+        .bind(entry.actor_id)
+        .bind(&entry.action)
+        .bind(entry.target_user_id)
+        .bind(&entry.details)
+        .bind(&entry.ip_address)
+        .bind(entry.created_at)
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Entries where the user acted or was acted on, oldest first
+    pub async fn list_for_user(&self, user_id: Uuid) -> Result<Vec<AuditLog>, sqlx::Error> {
+        sqlx::query_as::<_, AuditLog>(
+            r#"
+            SELECT id, actor_id, action, target_user_id, details, ip_address, created_at
+            FROM audit_logs
+            WHERE actor_id = $1 OR target_user_id = $1
+            ORDER BY created_at
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(self.pool)
+        .await
+    }
+}
@@ -0,0 +1,188 @@
+//! Data export repository - Data access for personal data exports
+
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+use crate::domain::models::DataExport;
+
+pub struct DataExportRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> DataExportRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Create a new export
+    pub async fn create(&self, export: &DataExport) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO data_exports (id, user_id, format, status, created_at)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+        )
+        .bind(export.id)
+        .bind(export.user_id)
+        .bind(export.format)
+        .bind(export.status)
+        .bind(export.created_at)
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Find one of a user's exports that has not expired
+    pub async fn find_for_user(
+        &self,
+        user_id: Uuid,
+        id: Uuid,
+    ) -> Result<Option<DataExport>, sqlx::Error> {
+        sqlx::query_as::<_, DataExport>(
+            r#"
+            SELECT id, user_id, format, status, created_at, completed_at, expires_at
+            FROM data_exports
+            WHERE id = $1 AND user_id = $2 AND (expires_at IS NULL OR expires_at > NOW())
+            "#,
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(self.pool)
+        .await
+    }
+
+    /// Find the export of a user that is still being assembled, if any
+    pub async fn find_pending_for_user(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Option<DataExport>, sqlx::Error> {
+        sqlx::query_as::<_, DataExport>(
+            r#"
+            SELECT id, user_id, format, status, created_at, completed_at, expires_at
+            FROM data_exports
+            WHERE user_id = $1 AND status = 'pending'
+            ORDER BY created_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(self.pool)
+        .await
+    }
+
+    /// Store the finished archive, downloadable until `expires_at`
+    pub async fn complete(
+        &self,
+        id: Uuid,
+        archive: &[u8],
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE data_exports
+            SET status = 'ready', archive = $2, completed_at = NOW(), expires_at = $3
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(archive)
+        .bind(expires_at)
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Mark an export as failed; the record is kept until `expires_at` so
+    /// its status can still be read
+    pub async fn fail(&self, id: Uuid, expires_at: DateTime<Utc>) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE data_exports
+            SET status = 'failed', completed_at = NOW(), expires_at = $2
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(expires_at)
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Load a ready, unexpired export together with its archive
+    pub async fn find_archive(
+        &self,
+        id: Uuid,
+    ) -> Result<Option<(DataExport, Vec<u8>)>, sqlx::Error> {
+        let row = sqlx::query_as::<_, ExportArchive>(
+            r#"
+            SELECT id, user_id, format, status, created_at, completed_at, expires_at, archive
+            FROM data_exports
+            WHERE id = $1 AND status = 'ready' AND expires_at > NOW()
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(self.pool)
+        .await?;
+
+        Ok(row.map(|row| (row.export, row.archive)))
+    }
+
+    /// Fail exports still pending since before `stale_before`, e.g. because a
+    /// restart interrupted them
+    pub async fn fail_stale(
+        &self,
+        stale_before: DateTime<Utc>,
+        expires_at: DateTime<Utc>,
+    ) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE data_exports
+            SET status = 'failed', completed_at = NOW(), expires_at = $2
+            WHERE status = 'pending' AND created_at < $1
+            "#,
+        )
+        .bind(stale_before)
+        .bind(expires_at)
+        .execute(self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Delete all of a user's exports and their archives, so their download
+    /// links stop working
+    pub async fn delete_for_user(&self, user_id: Uuid) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM data_exports WHERE user_id = $1")
+            .bind(user_id)
+            .execute(self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Delete expired exports and their archives. Returns how many were deleted.
+    pub async fn delete_expired(&self) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM data_exports
+            WHERE expires_at <= NOW()
+            "#,
+        )
+        .execute(self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+#[derive(FromRow)]
+struct ExportArchive {
+    #[sqlx(flatten)]
+    export: DataExport,
+    archive: Vec<u8>,
+}
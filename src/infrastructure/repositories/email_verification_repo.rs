@@ -0,0 +1,87 @@
+//! Email verification repository - Data access for email verification tokens
+
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::domain::models::EmailVerificationToken;
+
+pub struct EmailVerificationRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> EmailVerificationRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Create a new email verification token
+    pub async fn create(&self, token: &EmailVerificationToken) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO email_verification_tokens (id, user_id, email, token_hash, expires_at, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+        )
+        .bind(token.id)
+        .bind(token.user_id)
+        .bind(&token.email)
+        .bind(&token.token_hash)
+        .bind(token.expires_at)
+        .bind(token.created_at)
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Mark a valid (unused, unexpired) token as used and return its user ID and email.
+    ///
+    /// Returns `None` if the token does not exist, has expired or was already used.
+    pub async fn consume(&self, token_hash: &str) -> Result<Option<(Uuid, String)>, sqlx::Error> {
+        sqlx::query_as::<_, (Uuid, String)>(
+            r#"
+            UPDATE email_verification_tokens
+            SET used_at = NOW()
+            WHERE token_hash = $1 AND used_at IS NULL AND expires_at > NOW()
+            RETURNING user_id, email
+            "#,
+        )
+        .bind(token_hash)
+        .fetch_optional(self.pool)
+        .await
+    }
+
+    /// Invalidate all outstanding tokens of a user
+    pub async fn invalidate_for_user(&self, user_id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE email_verification_tokens
+            SET used_at = NOW()
+            WHERE user_id = $1 AND used_at IS NULL
+            "#,
+        )
+        .bind(user_id)
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// When the most recent token for a user was created
+    pub async fn find_latest_created_at(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+        sqlx::query_scalar::<_, Option<DateTime<Utc>>>(
+            r#"
+            SELECT MAX(created_at)
+            FROM email_verification_tokens
+            WHERE user_id = $1
+            "#,
+        )
+        .bind(user_id)
+        .fetch_one(self.pool)
+        .await
+    }
+}
@@ -0,0 +1,125 @@
+//! Invitation repository - Tenant-scoped data access for organization invitations
+
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use super::tenant::{self, TenantIsolation};
+use crate::domain::models::{Invitation, Tenant};
+
+/// Invitations to one organization. Every query is limited to the tenant the
+/// repository was created for; accepting an invitation goes through
+/// [`OrganizationRepository`](super::OrganizationRepository), as the invitee
+/// is not a member yet.
+pub struct InvitationRepository<'a> {
+    pool: &'a PgPool,
+    tenant: Tenant,
+    isolation: TenantIsolation,
+}
+
+#[allow(dead_code)]
+impl<'a> InvitationRepository<'a> {
+    pub fn new(pool: &'a PgPool, tenant: Tenant, isolation: TenantIsolation) -> Self {
+        Self {
+            pool,
+            tenant,
+            isolation,
+        }
+    }
+
+    /// Create an invitation, replacing any pending one for the same email
+    pub async fn create(&self, invitation: &Invitation) -> Result<(), sqlx::Error> {
+        let mut tx = self.begin().await?;
+
+        sqlx::query(
+            r#"
+            DELETE FROM organization_invitations
+            WHERE org_id = $1 AND lower(email) = lower($2) AND accepted_at IS NULL
+            "#,
+        )
+        .bind(self.tenant.org_id())
+        .bind(&invitation.email)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO organization_invitations
+                (id, org_id, email, role, token_hash, invited_by, expires_at, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            "#,
+        )
+        .bind(invitation.id)
+        .bind(self.tenant.org_id())
+        .bind(&invitation.email)
+        .bind(invitation.role)
+        .bind(&invitation.token_hash)
+        .bind(invitation.invited_by)
+        .bind(invitation.expires_at)
+        .bind(invitation.created_at)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await
+    }
+
+    /// List the invitations that can still be accepted, newest first
+    pub async fn list_pending(&self) -> Result<Vec<Invitation>, sqlx::Error> {
+        let mut tx = self.begin().await?;
+        let result = sqlx::query_as::<_, Invitation>(
+            r#"
+            SELECT id, org_id, email, role, token_hash, invited_by, expires_at, created_at
+            FROM organization_invitations
+            WHERE org_id = $1 AND accepted_at IS NULL AND expires_at > NOW()
+            ORDER BY created_at DESC, id DESC
+            "#,
+        )
+        .bind(self.tenant.org_id())
+        .fetch_all(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(result)
+    }
+
+    /// Find an invitation that has not been accepted by ID
+    pub async fn find(&self, id: Uuid) -> Result<Option<Invitation>, sqlx::Error> {
+        let mut tx = self.begin().await?;
+        let result = sqlx::query_as::<_, Invitation>(
+            r#"
+            SELECT id, org_id, email, role, token_hash, invited_by, expires_at, created_at
+            FROM organization_invitations
+            WHERE org_id = $1 AND id = $2 AND accepted_at IS NULL
+            "#,
+        )
+        .bind(self.tenant.org_id())
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(result)
+    }
+
+    /// Withdraw an invitation that has not been accepted. Returns `false` if
+    /// there was none.
+    pub async fn revoke(&self, id: Uuid) -> Result<bool, sqlx::Error> {
+        let mut tx = self.begin().await?;
+        let result = sqlx::query(
+            r#"
+            DELETE FROM organization_invitations
+            WHERE org_id = $1 AND id = $2 AND accepted_at IS NULL
+            "#,
+        )
+        .bind(self.tenant.org_id())
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn begin(&self) -> Result<Transaction<'a, Postgres>, sqlx::Error> {
+        tenant::begin(self.pool, self.tenant, self.isolation).await
+    }
+}
@@ -0,0 +1,95 @@
+//! Lockout repository - Data access for failed login tracking
+
+use sqlx::PgPool;
+
+use crate::domain::models::AccountLockout;
+
+/// Failed logins, keyed by email address ignoring case. Addresses need not
+/// belong to an account.
+pub struct LockoutRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> LockoutRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Get the failed login state of an email address
+    pub async fn find(&self, email: &str) -> Result<Option<AccountLockout>, sqlx::Error> {
+        sqlx::query_as::<_, AccountLockout>(
+            r#"
+            SELECT failed_attempts, last_failed_at, locked_until
+            FROM account_lockouts
+            WHERE email = lower($1)
+            "#,
+        )
+        .bind(email)
+        .fetch_optional(self.pool)
+        .await
+    }
+
+    /// Count a failed login, locking the address for `lockout_secs` once
+    /// `max_attempts` is reached. The counter restarts when the lock is set.
+    pub async fn record_failure(
+        &self,
+        email: &str,
+        max_attempts: i32,
+        lockout_secs: i64,
+    ) -> Result<AccountLockout, sqlx::Error> {
+        sqlx::query_as::<_, AccountLockout>(
+            r#"
+            INSERT INTO account_lockouts AS l (email, failed_attempts, last_failed_at, locked_until)
+            VALUES (
+                lower($1),
+                CASE WHEN 1 >= $2 THEN 0 ELSE 1 END,
+                NOW(),
+                CASE WHEN 1 >= $2 THEN NOW() + make_interval(secs => $3) END
+            )
+            ON CONFLICT (email) DO UPDATE SET
+                failed_attempts = CASE
+                    WHEN l.failed_attempts + 1 >= $2 THEN 0
+                    ELSE l.failed_attempts + 1
+                END,
+                last_failed_at = NOW(),
+                locked_until = CASE
+                    WHEN l.failed_attempts + 1 >= $2 THEN NOW() + make_interval(secs => $3)
+                    ELSE l.locked_until
+                END
+            RETURNING failed_attempts, last_failed_at, locked_until
+            "#,
+        )
+        .bind(email)
+        .bind(max_attempts)
+        .bind(lockout_secs as f64)
+        .fetch_one(self.pool)
+        .await
+    }
+
+    /// Forget failed logins and lift any lock. Returns `false` if there was nothing to clear.
+    pub async fn clear(&self, email: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM account_lockouts WHERE email = lower($1)")
+            .bind(email)
+            .execute(self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Delete entries that are not locked and whose last failure is older
+    /// than `max_age_secs`
+    pub async fn delete_stale(&self, max_age_secs: i64) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM account_lockouts
+            WHERE last_failed_at < NOW() - make_interval(secs => $1)
+              AND (locked_until IS NULL OR locked_until < NOW())
+            "#,
+        )
+        .bind(max_age_secs as f64)
+        .execute(self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,188 @@
+//! Member repository - Tenant-scoped data access for organization memberships
+
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use super::tenant::{self, TenantIsolation};
+use crate::domain::models::{Member, OrgRole, Tenant};
+
+/// Result of changing or removing a membership
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemberChange {
+    Applied,
+    /// The user is not a member of the tenant
+    NotFound,
+    /// Nothing was changed, as the organization would be left without an owner
+    LastOwner,
+}
+
+/// Memberships of one organization. Every query is limited to the tenant the
+/// repository was created for.
+pub struct MemberRepository<'a> {
+    pool: &'a PgPool,
+    tenant: Tenant,
+    isolation: TenantIsolation,
+}
+
+#[allow(dead_code)]
+impl<'a> MemberRepository<'a> {
+    pub fn new(pool: &'a PgPool, tenant: Tenant, isolation: TenantIsolation) -> Self {
+        Self {
+            pool,
+            tenant,
+            isolation,
+        }
+    }
+
+    /// List the members, oldest first
+    pub async fn list(&self) -> Result<Vec<Member>, sqlx::Error> {
+        let mut tx = self.begin().await?;
+        let result = sqlx::query_as::<_, Member>(
+            r#"
+            SELECT m.org_id, m.user_id, m.role, m.created_at, u.email, u.name
+            FROM organization_memberships m
+            JOIN users u ON u.id = m.user_id
+            WHERE m.org_id = $1
+            ORDER BY m.created_at, m.user_id
+            "#,
+        )
+        .bind(self.tenant.org_id())
+        .fetch_all(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(result)
+    }
+
+    /// Find a member by user ID
+    pub async fn find(&self, user_id: Uuid) -> Result<Option<Member>, sqlx::Error> {
+        let mut tx = self.begin().await?;
+        let result = sqlx::query_as::<_, Member>(
+            r#"
+            SELECT m.org_id, m.user_id, m.role, m.created_at, u.email, u.name
+            FROM organization_memberships m
+            JOIN users u ON u.id = m.user_id
+            WHERE m.org_id = $1 AND m.user_id = $2
+            "#,
+        )
+        .bind(self.tenant.org_id())
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(result)
+    }
+
+    /// Check whether a user with the given email, ignoring case, is a member
+    pub async fn exists_with_email(&self, email: &str) -> Result<bool, sqlx::Error> {
+        let mut tx = self.begin().await?;
+        let result = sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT EXISTS (
+                SELECT 1
+                FROM organization_memberships m
+                JOIN users u ON u.id = m.user_id
+                WHERE m.org_id = $1 AND lower(u.email) = lower($2)
+            )
+            "#,
+        )
+        .bind(self.tenant.org_id())
+        .bind(email)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(result)
+    }
+
+    /// Change a member's role, unless that leaves the organization without an owner
+    pub async fn update_role(
+        &self,
+        user_id: Uuid,
+        role: OrgRole,
+    ) -> Result<MemberChange, sqlx::Error> {
+        let mut tx = self.lock().await?;
+
+        let result = sqlx::query(
+            r#"
+            UPDATE organization_memberships
+            SET role = $3
+            WHERE org_id = $1 AND user_id = $2
+            "#,
+        )
+        .bind(self.tenant.org_id())
+        .bind(user_id)
+        .bind(role)
+        .execute(&mut *tx)
+        .await?;
+
+        self.finish(tx, result.rows_affected()).await
+    }
+
+    /// Remove a member, unless they are the last owner
+    pub async fn remove(&self, user_id: Uuid) -> Result<MemberChange, sqlx::Error> {
+        let mut tx = self.lock().await?;
+
+        let result = sqlx::query(
+            r#"
+            DELETE FROM organization_memberships
+            WHERE org_id = $1 AND user_id = $2
+            "#,
+        )
+        .bind(self.tenant.org_id())
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        self.finish(tx, result.rows_affected()).await
+    }
+
+    async fn begin(&self) -> Result<Transaction<'a, Postgres>, sqlx::Error> {
+        tenant::begin(self.pool, self.tenant, self.isolation).await
+    }
+
+    /// Start a transaction holding the organization's row lock, so concurrent
+    /// changes cannot together remove every owner
+    async fn lock(&self) -> Result<Transaction<'a, Postgres>, sqlx::Error> {
+        let mut tx = self.begin().await?;
+
+        sqlx::query("SELECT 1 FROM organizations WHERE id = $1 FOR UPDATE")
+            .bind(self.tenant.org_id())
+            .execute(&mut *tx)
+            .await?;
+
+        Ok(tx)
+    }
+
+    /// Commit a membership change unless it matched nothing or left no owner
+    async fn finish(
+        &self,
+        mut tx: Transaction<'a, Postgres>,
+        rows_affected: u64,
+    ) -> Result<MemberChange, sqlx::Error> {
+        if rows_affected == 0 {
+            tx.rollback().await?;
+            return Ok(MemberChange::NotFound);
+        }
+
+        let owners = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT COUNT(*)
+            FROM organization_memberships
+            WHERE org_id = $1 AND role = 'owner'
+            "#,
+        )
+        .bind(self.tenant.org_id())
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if owners == 0 {
+            tx.rollback().await?;
+            return Ok(MemberChange::LastOwner);
+        }
+
+        tx.commit().await?;
+        Ok(MemberChange::Applied)
+    }
+}
@@ -0,0 +1,158 @@
+//! MFA repository - Data access for TOTP enrollments and recovery codes
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::domain::models::UserMfa;
+
+pub struct MfaRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> MfaRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Find a user's enrollment (confirmed or pending)
+    pub async fn find_by_user_id(&self, user_id: Uuid) -> Result<Option<UserMfa>, sqlx::Error> {
+        sqlx::query_as::<_, UserMfa>(
+            r#"
+            SELECT secret_encrypted, enabled_at
+            FROM user_mfa
+            WHERE user_id = $1
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(self.pool)
+        .await
+    }
+
+    /// Store a pending (unconfirmed) secret, replacing any previous pending one.
+    ///
+    /// Does nothing if the user already has a confirmed enrollment.
+    pub async fn upsert_pending(
+        &self,
+        user_id: Uuid,
+        secret_encrypted: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO user_mfa (user_id, secret_encrypted)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id) DO UPDATE
+            SET secret_encrypted = EXCLUDED.secret_encrypted,
+                last_used_step = NULL,
+                created_at = NOW(),
+                updated_at = NOW()
+            WHERE user_mfa.enabled_at IS NULL
+            "#,
+        )
+        .bind(user_id)
+        .bind(secret_encrypted)
+        .execute(self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Confirm enrollment and replace the user's recovery codes
+    pub async fn enable(
+        &self,
+        user_id: Uuid,
+        step: i64,
+        recovery_code_hashes: &[String],
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            UPDATE user_mfa
+            SET enabled_at = NOW(), last_used_step = $2, updated_at = NOW()
+            WHERE user_id = $1
+            "#,
+        )
+        .bind(user_id)
+        .bind(step)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("DELETE FROM mfa_recovery_codes WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        for code_hash in recovery_code_hashes {
+            sqlx::query(
+                r#"
+                INSERT INTO mfa_recovery_codes (id, user_id, code_hash)
+                VALUES ($1, $2, $3)
+                "#,
+            )
+            .bind(Uuid::new_v4())
+            .bind(user_id)
+            .bind(code_hash)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await
+    }
+
+    /// Record a TOTP time step as used.
+    ///
+    /// Returns `false` if that step (or a later one) was already used, i.e. the
+    /// code is being replayed.
+    pub async fn use_step(&self, user_id: Uuid, step: i64) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE user_mfa
+            SET last_used_step = $2, updated_at = NOW()
+            WHERE user_id = $1 AND (last_used_step IS NULL OR last_used_step < $2)
+            "#,
+        )
+        .bind(user_id)
+        .bind(step)
+        .execute(self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Mark an unused recovery code as used. Returns `false` if no such code exists.
+    pub async fn use_recovery_code(
+        &self,
+        user_id: Uuid,
+        code_hash: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE mfa_recovery_codes
+            SET used_at = NOW()
+            WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL
+            "#,
+        )
+        .bind(user_id)
+        .bind(code_hash)
+        .execute(self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Remove a user's enrollment and recovery codes
+    pub async fn delete(&self, user_id: Uuid) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM mfa_recovery_codes WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM user_mfa WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await
+    }
+}
@@ -1,5 +1,33 @@
 //! Repository implementations
 
+mod api_key_repo;
+mod audit_log_repo;
+mod data_export_repo;
+mod email_verification_repo;
+mod invitation_repo;
+mod lockout_repo;
+mod member_repo;
+mod mfa_repo;
+mod organization_repo;
+mod password_reset_repo;
+mod refresh_token_repo;
+mod revocation_repo;
+mod role_repo;
+pub mod tenant;
 mod user_repo;
 
+pub use api_key_repo::ApiKeyRepository;
+pub use audit_log_repo::AuditLogRepository;
+pub use data_export_repo::DataExportRepository;
+pub use email_verification_repo::EmailVerificationRepository;
+pub use invitation_repo::InvitationRepository;
+pub use lockout_repo::LockoutRepository;
+pub use member_repo::{MemberChange, MemberRepository};
+pub use mfa_repo::MfaRepository;
+pub use organization_repo::OrganizationRepository;
+pub use password_reset_repo::PasswordResetRepository;
+pub use refresh_token_repo::RefreshTokenRepository;
+pub use revocation_repo::RevocationRepository;
+pub use role_repo::RoleRepository;
+pub use tenant::TenantIsolation;
 pub use user_repo::UserRepository;
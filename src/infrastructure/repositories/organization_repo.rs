@@ -0,0 +1,170 @@
+//! Organization repository - Data access for organizations from a user's side
+//!
+//! Queries here start from a user, not from an organization; data inside an
+//! organization goes through the tenant-scoped [`MemberRepository`] and
+//! [`InvitationRepository`].
+//!
+//! [`MemberRepository`]: super::MemberRepository
+//! [`InvitationRepository`]: super::InvitationRepository
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::domain::models::{Membership, OrgRole, Organization, OrganizationMembership, Tenant};
+
+pub struct OrganizationRepository<'a> {
+    pool: &'a PgPool,
+}
+
+#[allow(dead_code)]
+impl<'a> OrganizationRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Create an organization with its first owner
+    pub async fn create(
+        &self,
+        organization: &Organization,
+        owner: &Membership,
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO organizations (id, name, created_at)
+            VALUES ($1, $2, $3)
+            "#,
+        )
+        .bind(organization.id)
+        .bind(&organization.name)
+        .bind(organization.created_at)
+        .execute(&mut *tx)
+        .await?;
+
+        insert_membership(&mut tx, owner).await?;
+
+        tx.commit().await
+    }
+
+    /// List the organizations a user belongs to, oldest membership first
+    pub async fn list_for_user(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<OrganizationMembership>, sqlx::Error> {
+        sqlx::query_as::<_, OrganizationMembership>(
+            r#"
+            SELECT o.id, o.name, o.created_at, m.role
+            FROM organization_memberships m
+            JOIN organizations o ON o.id = m.org_id
+            WHERE m.user_id = $1
+            ORDER BY m.created_at, o.id
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(self.pool)
+        .await
+    }
+
+    /// Find a user's membership in an organization
+    pub async fn find_membership(
+        &self,
+        org_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Option<Membership>, sqlx::Error> {
+        sqlx::query_as::<_, Membership>(
+            r#"
+            SELECT org_id, user_id, role, created_at
+            FROM organization_memberships
+            WHERE org_id = $1 AND user_id = $2
+            "#,
+        )
+        .bind(org_id)
+        .bind(user_id)
+        .fetch_optional(self.pool)
+        .await
+    }
+
+    /// Find the organization of a tenant
+    pub async fn find(&self, tenant: Tenant) -> Result<Option<Organization>, sqlx::Error> {
+        sqlx::query_as::<_, Organization>(
+            r#"
+            SELECT id, name, created_at
+            FROM organizations
+            WHERE id = $1
+            "#,
+        )
+        .bind(tenant.org_id())
+        .fetch_optional(self.pool)
+        .await
+    }
+
+    /// Accept a pending invitation addressed to `email` (ignoring case),
+    /// making `user_id` a member with the invited role.
+    ///
+    /// Returns `None` if there is no such invitation. Accepting an invitation
+    /// to an organization the user already belongs to keeps their role.
+    pub async fn accept_invitation(
+        &self,
+        token_hash: &str,
+        email: &str,
+        user_id: Uuid,
+    ) -> Result<Option<Membership>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let invitation = sqlx::query_as::<_, (Uuid, OrgRole)>(
+            r#"
+            UPDATE organization_invitations
+            SET accepted_at = NOW()
+            WHERE token_hash = $1 AND lower(email) = lower($2)
+              AND accepted_at IS NULL AND expires_at > NOW()
+            RETURNING org_id, role
+            "#,
+        )
+        .bind(token_hash)
+        .bind(email)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some((org_id, role)) = invitation else {
+            return Ok(None);
+        };
+
+        insert_membership(&mut tx, &Membership::new(org_id, user_id, role)).await?;
+        let membership = sqlx::query_as::<_, Membership>(
+            r#"
+            SELECT org_id, user_id, role, created_at
+            FROM organization_memberships
+            WHERE org_id = $1 AND user_id = $2
+            "#,
+        )
+        .bind(org_id)
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(Some(membership))
+    }
+}
+
+/// Add a member unless they already belong to the organization
+async fn insert_membership(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    membership: &Membership,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO organization_memberships (org_id, user_id, role, created_at)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (org_id, user_id) DO NOTHING
+        "#,
+    )
+    .bind(membership.org_id)
+    .bind(membership.user_id)
+    .bind(membership.role)
+    .bind(membership.created_at)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
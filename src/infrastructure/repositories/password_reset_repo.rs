@@ -0,0 +1,82 @@
+//! Password reset repository - Data access for password reset tokens
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::domain::models::PasswordResetToken;
+
+pub struct PasswordResetRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> PasswordResetRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Create a new password reset token
+    pub async fn create(&self, token: &PasswordResetToken) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO password_reset_tokens (id, user_id, token_hash, expires_at, created_at)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+        )
+        .bind(token.id)
+        .bind(token.user_id)
+        .bind(&token.token_hash)
+        .bind(token.expires_at)
+        .bind(token.created_at)
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Return the user ID of a valid (unused, unexpired) token without using it up
+    pub async fn find_valid(&self, token_hash: &str) -> Result<Option<Uuid>, sqlx::Error> {
+        sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT user_id
+            FROM password_reset_tokens
+            WHERE token_hash = $1 AND used_at IS NULL AND expires_at > NOW()
+            "#,
+        )
+        .bind(token_hash)
+        .fetch_optional(self.pool)
+        .await
+    }
+
+    /// Mark a valid (unused, unexpired) token as used and return its user ID.
+    ///
+    /// Returns `None` if the token does not exist, has expired or was already used.
+    pub async fn consume(&self, token_hash: &str) -> Result<Option<Uuid>, sqlx::Error> {
+        sqlx::query_scalar::<_, Uuid>(
+            r#"
+            UPDATE password_reset_tokens
+            SET used_at = NOW()
+            WHERE token_hash = $1 AND used_at IS NULL AND expires_at > NOW()
+            RETURNING user_id
+            "#,
+        )
+        .bind(token_hash)
+        .fetch_optional(self.pool)
+        .await
+    }
+
+    /// Invalidate all outstanding tokens of a user
+    pub async fn invalidate_for_user(&self, user_id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE password_reset_tokens
+            SET used_at = NOW()
+            WHERE user_id = $1 AND used_at IS NULL
+            "#,
+        )
+        .bind(user_id)
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
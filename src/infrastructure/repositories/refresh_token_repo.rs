@@ -0,0 +1,146 @@
+//! Refresh token repository - Data access for refresh tokens
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::domain::models::RefreshToken;
+
+pub struct RefreshTokenRepository<'a> {
+    pool: &'a PgPool,
+}
+
+#[allow(dead_code)]
+impl<'a> RefreshTokenRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Create a new refresh token
+    pub async fn create(&self, token: &RefreshToken) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO refresh_tokens (id, user_id, family_id, org_id, token_hash, expires_at, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+        )
+        .bind(token.id)
+        .bind(token.user_id)
+        .bind(token.family_id)
+        .bind(token.org_id)
+        .bind(&token.token_hash)
+        .bind(token.expires_at)
+        .bind(token.created_at)
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Find refresh token by its hash (including revoked tokens)
+    pub async fn find_by_hash(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<RefreshToken>, sqlx::Error> {
+        sqlx::query_as::<_, RefreshToken>(
+            r#"
+            SELECT id, user_id, family_id, org_id, token_hash, expires_at, revoked_at, replaced_by, created_at
+            FROM refresh_tokens
+            WHERE token_hash = $1
+            "#,
+        )
+        .bind(token_hash)
+        .fetch_optional(self.pool)
+        .await
+    }
+
+    /// Replace a token with its successor.
+    ///
+    /// Returns `false` without inserting anything if the old token was already
+    /// revoked, which happens when two requests race to rotate the same token.
+    pub async fn rotate(&self, old_id: Uuid, new: &RefreshToken) -> Result<bool, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO refresh_tokens (id, user_id, family_id, org_id, token_hash, expires_at, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+        )
+        .bind(new.id)
+        .bind(new.user_id)
+        .bind(new.family_id)
+        .bind(new.org_id)
+        .bind(&new.token_hash)
+        .bind(new.expires_at)
+        .bind(new.created_at)
+        .execute(&mut *tx)
+        .await?;
+
+        let result = sqlx::query(
+            r#"
+            UPDATE refresh_tokens
+            SET revoked_at = NOW(), replaced_by = $2
+            WHERE id = $1 AND revoked_at IS NULL
+            "#,
+        )
+        .bind(old_id)
+        .bind(new.id)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            tx.rollback().await?;
+            return Ok(false);
+        }
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Revoke every token in a family
+    pub async fn revoke_family(&self, family_id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE refresh_tokens
+            SET revoked_at = NOW()
+            WHERE family_id = $1 AND revoked_at IS NULL
+            "#,
+        )
+        .bind(family_id)
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Revoke every token belonging to a user
+    pub async fn revoke_all_for_user(&self, user_id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE refresh_tokens
+            SET revoked_at = NOW()
+            WHERE user_id = $1 AND revoked_at IS NULL
+            "#,
+        )
+        .bind(user_id)
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// List all of a user's tokens, revoked and expired ones included, oldest first
+    pub async fn list_for_user(&self, user_id: Uuid) -> Result<Vec<RefreshToken>, sqlx::Error> {
+        sqlx::query_as::<_, RefreshToken>(
+            r#"
+            SELECT id, user_id, family_id, org_id, token_hash, expires_at, revoked_at, replaced_by, created_at
+            FROM refresh_tokens
+            WHERE user_id = $1
+            ORDER BY created_at
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(self.pool)
+        .await
+    }
+}
@@ -0,0 +1,114 @@
+//! Revocation repository - Data access for revoked access tokens
+
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+pub struct RevocationRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> RevocationRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Revoke a single access token by its JWT ID
+    pub async fn revoke_token(
+        &self,
+        jti: Uuid,
+        user_id: Uuid,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO revoked_tokens (jti, user_id, expires_at)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (jti) DO NOTHING
+            "#,
+        )
+        .bind(jti)
+        .bind(user_id)
+        .bind(expires_at)
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Check whether an access token has been revoked
+    pub async fn is_token_revoked(&self, jti: Uuid) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT EXISTS(SELECT 1 FROM revoked_tokens WHERE jti = $1)
+            "#,
+        )
+        .bind(jti)
+        .fetch_one(self.pool)
+        .await
+    }
+
+    /// Reject every access token issued to a user before `revoked_before`
+    pub async fn revoke_all_for_user(
+        &self,
+        user_id: Uuid,
+        revoked_before: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO user_token_revocations (user_id, revoked_before)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id) DO UPDATE SET revoked_before = EXCLUDED.revoked_before
+            "#,
+        )
+        .bind(user_id)
+        .bind(revoked_before)
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Get the cutoff before which a user's access tokens are rejected: the
+    /// later of the last logout everywhere and the last password change.
+    ///
+    /// For deactivated and deleted users it is `now`, rejecting every token
+    /// issued so far.
+    pub async fn find_user_cutoff(
+        &self,
+        user_id: Uuid,
+        now: DateTime<Utc>,
+    ) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+        let cutoff = sqlx::query_scalar::<_, Option<DateTime<Utc>>>(
+            r#"
+            SELECT CASE
+                       WHEN u.is_active THEN GREATEST(r.revoked_before, u.password_changed_at)
+                       ELSE $2
+                   END
+            FROM users u
+            LEFT JOIN user_token_revocations r ON r.user_id = u.id
+            WHERE u.id = $1
+            "#,
+        )
+        .bind(user_id)
+        .bind(now)
+        .fetch_optional(self.pool)
+        .await?;
+
+        Ok(cutoff.unwrap_or(Some(now)))
+    }
+
+    /// Delete revocation entries for tokens that have expired anyway
+    pub async fn delete_expired(&self) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM revoked_tokens
+            WHERE expires_at < NOW()
+            "#,
+        )
+        .execute(self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
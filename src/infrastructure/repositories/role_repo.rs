@@ -0,0 +1,76 @@
+//! Role repository - Data access for roles and permissions
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+pub struct RoleRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> RoleRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Assign a role to a user
+    pub async fn assign(&self, user_id: Uuid, role_name: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO user_roles (user_id, role_name)
+            VALUES ($1, $2)
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(user_id)
+        .bind(role_name)
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Names of the roles assigned to a user
+    pub async fn find_roles_for_user(&self, user_id: Uuid) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT role_name
+            FROM user_roles
+            WHERE user_id = $1
+            ORDER BY role_name
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(self.pool)
+        .await
+    }
+
+    /// Names of the permissions granted to a user through their roles.
+    ///
+    /// Roles marked `requires_mfa` only contribute permissions once the user has
+    /// confirmed two-factor authentication.
+    pub async fn find_permissions_for_user(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT DISTINCT rp.permission_name
+            FROM user_roles ur
+            JOIN roles r ON r.name = ur.role_name
+            JOIN role_permissions rp ON rp.role_name = ur.role_name
+            WHERE ur.user_id = $1
+              AND (
+                  NOT r.requires_mfa
+                  OR EXISTS (
+                      SELECT 1 FROM user_mfa m
+                      WHERE m.user_id = ur.user_id AND m.enabled_at IS NOT NULL
+                  )
+              )
+            ORDER BY rp.permission_name
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(self.pool)
+        .await
+    }
+}
@@ -0,0 +1,277 @@
+//! Tenant isolation for tenant-scoped repositories
+//!
+//! Tenant-scoped repositories ([`MemberRepository`](super::MemberRepository)
+//! and [`InvitationRepository`](super::InvitationRepository)) always filter
+//! their queries by the tenant's organization. With
+//! [`TenantIsolation::RowLevelSecurity`] the database enforces the same
+//! boundary for them: each call runs in a transaction that switches to the
+//! restricted `app_tenant` role and sets the `app.current_org_id` and
+//! `app.current_user_id` settings the row-level security policies read, so a
+//! query missing its filter still cannot reach other organizations' rows.
+//!
+//! Only queries made through [`begin`] are restricted. Users are covered
+//! where an organization reads them, i.e. the member listings of
+//! `MemberRepository`, which join `users` inside the tenant transaction.
+//! [`UserRepository`](super::UserRepository) is not tenant-scoped and is not
+//! covered: it serves account-level lookups (sign-up, login, profiles,
+//! administration) that run as the application role, as do the organization
+//! lookups that establish a membership in the first place. Organization data
+//! must not be read through it.
+
+use std::str::FromStr;
+
+use sqlx::{PgPool, Postgres, Transaction};
+
+use crate::domain::models::Tenant;
+
+/// Database role the row-level security policies restrict
+const TENANT_ROLE: &str = "app_tenant";
+
+/// How tenant-scoped repositories keep organizations apart
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TenantIsolation {
+    /// `WHERE org_id = ...` in every query
+    #[default]
+    Filter,
+    /// Query filters of tenant-scoped repositories, enforced by Postgres
+    /// row-level security policies
+    RowLevelSecurity,
+}
+
+impl FromStr for TenantIsolation {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "filter" => Ok(Self::Filter),
+            "row_level_security" => Ok(Self::RowLevelSecurity),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Begin a transaction for queries on behalf of `tenant`
+pub async fn begin(
+    pool: &PgPool,
+    tenant: Tenant,
+    isolation: TenantIsolation,
+) -> Result<Transaction<'_, Postgres>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    if isolation == TenantIsolation::RowLevelSecurity {
+        // Both only last until the transaction ends, so the connection goes
+        // back to the pool unrestricted
+        sqlx::query(&format!("SET LOCAL ROLE {TENANT_ROLE}"))
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            "SELECT set_config('app.current_org_id', $1, true), \
+                    set_config('app.current_user_id', $2, true)",
+        )
+        .bind(tenant.org_id().to_string())
+        .bind(tenant.user_id().to_string())
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    Ok(tx)
+}
+
+/// Check that the application can switch to the row-level security role
+pub async fn row_level_security_available(pool: &PgPool) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar::<_, bool>(
+        r#"
+        SELECT EXISTS (SELECT 1 FROM pg_roles WHERE rolname = $1)
+           AND pg_has_role(current_user, $1, 'MEMBER')
+        "#,
+    )
+    .bind(TENANT_ROLE)
+    .fetch_one(pool)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::{
+        domain::{
+            models::{Membership, OrgRole},
+            services::{AuthService, OrganizationService, UserService},
+        },
+        test_utils::test_state,
+    };
+
+    /// Two organizations with one owner and one pending invitation each
+    async fn two_tenants(pool: &PgPool) -> (Membership, Membership) {
+        let (state, _mailer) = test_state(pool.clone());
+        let organization_service = OrganizationService::new(&state);
+
+        let mut memberships = Vec::new();
+        for name in ["alpha", "beta"] {
+            let email = format!("{name}@example.com");
+            AuthService::new(&state)
+                .register(&email, "password123", name)
+                .await
+                .unwrap();
+            let user = UserService::new(&state).get_by_email(&email).await.unwrap();
+            let organization = organization_service
+                .create(user.id, name.into())
+                .await
+                .unwrap();
+            let membership = organization_service
+                .membership(organization.organization.id, user.id)
+                .await
+                .unwrap();
+            organization_service
+                .invite(
+                    &membership,
+                    &format!("{name}-invitee@example.com"),
+                    OrgRole::Member,
+                )
+                .await
+                .unwrap();
+            memberships.push(membership);
+        }
+
+        let beta = memberships.pop().unwrap();
+        let alpha = memberships.pop().unwrap();
+        (alpha, beta)
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_unfiltered_queries_only_see_the_current_tenant(pool: PgPool) {
+        let (alpha, beta) = two_tenants(&pool).await;
+        let mut tx = begin(&pool, alpha.tenant(), TenantIsolation::RowLevelSecurity)
+            .await
+            .unwrap();
+
+        // None of these queries filter by organization
+        let org_ids: Vec<Uuid> = sqlx::query_scalar("SELECT org_id FROM organization_memberships")
+            .fetch_all(&mut *tx)
+            .await
+            .unwrap();
+        assert_eq!(org_ids, vec![alpha.org_id]);
+
+        let invitations: Vec<String> =
+            sqlx::query_scalar("SELECT email FROM organization_invitations")
+                .fetch_all(&mut *tx)
+                .await
+                .unwrap();
+        assert_eq!(invitations, vec!["alpha-invitee@example.com".to_string()]);
+
+        let organizations: Vec<String> = sqlx::query_scalar("SELECT name FROM organizations")
+            .fetch_all(&mut *tx)
+            .await
+            .unwrap();
+        assert_eq!(organizations, vec!["alpha".to_string()]);
+
+        let emails: Vec<String> = sqlx::query_scalar("SELECT email FROM users")
+            .fetch_all(&mut *tx)
+            .await
+            .unwrap();
+        assert_eq!(emails, vec!["alpha@example.com".to_string()]);
+
+        // Other tenants' rows can neither be changed nor created
+        let updated = sqlx::query("UPDATE organization_memberships SET role = 'member'")
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+        assert_eq!(updated.rows_affected(), 1);
+        let deleted = sqlx::query("DELETE FROM organization_invitations WHERE org_id = $1")
+            .bind(beta.org_id)
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+        assert_eq!(deleted.rows_affected(), 0);
+        assert!(
+            sqlx::query(
+                "INSERT INTO organization_memberships (org_id, user_id, role) VALUES ($1, $2, 'owner')",
+            )
+            .bind(beta.org_id)
+            .bind(alpha.user_id)
+            .execute(&mut *tx)
+            .await
+            .is_err()
+        );
+        tx.rollback().await.unwrap();
+
+        // The restriction ends with the transaction
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM organization_memberships")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_application_role_without_ownership_is_unrestricted(pool: PgPool) {
+        let (alpha, _beta) = two_tenants(&pool).await;
+
+        // An application role that does not own the tables, as in deployments
+        // where migrations run as a separate role
+        let mut tx = pool.begin().await.unwrap();
+        for statement in [
+            "CREATE ROLE app_runtime_test NOLOGIN",
+            "GRANT app_tenant TO app_runtime_test",
+            "GRANT SELECT, INSERT ON users, organization_memberships TO app_runtime_test",
+            "SET LOCAL ROLE app_runtime_test",
+        ] {
+            sqlx::query(statement).execute(&mut *tx).await.unwrap();
+        }
+
+        let users: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users")
+            .fetch_one(&mut *tx)
+            .await
+            .unwrap();
+        assert_eq!(users, 2);
+        let memberships: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM organization_memberships")
+            .fetch_one(&mut *tx)
+            .await
+            .unwrap();
+        assert_eq!(memberships, 2);
+        sqlx::query(
+            "INSERT INTO users (id, email, password_hash, name) VALUES ($1, 'new@example.com', '', 'New')",
+        )
+        .bind(Uuid::new_v4())
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+
+        // Switching to the tenant role still restricts it
+        sqlx::query("SET LOCAL ROLE app_tenant")
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+        sqlx::query("SELECT set_config('app.current_org_id', $1, true)")
+            .bind(alpha.org_id.to_string())
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+        let memberships: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM organization_memberships")
+            .fetch_one(&mut *tx)
+            .await
+            .unwrap();
+        assert_eq!(memberships, 1);
+        tx.rollback().await.unwrap();
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a PostgreSQL database (DATABASE_URL)"]
+    async fn test_filter_isolation_relies_on_queries(pool: PgPool) {
+        let (alpha, _beta) = two_tenants(&pool).await;
+        assert!(row_level_security_available(&pool).await.unwrap());
+
+        let mut tx = begin(&pool, alpha.tenant(), TenantIsolation::Filter)
+            .await
+            .unwrap();
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM organization_memberships")
+            .fetch_one(&mut *tx)
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+    }
+}
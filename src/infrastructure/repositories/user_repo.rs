@@ -1,9 +1,13 @@
 //! User repository - Data access for users
 
-use sqlx::PgPool;
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, QueryBuilder, Transaction};
 use uuid::Uuid;
 
-use crate::domain::models::User;
+use crate::{
+    common::pagination::{Cursor, SortDirection},
+    domain::models::{User, UserFilter, UserSearchResult, UserSortField},
+};
 
 pub struct UserRepository<'a> {
     pool: &'a PgPool,
@@ -19,8 +23,8 @@ impl<'a> UserRepository<'a> {
     pub async fn create(&self, user: &User) -> Result<(), sqlx::Error> {
         sqlx::query(
             r#"
-            INSERT INTO users (id, email, password_hash, name, is_active, created_at, updated_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            INSERT INTO users (id, email, password_hash, name, is_active, email_verified_at, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
             "#,
         )
         .bind(user.id)
@@ -28,6 +32,7 @@ impl<'a> UserRepository<'a> {
         .bind(&user.password_hash)
         .bind(&user.name)
         .bind(user.is_active)
+        .bind(user.email_verified_at)
         .bind(user.created_at)
         .bind(user.updated_at)
         .execute(self.pool)
@@ -36,11 +41,52 @@ impl<'a> UserRepository<'a> {
         Ok(())
     }
 
+    /// Create a user with a role unless the email is already taken, ignoring
+    /// case. Returns `false` if it was.
+    pub async fn create_with_role_if_absent(
+        &self,
+        user: &User,
+        role_name: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO users (id, email, password_hash, name, is_active, email_verified_at, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT ((lower(email))) DO NOTHING
+            "#,
+        )
+        .bind(user.id)
+        .bind(&user.email)
+        .bind(&user.password_hash)
+        .bind(&user.name)
+        .bind(user.is_active)
+        .bind(user.email_verified_at)
+        .bind(user.created_at)
+        .bind(user.updated_at)
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        sqlx::query("INSERT INTO user_roles (user_id, role_name) VALUES ($1, $2)")
+            .bind(user.id)
+            .bind(role_name)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
     /// Find user by ID
     pub async fn find_by_id(&self, id: Uuid) -> Result<Option<User>, sqlx::Error> {
         sqlx::query_as::<_, User>(
             r#"
-            SELECT id, email, password_hash, name, is_active, created_at, updated_at
+            SELECT id, email, password_hash, name, is_active, email_verified_at, password_changed_at,
+                   deleted_at, created_at, updated_at
             FROM users
             WHERE id = $1 AND is_active = true
             "#,
@@ -50,13 +96,125 @@ impl<'a> UserRepository<'a> {
         .await
     }
 
-    /// Find user by email
+    /// Find user by ID, including deactivated users
+    pub async fn find_by_id_including_inactive(
+        &self,
+        id: Uuid,
+    ) -> Result<Option<User>, sqlx::Error> {
+        sqlx::query_as::<_, User>(
+            r#"
+            SELECT id, email, password_hash, name, is_active, email_verified_at, password_changed_at,
+                   deleted_at, created_at, updated_at
+            FROM users
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(self.pool)
+        .await
+    }
+
+    /// List users matching `filter` in the given order, starting after `after`
+    /// and skipping `offset` rows
+    pub async fn list(
+        &self,
+        filter: &UserFilter,
+        sort: UserSortField,
+        direction: SortDirection,
+        after: Option<&Cursor>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<User>, sqlx::Error> {
+        let mut query = select_users(filter, |query| {
+            query.push(USER_COLUMNS);
+        });
+
+        let column = sort.column();
+        if let Some(cursor) = after {
+            // Row comparison continues from the last item, ties broken by id
+            query.push(format_args!(
+                " AND ({column}, id) {} (",
+                direction.after_operator()
+            ));
+            query.push_bind(cursor.value.clone());
+            if sort == UserSortField::CreatedAt {
+                query.push("::timestamptz");
+            }
+            query.push(", ").push_bind(cursor.id).push(")");
+        }
+
+        let order = direction.sql();
+        query.push(format_args!(
+            " ORDER BY {column} {order}, id {order} LIMIT "
+        ));
+        query.push_bind(limit).push(" OFFSET ").push_bind(offset);
+
+        query.build_query_as::<User>().fetch_all(self.pool).await
+    }
+
+    /// Count the users matching `filter`
+    pub async fn count(&self, filter: &UserFilter) -> Result<i64, sqlx::Error> {
+        let mut query = select_users(filter, |query| {
+            query.push("COUNT(*)");
+        });
+
+        query.build_query_scalar::<i64>().fetch_one(self.pool).await
+    }
+
+    /// Find users matching `filter` whose name or email matches a normalized
+    /// query, best first.
+    ///
+    /// Matches whole words and word prefixes, similar words, and substrings
+    /// (for names written without spaces, such as Thai).
+    pub async fn search(
+        &self,
+        query: &str,
+        filter: &UserFilter,
+        limit: i64,
+    ) -> Result<Vec<UserSearchResult>, sqlx::Error> {
+        let tsquery = prefix_tsquery(query);
+
+        let mut builder = select_users(filter, |builder| {
+            builder
+                .push(USER_COLUMNS)
+                .push(", (COALESCE(ts_rank(search_vector, to_tsquery('simple', ")
+                .push_bind(tsquery.clone())
+                .push(")), 0) + word_similarity(")
+                .push_bind(query.to_string())
+                .push(", search_text) + CASE WHEN strpos(search_text, ")
+                .push_bind(query.to_string())
+                .push(") > 0 THEN 1 ELSE 0 END)::real AS rank");
+        });
+        builder
+            .push(" AND (search_text LIKE ")
+            .push_bind(like_pattern(query))
+            .push(" OR ")
+            .push_bind(query.to_string())
+            .push(" <% search_text");
+        if let Some(tsquery) = tsquery {
+            builder
+                .push(" OR search_vector @@ to_tsquery('simple', ")
+                .push_bind(tsquery)
+                .push(")");
+        }
+        builder
+            .push(") ORDER BY rank DESC, created_at DESC, id DESC LIMIT ")
+            .push_bind(limit);
+
+        builder
+            .build_query_as::<UserSearchResult>()
+            .fetch_all(self.pool)
+            .await
+    }
+
+    /// Find user by email, ignoring case
     pub async fn find_by_email(&self, email: &str) -> Result<Option<User>, sqlx::Error> {
         sqlx::query_as::<_, User>(
             r#"
-            SELECT id, email, password_hash, name, is_active, created_at, updated_at
+            SELECT id, email, password_hash, name, is_active, email_verified_at, password_changed_at,
+                   deleted_at, created_at, updated_at
             FROM users
-            WHERE email = $1 AND is_active = true
+            WHERE lower(email) = lower($1) AND is_active = true
             "#,
         )
         .bind(email)
@@ -64,37 +222,504 @@ impl<'a> UserRepository<'a> {
         .await
     }
 
+    /// Id and email of every account, active or not, at one of `domains`,
+    /// ignoring case
+    pub async fn list_emails_at_domains(
+        &self,
+        domains: &[&str],
+    ) -> Result<Vec<(Uuid, String)>, sqlx::Error> {
+        sqlx::query_as::<_, (Uuid, String)>(
+            r#"
+            SELECT id, email
+            FROM users
+            WHERE lower(split_part(email, '@', 2)) = ANY($1)
+            ORDER BY created_at, id
+            "#,
+        )
+        .bind(domains)
+        .fetch_all(self.pool)
+        .await
+    }
+
+    /// Replace a user's stored email with another form of the same address,
+    /// unless another account already uses it. Returns `false` if one does.
+    pub async fn rewrite_email(&self, id: Uuid, email: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE users
+            SET email = $2, updated_at = NOW()
+            WHERE id = $1
+              AND NOT EXISTS (
+                  SELECT 1 FROM users AS other
+                  WHERE other.id <> $1 AND lower(other.email) = lower($2)
+              )
+            "#,
+        )
+        .bind(id)
+        .bind(email)
+        .execute(self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Whether any account, active or not, uses the email, ignoring case
+    pub async fn email_taken(&self, email: &str) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS (SELECT 1 FROM users WHERE lower(email) = lower($1))",
+        )
+        .bind(email)
+        .fetch_one(self.pool)
+        .await
+    }
+
+    /// Id, email and creation time of every account, active or not, oldest first
+    pub async fn list_emails(&self) -> Result<Vec<(Uuid, String, DateTime<Utc>)>, sqlx::Error> {
+        sqlx::query_as::<_, (Uuid, String, DateTime<Utc>)>(
+            r#"
+            SELECT id, email, created_at
+            FROM users
+            ORDER BY created_at, id
+            "#,
+        )
+        .fetch_all(self.pool)
+        .await
+    }
+
     /// Update user
     pub async fn update(&self, user: &User) -> Result<(), sqlx::Error> {
         sqlx::query(
             r#"
             UPDATE users
-            SET email = $2, name = $3, updated_at = NOW()
+            SET email = $2, name = $3, email_verified_at = $4, updated_at = NOW(),
+                pending_email = CASE WHEN email = $2 THEN pending_email END
             WHERE id = $1
             "#,
         )
         .bind(user.id)
         .bind(&user.email)
         .bind(&user.name)
+        .bind(user.email_verified_at)
         .execute(self.pool)
         .await?;
 
         Ok(())
     }
 
-    /// Soft delete user
-    pub async fn delete(&self, id: Uuid) -> Result<(), sqlx::Error> {
+    /// Set a new password that changed at `changed_at`. Access tokens issued
+    /// before then are rejected, and outstanding password reset links are
+    /// deleted, as they were meant for the old password.
+    pub async fn update_password(
+        &self,
+        id: Uuid,
+        password_hash: &str,
+        changed_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
         sqlx::query(
             r#"
             UPDATE users
-            SET is_active = false, updated_at = NOW()
+            SET password_hash = $2, password_changed_at = $3, updated_at = NOW()
             WHERE id = $1
             "#,
         )
         .bind(id)
+        .bind(password_hash)
+        .bind(changed_at)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("DELETE FROM password_reset_tokens WHERE user_id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Replace the hash of the unchanged password, e.g. with stronger parameters.
+    /// Leaves `password_changed_at` alone, so existing sessions stay valid.
+    pub async fn update_password_hash(
+        &self,
+        id: Uuid,
+        password_hash: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET password_hash = $2, updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(password_hash)
         .execute(self.pool)
         .await?;
 
         Ok(())
     }
+
+    /// Mark the user's email as verified, provided it is still `email`
+    pub async fn mark_email_verified(&self, id: Uuid, email: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE users
+            SET email_verified_at = NOW(), updated_at = NOW()
+            WHERE id = $1 AND email = $2 AND is_active = true
+            "#,
+        )
+        .bind(id)
+        .bind(email)
+        .execute(self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Record a new email address to switch to once it is verified
+    pub async fn set_pending_email(&self, id: Uuid, email: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET pending_email = $2, updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(email)
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Switch to the pending email address and mark it verified, provided it
+    /// is still `email`. Returns `false` if it is not.
+    pub async fn confirm_pending_email(&self, id: Uuid, email: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE users
+            SET email = pending_email, pending_email = NULL, email_verified_at = NOW(),
+                updated_at = NOW()
+            WHERE id = $1 AND pending_email = $2 AND is_active = true
+            "#,
+        )
+        .bind(id)
+        .bind(email)
+        .execute(self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Activate or deactivate a user. Returns `false` if the user does not
+    /// exist, or is deleted and `is_active` is set; deleted users are restored
+    /// with [`Self::restore`] instead.
+    pub async fn set_active(&self, id: Uuid, is_active: bool) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE users
+            SET is_active = $2, updated_at = NOW()
+            WHERE id = $1 AND (NOT $2 OR deleted_at IS NULL)
+            "#,
+        )
+        .bind(id)
+        .bind(is_active)
+        .execute(self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Whether the user is the only owner of an organization, which would be
+    /// left without one if the user went away
+    pub async fn is_last_owner(&self, id: Uuid) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar::<_, bool>(LAST_OWNER_QUERY)
+            .bind(id)
+            .fetch_one(self.pool)
+            .await
+    }
+
+    /// Permanently delete a user and, through cascades, all of their data.
+    /// Returns `false` if the user does not exist or is the only owner of an
+    /// organization.
+    pub async fn hard_delete(&self, id: Uuid) -> Result<bool, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        if lock_ownerships(&mut tx, id).await? {
+            return Ok(false);
+        }
+
+        let result = sqlx::query(
+            r#"
+            DELETE FROM users
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Soft delete user; the row stays until it is purged. Returns `false`,
+    /// changing nothing, if the user does not exist or is the only owner of
+    /// an organization.
+    pub async fn delete(&self, id: Uuid) -> Result<bool, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        if lock_ownerships(&mut tx, id).await? {
+            return Ok(false);
+        }
+
+        let result = sqlx::query(
+            r#"
+            UPDATE users
+            SET is_active = false, deleted_at = NOW(), updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Undo a soft delete. Returns `false` if the user is not deleted or was
+    /// already purged.
+    pub async fn restore(&self, id: Uuid) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE users
+            SET is_active = true, deleted_at = NULL, updated_at = NOW()
+            WHERE id = $1 AND deleted_at IS NOT NULL AND purged_at IS NULL
+            "#,
+        )
+        .bind(id)
+        .execute(self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Ids of users deleted before `cutoff` and not yet purged, oldest first
+    pub async fn find_pending_purge(
+        &self,
+        cutoff: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<Uuid>, sqlx::Error> {
+        sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT id
+            FROM users u
+            WHERE deleted_at < $1 AND purged_at IS NULL
+              -- Purging the only owner would leave the organization without one
+              AND NOT EXISTS (
+                  SELECT 1
+                  FROM organization_memberships m
+                  WHERE m.user_id = u.id AND m.role = 'owner'
+                    AND NOT EXISTS (
+                        SELECT 1 FROM organization_memberships other
+                        WHERE other.org_id = m.org_id AND other.role = 'owner'
+                          AND other.user_id <> u.id
+                    )
+              )
+            ORDER BY deleted_at
+            LIMIT $2
+            "#,
+        )
+        .bind(cutoff)
+        .bind(limit)
+        .fetch_all(self.pool)
+        .await
+    }
+
+    /// Permanently delete a soft-deleted user, as [`Self::hard_delete`] does.
+    /// Returns `false` if the user is not deleted, e.g. was restored meanwhile,
+    /// or is the only owner of an organization.
+    pub async fn remove_deleted(&self, id: Uuid) -> Result<bool, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        if lock_ownerships(&mut tx, id).await? {
+            return Ok(false);
+        }
+
+        let result = sqlx::query(
+            r#"
+            DELETE FROM users
+            WHERE id = $1 AND deleted_at IS NOT NULL
+            "#,
+        )
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Strip a deleted user of personal data and remove everything else
+    /// belonging to them, keeping only the row with its id. The email is
+    /// replaced, so it can be registered again.
+    ///
+    /// The row is deleted and put back as a tombstone, so foreign keys clean up
+    /// everything referencing the user, data exports included, exactly as a
+    /// hard delete does. Returns `false` if the user is not deleted or is the
+    /// only owner of an organization.
+    pub async fn anonymize(&self, id: Uuid) -> Result<bool, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        if lock_ownerships(&mut tx, id).await? {
+            return Ok(false);
+        }
+
+        let deleted = sqlx::query_as::<_, (String, DateTime<Utc>, Option<DateTime<Utc>>)>(
+            r#"
+            DELETE FROM users
+            WHERE id = $1 AND deleted_at IS NOT NULL AND purged_at IS NULL
+            RETURNING email, created_at, deleted_at
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some((email, created_at, deleted_at)) = deleted else {
+            return Ok(false);
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO users (id, email, password_hash, name, is_active, created_at, updated_at,
+                               deleted_at, purged_at)
+            VALUES ($1, 'deleted-' || $1 || '@invalid', '', 'Deleted user', false, $2, NOW(),
+                    $3, NOW())
+            "#,
+        )
+        .bind(id)
+        .bind(created_at)
+        .bind(deleted_at)
+        .execute(&mut *tx)
+        .await?;
+
+        // Failed logins are tracked by address rather than by user
+        sqlx::query("DELETE FROM account_lockouts WHERE email = lower($1)")
+            .bind(&email)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+}
+
+/// Whether user `$1` is the only owner of an organization
+const LAST_OWNER_QUERY: &str = r#"
+    SELECT EXISTS (
+        SELECT 1
+        FROM organization_memberships m
+        WHERE m.user_id = $1 AND m.role = 'owner'
+          AND NOT EXISTS (
+              SELECT 1 FROM organization_memberships other
+              WHERE other.org_id = m.org_id AND other.role = 'owner' AND other.user_id <> $1
+          )
+    )
+"#;
+
+/// Lock the organizations the user owns, as membership changes do, so no
+/// other owner can leave meanwhile, and check whether removing the user would
+/// leave one of them without an owner
+async fn lock_ownerships(
+    tx: &mut Transaction<'_, Postgres>,
+    id: Uuid,
+) -> Result<bool, sqlx::Error> {
+    sqlx::query(
+        r#"
+        SELECT 1
+        FROM organizations
+        WHERE id IN (
+            SELECT org_id FROM organization_memberships
+            WHERE user_id = $1 AND role = 'owner'
+        )
+        ORDER BY id
+        FOR UPDATE
+        "#,
+    )
+    .bind(id)
+    .execute(&mut **tx)
+    .await?;
+
+    sqlx::query_scalar::<_, bool>(LAST_OWNER_QUERY)
+        .bind(id)
+        .fetch_one(&mut **tx)
+        .await
+}
+
+/// Columns of [`User`], for queries built with [`select_users`]
+const USER_COLUMNS: &str = "id, email, password_hash, name, is_active, email_verified_at, \
+                            password_changed_at, deleted_at, created_at, updated_at";
+
+/// Start a `SELECT` over the users matching `filter`, for the caller to add
+/// conditions, ordering and limits to. `select` pushes the selected columns.
+fn select_users<'q>(
+    filter: &UserFilter,
+    select: impl FnOnce(&mut QueryBuilder<'q, Postgres>),
+) -> QueryBuilder<'q, Postgres> {
+    let mut query = QueryBuilder::new("SELECT ");
+    select(&mut query);
+    query.push(" FROM users WHERE TRUE");
+    push_filter(&mut query, filter);
+
+    query
+}
+
+/// Append the `AND` conditions of a user filter to a query
+fn push_filter(query: &mut QueryBuilder<'_, Postgres>, filter: &UserFilter) {
+    if let Some(search) = &filter.search {
+        let pattern = like_pattern(search);
+        query
+            .push(" AND (email ILIKE ")
+            .push_bind(pattern.clone())
+            .push(" OR name ILIKE ")
+            .push_bind(pattern)
+            .push(")");
+    }
+    if let Some(active) = filter.active {
+        query.push(" AND is_active = ").push_bind(active);
+    }
+    if let Some(created_after) = filter.created_after {
+        query.push(" AND created_at >= ").push_bind(created_after);
+    }
+    if let Some(created_before) = filter.created_before {
+        query.push(" AND created_at < ").push_bind(created_before);
+    }
+    if let Some(domain) = &filter.email_domain {
+        query
+            .push(" AND lower(split_part(email, '@', 2)) = lower(")
+            .push_bind(domain.clone())
+            .push(")");
+    }
+}
+
+/// `tsquery` matching every word of `query` as a prefix, or `None` if it has
+/// no words. Only letters and digits are kept, so the result is always valid.
+fn prefix_tsquery(query: &str) -> Option<String> {
+    let words: Vec<String> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| format!("{word}:*"))
+        .collect();
+
+    (!words.is_empty()).then(|| words.join(" & "))
+}
+
+/// `LIKE`/`ILIKE` pattern matching `search` anywhere, with its wildcards escaped
+fn like_pattern(search: &str) -> String {
+    let escaped = search
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+
+    format!("%{escaped}%")
 }
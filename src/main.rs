@@ -7,9 +7,18 @@ mod common;
 mod config;
 mod domain;
 mod infrastructure;
+#[cfg(test)]
+mod test_utils;
+
+use std::{net::SocketAddr, time::Duration};
 
 use config::{AppConfig, AppState, DatabaseConfig};
+use domain::{
+    errors::DomainError,
+    services::{ExportService, LockoutService, SessionService, UserService},
+};
 use dotenvy::dotenv;
+use infrastructure::repositories::{TenantIsolation, tenant};
 use tower_http::cors::{Any, CorsLayer};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
@@ -30,8 +39,13 @@ async fn main() {
     // Load configuration
     let app_config = AppConfig::from_env().expect("Failed to load app configuration");
     let db_config = DatabaseConfig::from_env().expect("Failed to load database configuration");
-
-    tracing::info!("Starting server in {:?} mode", app_config.environment);
+    let jwt_keys = app_config.load_jwt_keys().expect("Failed to load JWT keys");
+    let breached_passwords = app_config
+        .load_breached_passwords()
+        .expect("Failed to load breached password list");
+    let mailer = app_config
+        .load_mailer()
+        .expect("Failed to set up mail transport");
 
     // Create database connection pool
     let db_pool = db_config
@@ -41,15 +55,89 @@ async fn main() {
 
     tracing::info!("Database connection established");
 
+    // Create application state
+    let state = AppState::new(
+        db_pool,
+        app_config.clone(),
+        jwt_keys,
+        breached_passwords,
+        mailer,
+    );
+
+    // One-off maintenance commands run before migrations, which may depend on them
+    if std::env::args().nth(1).as_deref() == Some("report-duplicate-emails") {
+        let found = report_duplicate_emails(&state).await;
+        std::process::exit(i32::from(found));
+    }
+
+    tracing::info!("Starting server in {:?} mode", app_config.environment);
+
     // Run migrations
     sqlx::migrate!()
-        .run(&db_pool)
+        .run(&state.db_pool)
         .await
         .expect("Failed to run migrations");
     tracing::info!("Migrations ran successfully");
 
-    // Create application state
-    let state = AppState::new(db_pool, app_config.clone());
+    // Accounts stored before canonicalization was turned on must be found
+    // under their canonical address
+    if app_config.email_canonicalize_providers {
+        let (rewritten, conflicting) = UserService::new(&state)
+            .canonicalize_stored_emails()
+            .await
+            .expect("Failed to canonicalize stored emails");
+        if rewritten > 0 {
+            tracing::info!("Canonicalized {} stored email addresses", rewritten);
+        }
+        if conflicting > 0 {
+            tracing::warn!(
+                "{} accounts share a canonical email address with another account and \
+                 cannot log in with it; run `report-duplicate-emails` to list them",
+                conflicting
+            );
+        }
+    }
+
+    if app_config.tenant_isolation == TenantIsolation::RowLevelSecurity {
+        let available = tenant::row_level_security_available(&state.db_pool)
+            .await
+            .expect("Failed to check row-level security setup");
+        assert!(
+            available,
+            "TENANT_ISOLATION=row_level_security needs the app_tenant role granted to the \
+             database user; see the tenant_row_level_security migration"
+        );
+    }
+
+    // Periodically purge data that is no longer needed
+    let hourly = Duration::from_secs(3600);
+    spawn_periodic(
+        "expired token revocations",
+        hourly,
+        state.clone(),
+        |state| async move { SessionService::new(&state).purge_expired().await },
+    );
+    spawn_periodic(
+        "deleted accounts",
+        hourly,
+        state.clone(),
+        |state| async move {
+            let purged = UserService::new(&state).purge_deleted().await?;
+            Ok(purged as u64)
+        },
+    );
+    spawn_periodic(
+        "expired data exports",
+        hourly,
+        state.clone(),
+        |state| async move { ExportService::new(&state).purge_expired().await },
+    );
+    spawn_periodic(
+        "stale login lockouts",
+        hourly,
+        state.clone(),
+        |state| async move { LockoutService::new(&state).purge_stale().await },
+    );
 
     // Configure CORS
     let cors = CorsLayer::new()
@@ -68,5 +156,60 @@ async fn main() {
 
     tracing::info!("🚀 Server listening on http://{}", addr);
 
-    axum::serve(listener, app).await.expect("Server error");
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .expect("Server error");
+}
+
+/// Run a purge task in the background every `interval`, logging what it
+/// removed under `name`
+fn spawn_periodic<F, Fut>(name: &'static str, interval: Duration, state: AppState, task: F)
+where
+    F: Fn(AppState) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<u64, DomainError>> + Send,
+{
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(interval);
+
+        loop {
+            interval.tick().await;
+
+            match task(state.clone()).await {
+                Ok(0) => {}
+                Ok(count) => tracing::info!("Purged {} {}", count, name),
+                Err(err) => tracing::error!("Failed to purge {}: {}", name, err),
+            }
+        }
+    });
+}
+
+/// Print accounts sharing an email address under the current normalization
+/// rules. Returns whether any were found.
+async fn report_duplicate_emails(state: &AppState) -> bool {
+    let duplicates = UserService::new(state)
+        .duplicate_emails()
+        .await
+        .expect("Failed to read user emails");
+
+    if duplicates.is_empty() {
+        println!("No accounts share an email address");
+        return false;
+    }
+
+    println!(
+        "{} email addresses belong to more than one account:",
+        duplicates.len()
+    );
+    for duplicate in duplicates {
+        println!("\n{}", duplicate.email);
+        for (id, email, created_at) in duplicate.accounts {
+            println!("  {id}  {email}  created {}", created_at.to_rfc3339());
+        }
+    }
+    println!("\nMerge or rename these accounts so each address belongs to one account.");
+
+    true
 }